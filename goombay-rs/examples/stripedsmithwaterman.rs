@@ -0,0 +1,37 @@
+use goombay_rs::align::{LocalAlignmentMatrix, SmithWaterman, StripedSmithWaterman};
+use goombay_rs::scoring::GeneralScoring;
+
+fn main() {
+    // Query scanned against a small database of subjects
+    let query = "TGTTACGGAAAAAAAAAAGTGAC";
+    let database = ["GGTTGACTA", "AAAAAAAAAAGT", "CCCCCCCC", "TTACGGAAAA"];
+
+    println!("Default Scoring");
+    let ssw = StripedSmithWaterman::default();
+    println!("Engine: {:?}", ssw.engine);
+    // Build the query profile once and reuse it for every subject
+    let mut profile = ssw.profile(query);
+    for subject in database {
+        let score = profile.score(subject);
+        println!("{subject}: {score}");
+    }
+
+    println!("Custom Scoring with alignment of the best hit");
+    let scores = GeneralScoring {
+        identity: 5,
+        mismatch: 3,
+        gap: 2,
+    };
+    let ssw_custom = StripedSmithWaterman::set_scores(&scores);
+    let mut profile = ssw_custom.profile(query);
+    let best = database
+        .iter()
+        .max_by_key(|subject| profile.score(subject))
+        .unwrap();
+
+    // The striped engine only scores, so recover the alignment with Smith-Waterman
+    let sw = SmithWaterman::set_scores(&scores).calculate_matrix(query, best);
+    println!("Best hit: {best}");
+    println!("{}", sw.align()[0]);
+    println!("Similarity: {}", sw.similarity());
+}
@@ -0,0 +1,105 @@
+use crate::align::global_base::GlobalAlignmentModel;
+use crate::align::{GlobalAlignmentMatrix, Scoring};
+use spindalis::utils::Arr2D;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Which global alignment score fills the matrix
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PairwiseMetric {
+    Distance,
+    NormalizedDistance,
+}
+
+impl PairwiseMetric {
+    fn score(&self, model: &GlobalAlignmentModel) -> f64 {
+        match self {
+            PairwiseMetric::Distance => model.distance() as f64,
+            PairwiseMetric::NormalizedDistance => model.normalized_distance(),
+        }
+    }
+}
+
+// Position of the pair (i, j), i != j, in a condensed upper-triangle vector of
+// `n` sequences. Pairs are stored row by row: (0, 1), (0, 2), ..., (1, 2), ...
+pub fn condensed_index(n: usize, i: usize, j: usize) -> usize {
+    let (i, j) = if i < j { (i, j) } else { (j, i) };
+    assert!(
+        j < n && i != j,
+        "pair ({i}, {j}) out of range for {n} sequences"
+    );
+    n * i - i * (i + 1) / 2 + (j - i - 1)
+}
+
+// Aligns every unordered pair once and returns the upper triangle of the
+// distance matrix in `condensed_index` order. With the `parallel` feature the
+// pairs are spread across the rayon thread pool.
+pub fn condensed_distances<A, S, Q>(
+    aligner: &A,
+    sequences: &[Q],
+    metric: PairwiseMetric,
+) -> Vec<f64>
+where
+    A: GlobalAlignmentMatrix<S> + Sync,
+    S: Scoring + Clone,
+    Q: AsRef<str> + Sync,
+{
+    condensed_map(aligner, sequences, |model| metric.score(model))
+}
+
+// Aligns every unordered pair once and maps each alignment to a value, in
+// `condensed_index` order
+pub(crate) fn condensed_map<A, S, Q, T, F>(aligner: &A, sequences: &[Q], map: F) -> Vec<T>
+where
+    A: GlobalAlignmentMatrix<S> + Sync,
+    S: Scoring + Clone,
+    Q: AsRef<str> + Sync,
+    T: Send,
+    F: Fn(&GlobalAlignmentModel) -> T + Sync,
+{
+    let n = sequences.len();
+    let pairs: Vec<(usize, usize)> = (0..n)
+        .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+        .collect();
+    let pair_value = |&(i, j): &(usize, usize)| {
+        let model = aligner.calculate_matrix(sequences[i].as_ref(), sequences[j].as_ref());
+        map(&model)
+    };
+
+    #[cfg(feature = "parallel")]
+    let values = pairs.par_iter().map(pair_value).collect();
+    #[cfg(not(feature = "parallel"))]
+    let values = pairs.iter().map(pair_value).collect();
+    values
+}
+
+// Dense symmetric n x n matrix with a zero diagonal
+pub fn distance_matrix<A, S, Q>(aligner: &A, sequences: &[Q], metric: PairwiseMetric) -> Arr2D<f64>
+where
+    A: GlobalAlignmentMatrix<S> + Sync,
+    S: Scoring + Clone,
+    Q: AsRef<str> + Sync,
+{
+    let n = sequences.len();
+    squareform(&condensed_distances(aligner, sequences, metric), n)
+}
+
+// Expands a condensed upper triangle back into a dense symmetric matrix
+pub fn squareform(condensed: &[f64], n: usize) -> Arr2D<f64> {
+    assert_eq!(
+        condensed.len(),
+        n * n.saturating_sub(1) / 2,
+        "condensed vector does not match {n} sequences"
+    );
+    let mut matrix = Arr2D::full(0.0, n, n);
+    let mut k = 0;
+    for i in 0..n {
+        for j in i + 1..n {
+            matrix[i][j] = condensed[k];
+            matrix[j][i] = condensed[k];
+            k += 1;
+        }
+    }
+    matrix
+}
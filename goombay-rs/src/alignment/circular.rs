@@ -0,0 +1,199 @@
+use crate::align::edit::needleman_wunsch::NeedlemanWunsch;
+use crate::align::global_base::GlobalAlignmentModel;
+use crate::align::scoring::GeneralScoring;
+use crate::align::{GlobalAlignmentMatrix, Scoring};
+
+// Rotates a sequence left by `offset` chars, so position `offset` comes
+// first. Offsets wrap around the sequence length.
+pub fn rotate(sequence: &str, offset: usize) -> String {
+    let chars: Vec<char> = sequence.chars().collect();
+    if chars.is_empty() {
+        return String::new();
+    }
+    let offset = offset % chars.len();
+    chars[offset..].iter().chain(&chars[..offset]).collect()
+}
+
+// Global alignment against a circular subject. `offset` is the position of
+// the subject the alignment starts at, and `model` is the `NeedlemanWunsch`
+// alignment of the query against `rotate(subject, offset)`.
+pub struct CircularAlignment {
+    pub offset: usize,
+    pub score: i32,
+    pub model: GlobalAlignmentModel,
+}
+
+// Global alignment for sequences without a fixed origin, such as plasmids and
+// mitochondrial genomes. Every rotation of the subject is a path through the
+// grid of the query against the subject written out twice, from (0, offset)
+// to (m, offset + n). Optimal paths of different rotations can be chosen not
+// to cross (Maes 1990), so after the path for offset 0 the offsets are split
+// in halves and each is searched only between the paths bounding it. That
+// finds the best rotation exactly in O(mn log n) time. Ties go to the
+// smallest offset.
+pub struct CircularAligner<S: Scoring + Clone> {
+    pub scores: S,
+}
+
+impl Default for CircularAligner<GeneralScoring> {
+    fn default() -> Self {
+        let scores = GeneralScoring {
+            identity: 2,
+            mismatch: 1,
+            gap: 2,
+        };
+        Self { scores }
+    }
+}
+
+impl<S: Scoring + Clone> CircularAligner<S>
+where
+    NeedlemanWunsch<S>: GlobalAlignmentMatrix<S>,
+{
+    pub fn set_scores(scores: &S) -> Self {
+        Self {
+            scores: scores.clone(),
+        }
+    }
+
+    pub fn calculate_matrix(&self, query: &str, subject: &str) -> CircularAlignment {
+        let nw = NeedlemanWunsch::set_scores(&self.scores);
+        let query_chars: Vec<char> = query.to_uppercase().chars().collect();
+        let subject_chars: Vec<char> = subject.to_uppercase().chars().collect();
+        let n = subject_chars.len();
+
+        let mut offset = 0;
+        if n > 1 && !query_chars.is_empty() {
+            let doubled: Vec<char> = subject_chars
+                .iter()
+                .chain(&subject_chars)
+                .copied()
+                .collect();
+            let search = RotationSearch {
+                scores: &self.scores,
+                query: &query_chars,
+                doubled: &doubled,
+                n,
+            };
+            offset = search.best_offset();
+        }
+
+        let model = nw.calculate_matrix(query, &rotate(subject, offset));
+        CircularAlignment {
+            offset,
+            score: model.similarity(),
+            model,
+        }
+    }
+}
+
+// First and last column a path visits in each row of the grid
+type Span = Vec<(usize, usize)>;
+
+// Maes' divide and conquer over the rotations of the subject
+struct RotationSearch<'a, S: Scoring> {
+    scores: &'a S,
+    query: &'a [char],
+    doubled: &'a [char],
+    n: usize,
+}
+
+impl<S: Scoring> RotationSearch<'_, S> {
+    fn best_offset(&self) -> usize {
+        let n = self.n;
+        let open: Span = vec![(0, 2 * n); self.query.len() + 1];
+        let mut scores = vec![0; n];
+        let (score, first) = self.path(0, &open, &open);
+        scores[0] = score;
+        // Offset n is offset 0 again, one turn further along
+        let last: Span = first.iter().map(|&(from, to)| (from + n, to + n)).collect();
+        self.divide(0, n, &first, &last, &mut scores);
+
+        let best = scores.iter().copied().max().unwrap();
+        scores.iter().position(|&score| score == best).unwrap()
+    }
+
+    // Scores every offset strictly between `low` and `high`, whose paths
+    // are `lower` and `upper`
+    fn divide(&self, low: usize, high: usize, lower: &Span, upper: &Span, scores: &mut [i32]) {
+        if high - low < 2 {
+            return;
+        }
+        let middle = (low + high) / 2;
+        let (score, path) = self.path(middle, lower, upper);
+        scores[middle] = score;
+        self.divide(low, middle, lower, &path, scores);
+        self.divide(middle, high, &path, upper, scores);
+    }
+
+    // Best path for `offset` that keeps right of `lower` and left of
+    // `upper`, with the same recurrence as `NeedlemanWunsch`
+    fn path(&self, offset: usize, lower: &Span, upper: &Span) -> (i32, Span) {
+        let gap = self.scores.get_gap_score() as i32;
+        let m = self.query.len();
+        let bands: Vec<(usize, usize)> = (0..=m)
+            .map(|i| (lower[i].0.max(offset), upper[i].1.min(offset + self.n)))
+            .collect();
+        let pair = |i: usize, j: usize| {
+            self.scores
+                .pair_score(self.query[i - 1], self.doubled[j - 1])
+        };
+        let cell = |rows: &[Vec<Option<i32>>], i: usize, j: usize| {
+            let (from, to) = bands[i];
+            if j < from || j > to {
+                None
+            } else {
+                rows[i][j - from]
+            }
+        };
+
+        // Scores within each row's band; unreachable cells are None
+        let mut rows: Vec<Vec<Option<i32>>> = Vec::with_capacity(m + 1);
+        for (i, &(from, to)) in bands.iter().enumerate() {
+            rows.push(vec![None; to + 1 - from.min(to + 1)]);
+            for j in from..=to {
+                let value = if i == 0 && j == offset {
+                    Some(0)
+                } else {
+                    let identity = (i > 0 && j > 0)
+                        .then(|| cell(&rows, i - 1, j - 1))
+                        .flatten()
+                        .map(|score| score + pair(i, j));
+                    let ugap = (i > 0)
+                        .then(|| cell(&rows, i - 1, j))
+                        .flatten()
+                        .map(|score| score - gap);
+                    let lgap = (j > from)
+                        .then(|| rows[i][j - 1 - from])
+                        .flatten()
+                        .map(|score| score - gap);
+                    [identity, ugap, lgap].into_iter().flatten().max()
+                };
+                rows[i][j - from] = value;
+            }
+        }
+
+        // Trace back from the end, preferring match, then up, then left
+        let mut span: Span = vec![(usize::MAX, 0); m + 1];
+        let (mut i, mut j) = (m, offset + self.n);
+        let score = cell(&rows, i, j).unwrap();
+        loop {
+            span[i] = (span[i].0.min(j), span[i].1.max(j));
+            if i == 0 && j == offset {
+                break;
+            }
+            let here = cell(&rows, i, j).unwrap();
+            if i > 0
+                && j > 0
+                && cell(&rows, i - 1, j - 1).is_some_and(|score| score + pair(i, j) == here)
+            {
+                (i, j) = (i - 1, j - 1);
+            } else if i > 0 && cell(&rows, i - 1, j).is_some_and(|score| score - gap == here) {
+                i -= 1;
+            } else {
+                j -= 1;
+            }
+        }
+        (score, span)
+    }
+}
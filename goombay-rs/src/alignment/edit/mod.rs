@@ -1,3 +1,6 @@
 pub mod needleman_wunsch;
 pub mod smith_waterman;
+pub mod striped_smith_waterman;
+pub mod translated;
 pub mod wagner_fischer;
+pub mod x_drop;
@@ -1,6 +1,9 @@
 use crate::align::global_base::{GlobalAlgorithm, GlobalAlignmentModel, Metric};
-use crate::align::scoring::GeneralScoring;
+use crate::align::scoring::{GeneralScoring, NucleotideScoring};
+use crate::align::wavefront::{SharedGrid, wavefront};
 use crate::align::{AlignmentData, GlobalAlignmentMatrix, PointerValues, Scoring};
+use spindalis::utils::Arr2D;
+use std::ops::IndexMut;
 
 pub struct NeedlemanWunsch<S: Scoring + Clone> {
     pub scores: S,
@@ -33,57 +36,182 @@ impl GlobalAlignmentMatrix<GeneralScoring> for NeedlemanWunsch<GeneralScoring> {
 
     fn calculate_matrix(&self, query: &str, subject: &str) -> GlobalAlignmentModel {
         let mut alignments = AlignmentData::new(query, subject);
-        let query_len = alignments.query.len() + 1;
-        let subject_len = alignments.subject.len() + 1;
-        let score_matrix = &mut alignments.score_matrix[0];
-        let pointer_matrix = &mut alignments.pointer_matrix[0];
+        self.fill_matrices(
+            &alignments.query,
+            &alignments.subject,
+            &mut alignments.score_matrix[0],
+            &mut alignments.pointer_matrix[0],
+        );
+        self.model(alignments)
+    }
+}
+
+impl GlobalAlignmentMatrix<NucleotideScoring> for NeedlemanWunsch<NucleotideScoring> {
+    fn compute(query: &str, subject: &str) -> GlobalAlignmentModel {
+        let nw_default = NeedlemanWunsch::set_scores(&NucleotideScoring::default());
+        nw_default.calculate_matrix(query, subject)
+    }
+
+    fn set_scores(scores: &NucleotideScoring) -> Self {
+        // Set custom scores before manually calculating matrices
+        Self {
+            scores: scores.clone(),
+        }
+    }
+
+    fn calculate_matrix(&self, query: &str, subject: &str) -> GlobalAlignmentModel {
+        let mut alignments = AlignmentData::new(query, subject);
+        self.fill_matrices(
+            &alignments.query,
+            &alignments.subject,
+            &mut alignments.score_matrix[0],
+            &mut alignments.pointer_matrix[0],
+        );
+        self.model(alignments)
+    }
+}
 
+// Keeps `NeedlemanWunsch::compute` unambiguous now that other scorings also
+// implement `GlobalAlignmentMatrix`: inherent functions are resolved first
+impl NeedlemanWunsch<GeneralScoring> {
+    pub fn compute(query: &str, subject: &str) -> GlobalAlignmentModel {
+        <Self as GlobalAlignmentMatrix<GeneralScoring>>::compute(query, subject)
+    }
+}
+
+impl<S: Scoring + Clone> NeedlemanWunsch<S> {
+    // Fills the matrices in square tiles, one anti-diagonal of tiles at a
+    // time. With the `parallel` feature each anti-diagonal runs across threads;
+    // without it the tiles run one after another on the calling thread, which
+    // is no faster than `calculate_matrix`. The matrices are identical to
+    // those of `calculate_matrix`.
+    pub fn calculate_matrix_parallel(
+        &self,
+        query: &str,
+        subject: &str,
+        tile_size: usize,
+    ) -> GlobalAlignmentModel
+    where
+        S: Sync,
+    {
+        let query: Vec<char> = query.to_uppercase().chars().collect();
+        let subject: Vec<char> = subject.to_uppercase().chars().collect();
+        let mut score_matrix = Arr2D::full(0, query.len() + 1, subject.len() + 1);
+        let mut pointer_matrix = Arr2D::full(0, query.len() + 1, subject.len() + 1);
+        self.init_borders(&query, &subject, &mut score_matrix, &mut pointer_matrix);
+
+        let scores = SharedGrid::new(&mut score_matrix);
+        let pointers = SharedGrid::new(&mut pointer_matrix);
+        wavefront(
+            query.len() + 1,
+            subject.len() + 1,
+            tile_size,
+            |rows, cols| {
+                for i in rows {
+                    for j in cols.clone() {
+                        // Safety: the wavefront only runs a tile once the tiles it
+                        // reads from are finished, and no two tiles share a cell
+                        unsafe {
+                            let (score, pointer) = self.cell(
+                                query[i - 1],
+                                subject[j - 1],
+                                scores.get(i - 1, j - 1),
+                                scores.get(i - 1, j),
+                                scores.get(i, j - 1),
+                            );
+                            scores.set(i, j, score);
+                            pointers.set(i, j, pointer);
+                        }
+                    }
+                }
+            },
+        );
+
+        self.model(AlignmentData {
+            query,
+            subject,
+            score_matrix: vec![score_matrix],
+            pointer_matrix: vec![pointer_matrix],
+        })
+    }
+
+    // Fills zero-initialised score and pointer matrices of shape
+    // (query.len() + 1, subject.len() + 1). Shared by `calculate_matrix` and
+    // the reusable `Aligner` workspace.
+    pub(crate) fn fill_matrices<M: IndexMut<usize, Output = [i32]>>(
+        &self,
+        query: &[char],
+        subject: &[char],
+        score_matrix: &mut M,
+        pointer_matrix: &mut M,
+    ) {
+        self.init_borders(query, subject, score_matrix, pointer_matrix);
+
+        // Build pointer and score matrix
+        for i in 1..query.len() + 1 {
+            for j in 1..subject.len() + 1 {
+                let (score, pointer) = self.cell(
+                    query[i - 1],
+                    subject[j - 1],
+                    score_matrix[i - 1][j - 1],
+                    score_matrix[i - 1][j],
+                    score_matrix[i][j - 1],
+                );
+                score_matrix[i][j] = score;
+                pointer_matrix[i][j] = pointer;
+            }
+        }
+    }
+
+    fn init_borders<M: IndexMut<usize, Output = [i32]>>(
+        &self,
+        query: &[char],
+        subject: &[char],
+        score_matrix: &mut M,
+        pointer_matrix: &mut M,
+    ) {
         // initialise score and pointer matrices
         pointer_matrix[0][0] = PointerValues::Left as i32;
-        for i in 1..query_len {
-            score_matrix[i][0] = -(i as i32 * self.scores.gap as i32);
+        for i in 1..query.len() + 1 {
+            score_matrix[i][0] = -(i as i32 * self.scores.get_gap_score() as i32);
             pointer_matrix[i][0] = PointerValues::Up as i32;
         }
-        for j in 1..subject_len {
-            score_matrix[0][j] = -(j as i32 * self.scores.gap as i32);
+        for j in 1..subject.len() + 1 {
+            score_matrix[0][j] = -(j as i32 * self.scores.get_gap_score() as i32);
             pointer_matrix[0][j] = PointerValues::Left as i32;
         }
+    }
 
-        // Build pointer and score matrix
-        for i in 1..query_len {
-            for j in 1..subject_len {
-                let identity = {
-                    if alignments.query[i - 1] == alignments.subject[j - 1] {
-                        score_matrix[i - 1][j - 1] + self.scores.identity as i32
-                    } else {
-                        score_matrix[i - 1][j - 1] - self.scores.mismatch as i32
-                    }
-                };
-                let ugap = score_matrix[i - 1][j] - self.scores.gap as i32;
-                let lgap = score_matrix[i][j - 1] - self.scores.gap as i32;
-
-                let tmax = [identity, ugap, lgap].iter().max().copied().unwrap();
-                score_matrix[i][j] = tmax;
+    // Score and pointer of one cell from its diagonal, upper and left neighbours
+    #[inline(always)]
+    fn cell(&self, query: char, subject: char, diag: i32, up: i32, left: i32) -> (i32, i32) {
+        let identity = diag + self.scores.pair_score(query, subject);
+        let gap = self.scores.get_gap_score() as i32;
+        let ugap = up - gap;
+        let lgap = left - gap;
 
-                if tmax == identity {
-                    pointer_matrix[i][j] += PointerValues::Match as i32;
-                }
-                if tmax == ugap {
-                    pointer_matrix[i][j] += PointerValues::Up as i32;
-                }
-                if tmax == lgap {
-                    pointer_matrix[i][j] += PointerValues::Left as i32;
-                }
-            }
+        let tmax = [identity, ugap, lgap].iter().max().copied().unwrap();
+        let mut pointer = 0;
+        if tmax == identity {
+            pointer += PointerValues::Match as i32;
+        }
+        if tmax == ugap {
+            pointer += PointerValues::Up as i32;
         }
+        if tmax == lgap {
+            pointer += PointerValues::Left as i32;
+        }
+        (tmax, pointer)
+    }
 
+    fn model(&self, alignments: AlignmentData) -> GlobalAlignmentModel {
         GlobalAlignmentModel {
             data: alignments,
             aligner: GlobalAlgorithm::NeedlemanWunsch,
             metric: Metric::Similarity,
-            identity: self.scores.identity,
-            mismatch: self.scores.mismatch,
-            gap: self.scores.gap,
+            identity: self.scores.get_match_score(),
+            mismatch: self.scores.get_mismatch_score(),
+            gap: self.scores.get_gap_score(),
             all_alignments: false,
         }
     }
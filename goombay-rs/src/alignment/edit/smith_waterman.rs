@@ -1,6 +1,9 @@
 use crate::align::local_base::{LocalAlgorithm, LocalAlignmentModel, LocalMetric};
-use crate::align::scoring::GeneralScoring;
+use crate::align::scoring::{GeneralScoring, NucleotideScoring};
+use crate::align::wavefront::{SharedGrid, wavefront};
 use crate::align::{AlignmentData, LocalAlignmentMatrix, PointerValues, Scoring};
+use spindalis::utils::Arr2D;
+use std::ops::IndexMut;
 
 pub struct SmithWaterman<S: Scoring + Clone> {
     pub scores: S,
@@ -31,80 +34,218 @@ impl LocalAlignmentMatrix<GeneralScoring> for SmithWaterman<GeneralScoring> {
 
     fn calculate_matrix(&self, query: &str, subject: &str) -> LocalAlignmentModel {
         let mut alignments = AlignmentData::new(query, subject);
-        let query_len = alignments.query.len() + 1;
-        let subject_len = alignments.subject.len() + 1;
-        let score_matrix = &mut alignments.score_matrix[0];
-        let pointer_matrix = &mut alignments.pointer_matrix[0];
+        let mut start_indices = Vec::new();
+        let max_score = self.fill_matrices(
+            &alignments.query,
+            &alignments.subject,
+            &mut alignments.score_matrix[0],
+            &mut alignments.pointer_matrix[0],
+            &mut start_indices,
+        );
+        self.model(alignments, max_score, start_indices)
+    }
+}
 
-        let mut max_score = 0;
+impl LocalAlignmentMatrix<NucleotideScoring> for SmithWaterman<NucleotideScoring> {
+    fn compute(query: &str, subject: &str) -> LocalAlignmentModel {
+        let sw_default = SmithWaterman::set_scores(&NucleotideScoring::default());
+        sw_default.calculate_matrix(query, subject)
+    }
+
+    fn set_scores(scores: &NucleotideScoring) -> Self {
+        Self {
+            scores: scores.clone(),
+        }
+    }
+
+    fn calculate_matrix(&self, query: &str, subject: &str) -> LocalAlignmentModel {
+        let mut alignments = AlignmentData::new(query, subject);
         let mut start_indices = Vec::new();
+        let max_score = self.fill_matrices(
+            &alignments.query,
+            &alignments.subject,
+            &mut alignments.score_matrix[0],
+            &mut alignments.pointer_matrix[0],
+            &mut start_indices,
+        );
+        self.model(alignments, max_score, start_indices)
+    }
+}
 
-        // initialise score and pointer matrices (first row and column are 0 for SW)
-        // AlignmentData::new already initialises to 0, so we just set pointers if needed.
-        // For local alignment, we don't strictly need to set pointers for the first row/col as we stop at 0.
+// Same shim as for `NeedlemanWunsch::compute`
+impl SmithWaterman<GeneralScoring> {
+    pub fn compute(query: &str, subject: &str) -> LocalAlignmentModel {
+        <Self as LocalAlignmentMatrix<GeneralScoring>>::compute(query, subject)
+    }
+}
 
-        // Build pointer and score matrix
-        for i in 1..query_len {
-            for j in 1..subject_len {
-                let identity = {
-                    if alignments.query[i - 1] == alignments.subject[j - 1] {
-                        score_matrix[i - 1][j - 1] + self.scores.identity as i32
-                    } else {
-                        score_matrix[i - 1][j - 1] - self.scores.mismatch as i32
+impl<S: Scoring + Clone> SmithWaterman<S> {
+    // Fills the matrices in square tiles, one anti-diagonal of tiles at a
+    // time. With the `parallel` feature each anti-diagonal runs across threads;
+    // without it the tiles run one after another on the calling thread, which
+    // is no faster than `calculate_matrix`. The matrices and start indices are
+    // identical to those of `calculate_matrix`.
+    pub fn calculate_matrix_parallel(
+        &self,
+        query: &str,
+        subject: &str,
+        tile_size: usize,
+    ) -> LocalAlignmentModel
+    where
+        S: Sync,
+    {
+        let query: Vec<char> = query.to_uppercase().chars().collect();
+        let subject: Vec<char> = subject.to_uppercase().chars().collect();
+        let mut score_matrix = Arr2D::full(0, query.len() + 1, subject.len() + 1);
+        let mut pointer_matrix = Arr2D::full(0, query.len() + 1, subject.len() + 1);
+
+        let scores = SharedGrid::new(&mut score_matrix);
+        let pointers = SharedGrid::new(&mut pointer_matrix);
+        let tile_maxima = wavefront(
+            query.len() + 1,
+            subject.len() + 1,
+            tile_size,
+            |rows, cols| {
+                let mut max_score = 0;
+                let mut start_indices = Vec::new();
+                for i in rows {
+                    for j in cols.clone() {
+                        // Safety: the wavefront only runs a tile once the tiles it
+                        // reads from are finished, and no two tiles share a cell
+                        let score = unsafe {
+                            let (score, pointer) = self.cell(
+                                query[i - 1],
+                                subject[j - 1],
+                                scores.get(i - 1, j - 1),
+                                scores.get(i - 1, j),
+                                scores.get(i, j - 1),
+                            );
+                            scores.set(i, j, score);
+                            pointers.set(i, j, pointer);
+                            score
+                        };
+                        track_max(&mut max_score, &mut start_indices, score, (i, j));
                     }
-                };
-                let ugap = score_matrix[i - 1][j] - self.scores.gap as i32;
-                let lgap = score_matrix[i][j - 1] - self.scores.gap as i32;
-
-                // Smith-Waterman: score_matrix[i][j] = max(0, identity, ugap, lgap)
-                let mut current_max = 0;
-                let mut choices = Vec::new();
-
-                if identity > current_max {
-                    current_max = identity;
-                    choices = vec![PointerValues::Match as i32];
-                } else if identity == current_max && current_max > 0 {
-                    choices.push(PointerValues::Match as i32);
                 }
+                (max_score, start_indices)
+            },
+        );
 
-                if ugap > current_max {
-                    current_max = ugap;
-                    choices = vec![PointerValues::Up as i32];
-                } else if ugap == current_max && current_max > 0 {
-                    choices.push(PointerValues::Up as i32);
-                }
+        // Merge the per-tile maxima back into the row-major order of the serial fill
+        let max_score = tile_maxima
+            .iter()
+            .map(|(score, _)| *score)
+            .max()
+            .unwrap_or(0);
+        let mut start_indices: Vec<(usize, usize)> = tile_maxima
+            .into_iter()
+            .filter(|(score, _)| *score == max_score && max_score > 0)
+            .flat_map(|(_, indices)| indices)
+            .collect();
+        start_indices.sort_unstable();
 
-                if lgap > current_max {
-                    current_max = lgap;
-                    choices = vec![PointerValues::Left as i32];
-                } else if lgap == current_max && current_max > 0 {
-                    choices.push(PointerValues::Left as i32);
-                }
+        let alignments = AlignmentData {
+            query,
+            subject,
+            score_matrix: vec![score_matrix],
+            pointer_matrix: vec![pointer_matrix],
+        };
+        self.model(alignments, max_score, start_indices)
+    }
 
-                score_matrix[i][j] = current_max;
-                for choice in choices {
-                    pointer_matrix[i][j] += choice;
-                }
+    // Fills zero-initialised score and pointer matrices of shape
+    // (query.len() + 1, subject.len() + 1) and returns the best score.
+    // `start_indices` must be empty and receives every cell holding that score.
+    pub(crate) fn fill_matrices<M: IndexMut<usize, Output = [i32]>>(
+        &self,
+        query: &[char],
+        subject: &[char],
+        score_matrix: &mut M,
+        pointer_matrix: &mut M,
+        start_indices: &mut Vec<(usize, usize)>,
+    ) -> i32 {
+        let mut max_score = 0;
 
-                if current_max > max_score {
-                    max_score = current_max;
-                    start_indices = vec![(i, j)];
-                } else if current_max == max_score && max_score > 0 {
-                    start_indices.push((i, j));
-                }
+        // The first row and column stay 0 for SW, and we don't need pointers
+        // there because local traceback stops when the score reaches 0.
+
+        // Build pointer and score matrix
+        for i in 1..query.len() + 1 {
+            for j in 1..subject.len() + 1 {
+                let (score, pointer) = self.cell(
+                    query[i - 1],
+                    subject[j - 1],
+                    score_matrix[i - 1][j - 1],
+                    score_matrix[i - 1][j],
+                    score_matrix[i][j - 1],
+                );
+                score_matrix[i][j] = score;
+                pointer_matrix[i][j] = pointer;
+                track_max(&mut max_score, start_indices, score, (i, j));
             }
         }
+        max_score
+    }
+
+    // Score and pointer of one cell from its diagonal, upper and left neighbours
+    #[inline(always)]
+    fn cell(&self, query: char, subject: char, diag: i32, up: i32, left: i32) -> (i32, i32) {
+        let identity = diag + self.scores.pair_score(query, subject);
+        let gap = self.scores.get_gap_score() as i32;
+        let ugap = up - gap;
+        let lgap = left - gap;
 
+        // Smith-Waterman: score_matrix[i][j] = max(0, identity, ugap, lgap)
+        let current_max = [0, identity, ugap, lgap].iter().max().copied().unwrap();
+
+        // Cells at 0 end the alignment, so they get no pointer
+        let mut pointer = 0;
+        if current_max > 0 {
+            if identity == current_max {
+                pointer += PointerValues::Match as i32;
+            }
+            if ugap == current_max {
+                pointer += PointerValues::Up as i32;
+            }
+            if lgap == current_max {
+                pointer += PointerValues::Left as i32;
+            }
+        }
+        (current_max, pointer)
+    }
+
+    fn model(
+        &self,
+        alignments: AlignmentData,
+        max_score: i32,
+        start_indices: Vec<(usize, usize)>,
+    ) -> LocalAlignmentModel {
         LocalAlignmentModel {
             data: alignments,
             aligner: LocalAlgorithm::SmithWaterman,
             metric: LocalMetric::Similarity,
-            identity: self.scores.identity,
-            mismatch: self.scores.mismatch,
-            gap: self.scores.gap,
+            identity: self.scores.get_match_score(),
+            mismatch: self.scores.get_mismatch_score(),
+            gap: self.scores.get_gap_score(),
             all_alignments: false,
             max_score,
             start_indices,
         }
     }
 }
+
+// Keeps every cell that holds the best score seen so far
+fn track_max(
+    max_score: &mut i32,
+    start_indices: &mut Vec<(usize, usize)>,
+    score: i32,
+    cell: (usize, usize),
+) {
+    if score > *max_score {
+        *max_score = score;
+        start_indices.clear();
+        start_indices.push(cell);
+    } else if score == *max_score && *max_score > 0 {
+        start_indices.push(cell);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::align::Scoring;
+use crate::align::scoring::GeneralScoring;
+use crate::align::simd::{Lanes, PortableI16, PortableU8, SimdEngine};
+use std::collections::HashMap;
+
+#[cfg(target_arch = "x86_64")]
+use crate::align::simd::{Avx2I16, Avx2U8, Sse2I16, Sse2U8};
+
+// Score-only Smith-Waterman using Farrar's striped query profile.
+// No matrices are kept, so only the best local score is returned. Use
+// `SmithWaterman` on the hits that matter to recover the alignment itself.
+pub struct StripedSmithWaterman<S: Scoring + Clone> {
+    pub scores: S,
+    pub engine: SimdEngine,
+}
+
+impl Default for StripedSmithWaterman<GeneralScoring> {
+    fn default() -> Self {
+        let scores = GeneralScoring {
+            identity: 2,
+            mismatch: 1,
+            gap: 2,
+        };
+        Self {
+            scores,
+            engine: SimdEngine::detect(),
+        }
+    }
+}
+
+impl<S: Scoring + Clone> StripedSmithWaterman<S> {
+    pub fn set_scores(scores: &S) -> Self {
+        Self {
+            scores: scores.clone(),
+            engine: SimdEngine::detect(),
+        }
+    }
+
+    // Request a specific instruction set. Engines the CPU does not support
+    // are replaced with the best one that it does.
+    pub fn engine(&self, engine: SimdEngine) -> Self {
+        let engine = if engine.is_supported() {
+            engine
+        } else {
+            SimdEngine::detect()
+        };
+        Self {
+            scores: self.scores.clone(),
+            engine,
+        }
+    }
+
+    pub fn profile(&self, query: &str) -> StripedProfile<S> {
+        StripedProfile {
+            query: query.to_uppercase().chars().collect(),
+            scores: self.scores.clone(),
+            engine: self.engine,
+            byte_rows: HashMap::new(),
+            word_rows: HashMap::new(),
+        }
+    }
+
+    pub fn score(&self, query: &str, subject: &str) -> i32 {
+        self.profile(query).score(subject)
+    }
+}
+
+// The query preprocessed into striped score rows, one per subject residue.
+// Build it once and reuse it to scan many subjects. Rows are built lazily the
+// first time a residue is seen in a subject.
+pub struct StripedProfile<S: Scoring + Clone> {
+    query: Vec<char>,
+    scores: S,
+    engine: SimdEngine,
+    byte_rows: HashMap<char, Vec<u8>>,
+    word_rows: HashMap<char, Vec<i16>>,
+}
+
+impl<S: Scoring + Clone> StripedProfile<S> {
+    pub fn query(&self) -> &[char] {
+        &self.query
+    }
+
+    pub fn engine(&self) -> SimdEngine {
+        self.engine
+    }
+
+    pub fn score(&mut self, subject: &str) -> i32 {
+        let subject: Vec<char> = subject.to_uppercase().chars().collect();
+        self.score_chars(&subject)
+    }
+
+    // Same as `score` for a subject that has already been upper-cased
+    pub fn score_chars(&mut self, subject: &[char]) -> i32 {
+        if self.query.is_empty() || subject.is_empty() {
+            return 0;
+        }
+        // Try 8-bit lanes first, widen to 16 bits and then to scalar i32
+        // whenever the narrower lanes saturate.
+        if let Some(score) = self.score_bytes(subject) {
+            return score;
+        }
+        if let Some(score) = self.score_words(subject) {
+            return score;
+        }
+        scalar_score(&self.query, subject, &self.scores)
+    }
+
+    fn score_bytes(&mut self, subject: &[char]) -> Option<i32> {
+        let identity = self.scores.get_match_score();
+        let bias = self.scores.get_mismatch_score();
+        let gap = self.scores.get_gap_score();
+        // Scores are stored as `score + bias` so that they fit in unsigned lanes
+        if identity + bias >= u8::MAX as usize || gap > u8::MAX as usize {
+            return None;
+        }
+
+        let lanes = self.engine.byte_lanes();
+        let seg_len = self.query.len().div_ceil(lanes);
+        for &residue in subject {
+            if self.byte_rows.contains_key(&residue) {
+                continue;
+            }
+            // Scorings may go beyond the match and mismatch scores, e.g. for N
+            let fits = self.query.iter().all(|&q| {
+                let value = self.scores.pair_score(q, residue) + bias as i32;
+                (0..=u8::MAX as i32).contains(&value)
+            });
+            if !fits {
+                return None;
+            }
+            let row = striped_row(&self.query, lanes, 0, |q| {
+                (self.scores.pair_score(q, residue) + bias as i32) as u8
+            });
+            self.byte_rows.insert(residue, row);
+        }
+        let rows: Vec<&[u8]> = subject
+            .iter()
+            .map(|c| self.byte_rows[c].as_slice())
+            .collect();
+
+        let (bias, gap) = (bias as u8, gap as u8);
+        let max_score = match self.engine {
+            #[cfg(target_arch = "x86_64")]
+            SimdEngine::Avx2 => unsafe { avx2_kernel::<Avx2U8>(&rows, seg_len, bias, gap) },
+            #[cfg(target_arch = "x86_64")]
+            SimdEngine::Sse2 => unsafe { striped_kernel::<Sse2U8>(&rows, seg_len, bias, gap) },
+            _ => unsafe { striped_kernel::<PortableU8>(&rows, seg_len, bias, gap) },
+        };
+
+        if max_score >= (u8::MAX - bias) as i32 {
+            return None;
+        }
+        Some(max_score)
+    }
+
+    fn score_words(&mut self, subject: &[char]) -> Option<i32> {
+        let limit = i16::MAX as i32;
+        let mismatch = self.scores.get_mismatch_score();
+        let gap = self.scores.get_gap_score();
+        if mismatch > limit as usize || gap > limit as usize {
+            return None;
+        }
+
+        let lanes = self.engine.byte_lanes() / 2;
+        let seg_len = self.query.len().div_ceil(lanes);
+        let pad = -(mismatch as i16);
+        for &residue in subject {
+            if self.word_rows.contains_key(&residue) {
+                continue;
+            }
+            let fits = self.query.iter().all(|&q| {
+                let value = self.scores.pair_score(q, residue);
+                (-limit..=limit).contains(&value)
+            });
+            if !fits {
+                return None;
+            }
+            let row = striped_row(&self.query, lanes, pad, |q| {
+                self.scores.pair_score(q, residue) as i16
+            });
+            self.word_rows.insert(residue, row);
+        }
+        let rows: Vec<&[i16]> = subject
+            .iter()
+            .map(|c| self.word_rows[c].as_slice())
+            .collect();
+
+        let gap = gap as i16;
+        let max_score = match self.engine {
+            #[cfg(target_arch = "x86_64")]
+            SimdEngine::Avx2 => unsafe { avx2_kernel::<Avx2I16>(&rows, seg_len, 0, gap) },
+            #[cfg(target_arch = "x86_64")]
+            SimdEngine::Sse2 => unsafe { striped_kernel::<Sse2I16>(&rows, seg_len, 0, gap) },
+            _ => unsafe { striped_kernel::<PortableI16>(&rows, seg_len, 0, gap) },
+        };
+
+        if max_score >= i16::MAX as i32 {
+            return None;
+        }
+        Some(max_score)
+    }
+}
+
+// Lays the query out so that lane k of segment s holds query[k * seg_len + s].
+// Positions past the end of the query are filled with `pad`.
+fn striped_row<T: Copy>(query: &[char], lanes: usize, pad: T, value: impl Fn(char) -> T) -> Vec<T> {
+    let seg_len = query.len().div_ceil(lanes);
+    let mut row = Vec::with_capacity(seg_len * lanes);
+    for s in 0..seg_len {
+        for k in 0..lanes {
+            let i = k * seg_len + s;
+            row.push(if i < query.len() {
+                value(query[i])
+            } else {
+                pad
+            });
+        }
+    }
+    row
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn avx2_kernel<V: Lanes>(
+    rows: &[&[V::Elem]],
+    seg_len: usize,
+    bias: V::Elem,
+    gap: V::Elem,
+) -> i32 {
+    unsafe { striped_kernel::<V>(rows, seg_len, bias, gap) }
+}
+
+// Farrar's striped recurrence with a lazy-F correction pass. With a linear gap
+// the gap open and gap extend penalties are the same value.
+#[inline(always)]
+unsafe fn striped_kernel<V: Lanes>(
+    rows: &[&[V::Elem]],
+    seg_len: usize,
+    bias: V::Elem,
+    gap: V::Elem,
+) -> i32 {
+    unsafe {
+        let zero = V::splat(V::Elem::default());
+        let v_bias = V::splat(bias);
+        let v_gap = V::splat(gap);
+
+        let mut h_store = vec![zero; seg_len];
+        let mut h_load = vec![zero; seg_len];
+        let mut e_store = vec![zero; seg_len];
+        let mut v_max = zero;
+
+        for row in rows {
+            let mut v_f = zero;
+            // Diagonal values for lane 0 come from the last segment of the previous column
+            let mut v_h = h_store[seg_len - 1].shift_lanes();
+            std::mem::swap(&mut h_load, &mut h_store);
+
+            for s in 0..seg_len {
+                let profile = V::load(&row[s * V::LANES..]);
+                v_h = v_h.adds(profile).subs(v_bias).max(zero);
+                let v_e = e_store[s];
+                v_h = v_h.max(v_e).max(v_f);
+                v_max = v_max.max(v_h);
+                h_store[s] = v_h;
+
+                let v_open = v_h.subs(v_gap);
+                e_store[s] = v_e.subs(v_gap).max(v_open);
+                v_f = v_f.subs(v_gap).max(v_open);
+                v_h = h_load[s];
+            }
+
+            // Vertical gaps that cross a segment boundary were missed above.
+            // Propagate them until they can no longer improve any cell.
+            'lazy_f: for _ in 0..V::LANES {
+                v_f = v_f.shift_lanes();
+                for s in 0..seg_len {
+                    // Once F improves no cell in a segment, every later cell
+                    // already saw a larger value during the main pass
+                    if !v_f.any_gt(h_store[s]) {
+                        break 'lazy_f;
+                    }
+                    let v_h = h_store[s].max(v_f);
+                    h_store[s] = v_h;
+                    v_max = v_max.max(v_h);
+
+                    e_store[s] = e_store[s].max(v_h.subs(v_gap));
+                    v_f = v_f.subs(v_gap);
+                }
+            }
+        }
+        v_max.hmax()
+    }
+}
+
+// Linear-space scalar recurrence used once 16-bit lanes overflow
+fn scalar_score<S: Scoring>(query: &[char], subject: &[char], scores: &S) -> i32 {
+    let gap = scores.get_gap_score() as i32;
+    let mut prev = vec![0_i32; query.len() + 1];
+    let mut curr = vec![0_i32; query.len() + 1];
+    let mut max_score = 0;
+    for &residue in subject {
+        for i in 1..=query.len() {
+            let diag = prev[i - 1] + scores.pair_score(query[i - 1], residue);
+            let ugap = curr[i - 1] - gap;
+            let lgap = prev[i] - gap;
+            curr[i] = [0, diag, ugap, lgap].iter().max().copied().unwrap();
+            max_score = max_score.max(curr[i]);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    max_score
+}
@@ -0,0 +1,277 @@
+use crate::align::Scoring;
+use crate::align::genetic_code::GeneticCode;
+use crate::align::scoring::GeneralScoring;
+use crate::align::strand::{Strand, reverse_complement};
+use spindalis::utils::Arr2D;
+
+// Local alignment of a protein query against DNA, scored on the translated
+// codons. Rows follow the protein and columns follow nucleotides, so a
+// residue consumes three columns. Besides whole-codon gaps the DNA may skip
+// one or two nucleotides at a cost of `frameshift`, which lets the alignment
+// change reading frame. With `both_strands` the reverse complement is tried
+// as well, covering all six frames.
+pub struct TranslatedAligner<S: Scoring + Clone> {
+    pub scores: S,
+    pub code: GeneticCode,
+    pub frameshift: usize,
+    pub both_strands: bool,
+}
+
+impl Default for TranslatedAligner<GeneralScoring> {
+    fn default() -> Self {
+        let scores = GeneralScoring {
+            identity: 2,
+            mismatch: 1,
+            gap: 2,
+        };
+        Self {
+            scores,
+            code: GeneticCode::standard(),
+            frameshift: 4,
+            both_strands: true,
+        }
+    }
+}
+
+// Moves stored in the pointer matrix. A cell keeps only the first best move
+// in this order, so there is a single traceback.
+const CODON: i32 = 1;
+const RESIDUE_GAP: i32 = 2;
+const CODON_GAP: i32 = 3;
+const SHIFT_ONE: i32 = 4;
+const SHIFT_TWO: i32 = 5;
+
+// Result of a translated alignment. `dna` is the strand that was aligned and
+// the matrices are laid out as in `LocalAlignmentModel`. Coordinates are
+// 0-based and half-open; the DNA span always refers to the forward strand.
+// `frame` is +1, +2, +3 or -1, -2, -3 for the frame the alignment starts in,
+// as in `GeneticCode::six_frames`.
+pub struct TranslatedAlignmentModel {
+    pub protein: Vec<char>,
+    pub dna: Vec<char>,
+    pub score_matrix: Arr2D<i32>,
+    pub pointer_matrix: Arr2D<i32>,
+    pub max_score: i32,
+    pub start_indices: Vec<(usize, usize)>,
+    pub strand: Strand,
+    pub frame: i8,
+    pub query_start: usize,
+    pub query_end: usize,
+    pub subject_start: usize,
+    pub subject_end: usize,
+    pub frameshifts: usize,
+}
+
+impl TranslatedAlignmentModel {
+    // Protein residues over the codons they were aligned to, e.g.
+    //  M  K --- L ! W
+    // ATGAAAGCACTGaTGG
+    // Each residue sits in the middle of its three columns. Nucleotides
+    // skipped by a frameshift are lower case and marked with '!' above.
+    pub fn align(&self) -> Vec<String> {
+        let Some(&(i, j)) = self.start_indices.last() else {
+            return Vec::new();
+        };
+        let mut protein_line = String::new();
+        let mut dna_line = String::new();
+        for (step, (i, j)) in self.path(i, j) {
+            let codon: String = self.dna[j.saturating_sub(3)..j].iter().collect();
+            let (top, bottom) = match step {
+                CODON => (format!(" {} ", self.protein[i - 1]), codon),
+                RESIDUE_GAP => (format!(" {} ", self.protein[i - 1]), "---".to_string()),
+                CODON_GAP => ("---".to_string(), codon),
+                SHIFT_ONE => (
+                    "!".to_string(),
+                    self.dna[j - 1].to_ascii_lowercase().to_string(),
+                ),
+                _ => (
+                    "!!".to_string(),
+                    self.dna[j - 2..j].iter().collect::<String>().to_lowercase(),
+                ),
+            };
+            protein_line.insert_str(0, &top);
+            dna_line.insert_str(0, &bottom);
+        }
+        vec![format!("{protein_line}\n{dna_line}")]
+    }
+
+    pub fn similarity(&self) -> i32 {
+        self.max_score
+    }
+
+    // Moves from the end cell back to the first zero cell, last move first,
+    // each paired with the cell it leads into
+    fn path(&self, mut i: usize, mut j: usize) -> Vec<(i32, (usize, usize))> {
+        let mut steps = Vec::new();
+        while self.score_matrix[i][j] > 0 {
+            let step = self.pointer_matrix[i][j];
+            steps.push((step, (i, j)));
+            let (di, dj) = step_size(step);
+            i -= di;
+            j -= dj;
+        }
+        steps
+    }
+}
+
+// Rows and columns consumed by a move
+fn step_size(step: i32) -> (usize, usize) {
+    match step {
+        CODON => (1, 3),
+        RESIDUE_GAP => (1, 0),
+        CODON_GAP => (0, 3),
+        SHIFT_ONE => (0, 1),
+        _ => (0, 2),
+    }
+}
+
+impl<S: Scoring + Clone> TranslatedAligner<S> {
+    pub fn set_scores(scores: &S) -> Self {
+        Self {
+            scores: scores.clone(),
+            code: GeneticCode::standard(),
+            frameshift: 4,
+            both_strands: true,
+        }
+    }
+
+    pub fn genetic_code(&self, code: GeneticCode) -> Self {
+        Self {
+            scores: self.scores.clone(),
+            code,
+            frameshift: self.frameshift,
+            both_strands: self.both_strands,
+        }
+    }
+
+    pub fn frameshift(&self, value: usize) -> Self {
+        Self {
+            scores: self.scores.clone(),
+            code: self.code.clone(),
+            frameshift: value,
+            both_strands: self.both_strands,
+        }
+    }
+
+    pub fn both_strands(&self, value: bool) -> Self {
+        Self {
+            scores: self.scores.clone(),
+            code: self.code.clone(),
+            frameshift: self.frameshift,
+            both_strands: value,
+        }
+    }
+
+    // Aligns the protein against the DNA, keeping the better strand when
+    // `both_strands` is set. Ties go to the forward strand.
+    pub fn calculate_matrix(&self, protein: &str, dna: &str) -> TranslatedAlignmentModel {
+        let forward = self.align_strand(protein, dna, Strand::Forward);
+        if !self.both_strands {
+            return forward;
+        }
+        let reverse = self.align_strand(protein, &reverse_complement(dna), Strand::Reverse);
+        if reverse.max_score > forward.max_score {
+            reverse
+        } else {
+            forward
+        }
+    }
+
+    fn align_strand(&self, protein: &str, dna: &str, strand: Strand) -> TranslatedAlignmentModel {
+        let protein: Vec<char> = protein.to_uppercase().chars().collect();
+        let dna: Vec<char> = dna.to_uppercase().chars().collect();
+        let (m, n) = (protein.len(), dna.len());
+        let gap = self.scores.get_gap_score() as i32;
+        let frameshift = self.frameshift as i32;
+
+        // Amino acid of the codon ending just before column j
+        let codons: Vec<char> = (0..=n)
+            .map(|j| {
+                if j >= 3 {
+                    self.code.codon(&dna[j - 3..j])
+                } else {
+                    'X'
+                }
+            })
+            .collect();
+
+        let mut score_matrix = Arr2D::full(0, m + 1, n + 1);
+        let mut pointer_matrix = Arr2D::full(0, m + 1, n + 1);
+        let mut max_score = 0;
+        let mut start_indices = Vec::new();
+        for i in 0..=m {
+            for j in 0..=n {
+                let mut moves = [None; 5];
+                if i >= 1 && j >= 3 {
+                    let codon = self.scores.pair_score(protein[i - 1], codons[j]);
+                    moves[0] = Some((score_matrix[i - 1][j - 3] + codon, CODON));
+                }
+                if i >= 1 {
+                    moves[1] = Some((score_matrix[i - 1][j] - gap, RESIDUE_GAP));
+                }
+                if j >= 3 {
+                    moves[2] = Some((score_matrix[i][j - 3] - gap, CODON_GAP));
+                }
+                if j >= 1 {
+                    moves[3] = Some((score_matrix[i][j - 1] - frameshift, SHIFT_ONE));
+                }
+                if j >= 2 {
+                    moves[4] = Some((score_matrix[i][j - 2] - frameshift, SHIFT_TWO));
+                }
+
+                let mut cell = (0, 0);
+                for (score, step) in moves.into_iter().flatten() {
+                    if score > cell.0 {
+                        cell = (score, step);
+                    }
+                }
+                score_matrix[i][j] = cell.0;
+                pointer_matrix[i][j] = cell.1;
+
+                if cell.0 > max_score {
+                    max_score = cell.0;
+                    start_indices.clear();
+                    start_indices.push((i, j));
+                } else if cell.0 == max_score && max_score > 0 {
+                    start_indices.push((i, j));
+                }
+            }
+        }
+
+        let mut model = TranslatedAlignmentModel {
+            protein,
+            dna,
+            score_matrix,
+            pointer_matrix,
+            max_score,
+            start_indices,
+            strand,
+            frame: 0,
+            query_start: 0,
+            query_end: 0,
+            subject_start: 0,
+            subject_end: 0,
+            frameshifts: 0,
+        };
+        if let Some(&(i, j)) = model.start_indices.last() {
+            let path = model.path(i, j);
+            let (mut start_i, mut start_j) = (i, j);
+            for &(step, _) in &path {
+                let (di, dj) = step_size(step);
+                start_i -= di;
+                start_j -= dj;
+            }
+            model.frameshifts = path
+                .iter()
+                .filter(|(step, _)| matches!(*step, SHIFT_ONE | SHIFT_TWO))
+                .count();
+            (model.query_start, model.query_end) = (start_i, i);
+            let offset = (start_j % 3) as i8 + 1;
+            (model.frame, model.subject_start, model.subject_end) = match strand {
+                Strand::Forward => (offset, start_j, j),
+                Strand::Reverse => (-offset, n - j, n - start_j),
+            };
+        }
+        model
+    }
+}
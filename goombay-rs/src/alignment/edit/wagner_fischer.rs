@@ -1,6 +1,7 @@
 use crate::align::global_base::{GlobalAlgorithm, GlobalAlignmentModel, Metric};
-use crate::align::scoring::LevenshteinScoring;
+use crate::align::scoring::{LevenshteinScoring, NucleotideScoring};
 use crate::align::{AlignmentData, GlobalAlignmentMatrix, PointerValues, Scoring};
+use std::ops::IndexMut;
 
 pub struct WagnerFischer<S: Scoring + Clone> {
     pub scores: S,
@@ -30,34 +31,87 @@ impl GlobalAlignmentMatrix<LevenshteinScoring> for WagnerFischer<LevenshteinScor
     }
     fn calculate_matrix(&self, query: &str, subject: &str) -> GlobalAlignmentModel {
         let mut alignments = AlignmentData::new(query, subject);
-        let query_len = alignments.query.len() + 1;
-        let subject_len = alignments.subject.len() + 1;
-        let score_matrix = &mut alignments.score_matrix[0];
-        let pointer_matrix = &mut alignments.pointer_matrix[0];
+        self.fill_matrices(
+            &alignments.query,
+            &alignments.subject,
+            &mut alignments.score_matrix[0],
+            &mut alignments.pointer_matrix[0],
+        );
+        self.model(alignments)
+    }
+}
+
+impl GlobalAlignmentMatrix<NucleotideScoring> for WagnerFischer<NucleotideScoring> {
+    fn compute(query: &str, subject: &str) -> GlobalAlignmentModel {
+        // Unit edit costs, with N free against any base
+        let scores = NucleotideScoring {
+            identity: 0,
+            mismatch: 1,
+            gap: 1,
+            n_score: 0,
+        };
+        let wf_default = WagnerFischer::set_scores(&scores);
+        wf_default.calculate_matrix(query, subject)
+    }
+    fn set_scores(scores: &NucleotideScoring) -> Self {
+        // Set custom scores before manually calculating matrices
+        Self {
+            scores: scores.clone(),
+        }
+    }
+    fn calculate_matrix(&self, query: &str, subject: &str) -> GlobalAlignmentModel {
+        let mut alignments = AlignmentData::new(query, subject);
+        self.fill_matrices(
+            &alignments.query,
+            &alignments.subject,
+            &mut alignments.score_matrix[0],
+            &mut alignments.pointer_matrix[0],
+        );
+        self.model(alignments)
+    }
+}
+
+// Same shim as for `NeedlemanWunsch::compute`
+impl WagnerFischer<LevenshteinScoring> {
+    pub fn compute(query: &str, subject: &str) -> GlobalAlignmentModel {
+        <Self as GlobalAlignmentMatrix<LevenshteinScoring>>::compute(query, subject)
+    }
+}
+
+impl<S: Scoring + Clone> WagnerFischer<S> {
+    // Fills zero-initialised score and pointer matrices of shape
+    // (query.len() + 1, subject.len() + 1). Shared by `calculate_matrix` and
+    // the reusable `Aligner` workspace.
+    pub(crate) fn fill_matrices<M: IndexMut<usize, Output = [i32]>>(
+        &self,
+        query: &[char],
+        subject: &[char],
+        score_matrix: &mut M,
+        pointer_matrix: &mut M,
+    ) {
+        let query_len = query.len() + 1;
+        let subject_len = subject.len() + 1;
+        let gap = self.scores.get_gap_score() as i32;
 
         // initialise score and pointer matrices
         pointer_matrix[0][0] = PointerValues::Left as i32;
         for i in 1..query_len {
-            score_matrix[i][0] = i as i32 * self.scores.gap as i32;
+            score_matrix[i][0] = i as i32 * gap;
             pointer_matrix[i][0] = PointerValues::Up as i32;
         }
         for j in 1..subject_len {
-            score_matrix[0][j] = j as i32 * self.scores.gap as i32;
+            score_matrix[0][j] = j as i32 * gap;
             pointer_matrix[0][j] = PointerValues::Left as i32;
         }
 
         // Build pointer and score matrix
         for i in 1..query_len {
             for j in 1..subject_len {
-                let identity = {
-                    if alignments.query[i - 1] != alignments.subject[j - 1] {
-                        score_matrix[i - 1][j - 1] + self.scores.substitution as i32
-                    } else {
-                        score_matrix[i - 1][j - 1] // Score unchanged for matching letters
-                    }
-                };
-                let ugap = score_matrix[i - 1][j] + self.scores.gap as i32;
-                let lgap = score_matrix[i][j - 1] + self.scores.gap as i32;
+                // Cost is zero for matching letters
+                let identity = score_matrix[i - 1][j - 1]
+                    + self.scores.pair_cost(query[i - 1], subject[j - 1]);
+                let ugap = score_matrix[i - 1][j] + gap;
+                let lgap = score_matrix[i][j - 1] + gap;
 
                 let tmax = [identity, ugap, lgap].iter().min().copied().unwrap();
                 score_matrix[i][j] = tmax;
@@ -73,14 +127,16 @@ impl GlobalAlignmentMatrix<LevenshteinScoring> for WagnerFischer<LevenshteinScor
                 }
             }
         }
+    }
 
+    fn model(&self, alignments: AlignmentData) -> GlobalAlignmentModel {
         GlobalAlignmentModel {
             data: alignments,
             aligner: GlobalAlgorithm::WagnerFischer,
             metric: Metric::Distance,
             identity: 0,
-            mismatch: self.scores.substitution,
-            gap: self.scores.gap,
+            mismatch: self.scores.get_mismatch_score(),
+            gap: self.scores.get_gap_score(),
             all_alignments: false,
         }
     }
@@ -0,0 +1,381 @@
+use crate::align::local_base::{LocalAlgorithm, LocalAlignmentModel, LocalMetric};
+use crate::align::scoring::GeneralScoring;
+use crate::align::{AlignmentData, PointerValues, Scoring};
+use spindalis::utils::Arr2D;
+
+// Gapped seed extension that stops once the score drops more than `x_drop`
+// below the best score seen so far. Only the band of cells that stay within
+// the drop-off is computed, in each direction from the seed.
+pub struct XDrop<S: Scoring + Clone> {
+    pub scores: S,
+    pub x_drop: i32,
+}
+
+impl Default for XDrop<GeneralScoring> {
+    fn default() -> Self {
+        let scores = GeneralScoring {
+            identity: 2,
+            mismatch: 1,
+            gap: 2,
+        };
+        Self { scores, x_drop: 10 }
+    }
+}
+
+// Extended alignment around a seed, covering query[query_start..query_end]
+// vs subject[subject_start..subject_end]. Only the alignment path is kept,
+// so memory grows with the length of the extension, not its area. `align`
+// and `similarity` behave like those of a `SmithWaterman` model.
+pub struct XDropExtension {
+    pub query_start: usize,
+    pub query_end: usize,
+    pub subject_start: usize,
+    pub subject_end: usize,
+    pub cells_computed: usize,
+    score: i32,
+    query: Vec<char>,
+    subject: Vec<char>,
+    path: Vec<Step>,
+    // Running score after each step of the path
+    running: Vec<i32>,
+    identity: usize,
+    mismatch: usize,
+    gap: usize,
+}
+
+impl XDropExtension {
+    pub fn align(&self) -> Vec<String> {
+        if self.score <= 0 {
+            return Vec::new();
+        }
+        let (mut qs, mut ss) = (String::new(), String::new());
+        let (mut i, mut j) = (0, 0);
+        for &step in &self.path {
+            match step {
+                Step::Match => {
+                    qs.push(self.query[i]);
+                    ss.push(self.subject[j]);
+                    i += 1;
+                    j += 1;
+                }
+                Step::Up => {
+                    qs.push(self.query[i]);
+                    ss.push('-');
+                    i += 1;
+                }
+                Step::Left => {
+                    qs.push('-');
+                    ss.push(self.subject[j]);
+                    j += 1;
+                }
+            }
+        }
+        vec![format!("{qs}\n{ss}")]
+    }
+
+    pub fn similarity(&self) -> i32 {
+        self.score
+    }
+
+    // `SmithWaterman`-style model of the window with only the cells on the
+    // path set. It fills matrices over the whole window, so it is built only
+    // when asked for.
+    pub fn model(&self) -> LocalAlignmentModel {
+        let height = self.query.len() + 1;
+        let width = self.subject.len() + 1;
+        let mut alignments = AlignmentData {
+            query: self.query.clone(),
+            subject: self.subject.clone(),
+            score_matrix: vec![Arr2D::full(0, height, width)],
+            pointer_matrix: vec![Arr2D::full(0, height, width)],
+        };
+        let (mut i, mut j) = (0, 0);
+        for (&step, &score) in self.path.iter().zip(&self.running) {
+            let pointer = match step {
+                Step::Match => {
+                    i += 1;
+                    j += 1;
+                    PointerValues::Match
+                }
+                Step::Up => {
+                    i += 1;
+                    PointerValues::Up
+                }
+                Step::Left => {
+                    j += 1;
+                    PointerValues::Left
+                }
+            };
+            alignments.score_matrix[0][i][j] = score;
+            alignments.pointer_matrix[0][i][j] = pointer as i32;
+        }
+        let start_indices = if self.score > 0 {
+            vec![(self.query.len(), self.subject.len())]
+        } else {
+            Vec::new()
+        };
+
+        LocalAlignmentModel {
+            data: alignments,
+            aligner: LocalAlgorithm::SmithWaterman,
+            metric: LocalMetric::Similarity,
+            identity: self.identity,
+            mismatch: self.mismatch,
+            gap: self.gap,
+            all_alignments: false,
+            max_score: self.score,
+            start_indices,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    Match,
+    Up,
+    Left,
+}
+
+// One row of the banded DP, covering columns lo..lo + scores.len()
+struct BandRow {
+    lo: usize,
+    scores: Vec<i32>,
+    steps: Vec<Step>,
+}
+
+impl BandRow {
+    fn get(&self, j: usize) -> Option<i32> {
+        if j < self.lo {
+            return None;
+        }
+        self.scores
+            .get(j - self.lo)
+            .copied()
+            .filter(|&s| s != DROPPED)
+    }
+}
+
+const DROPPED: i32 = i32::MIN;
+
+struct Extension {
+    end: (usize, usize),
+    steps: Vec<Step>,
+    cells: usize,
+}
+
+impl<S: Scoring + Clone> XDrop<S> {
+    pub fn set_scores(scores: &S) -> Self {
+        Self {
+            scores: scores.clone(),
+            x_drop: 10,
+        }
+    }
+
+    pub fn x_drop(&self, value: i32) -> Self {
+        Self {
+            scores: self.scores.clone(),
+            x_drop: value,
+        }
+    }
+
+    // Extends right from query[i], subject[j] (inclusive) and left from just
+    // before them. The seed itself gets no special treatment, so for the usual
+    // case it should point at the first residue of an exact word match.
+    pub fn extend(&self, query: &str, subject: &str, seed: (usize, usize)) -> XDropExtension {
+        let query: Vec<char> = query.to_uppercase().chars().collect();
+        let subject: Vec<char> = subject.to_uppercase().chars().collect();
+        let (i, j) = seed;
+        assert!(
+            i <= query.len() && j <= subject.len(),
+            "seed ({i}, {j}) is outside the sequences"
+        );
+
+        let right = self.extend_one_way(&query[i..], &subject[j..]);
+        let left_query: Vec<char> = query[..i].iter().rev().copied().collect();
+        let left_subject: Vec<char> = subject[..j].iter().rev().copied().collect();
+        let left = self.extend_one_way(&left_query, &left_subject);
+
+        // Tracebacks run from the far end towards the seed. For the reversed
+        // left half that is already left-to-right order, the right half is flipped.
+        let mut path = left.steps;
+        path.extend(right.steps.iter().rev());
+
+        let query_start = i - left.end.0;
+        let subject_start = j - left.end.1;
+        self.build(
+            &query,
+            &subject,
+            (query_start, subject_start),
+            &path,
+            left.cells + right.cells,
+        )
+    }
+
+    // Banded DP anchored at (0, 0) with a free end point
+    fn extend_one_way(&self, query: &[char], subject: &[char]) -> Extension {
+        let gap = self.scores.get_gap_score() as i32;
+        let mut best = 0;
+        let mut end = (0, 0);
+        let mut cells = 0;
+        let mut rows: Vec<BandRow> = Vec::new();
+
+        // Row 0 only holds leading gaps in the query
+        let mut first = BandRow {
+            lo: 0,
+            scores: vec![0],
+            steps: vec![Step::Left],
+        };
+        for j in 1..=subject.len() {
+            let score = -(j as i32) * gap;
+            if score < best - self.x_drop {
+                break;
+            }
+            first.scores.push(score);
+            first.steps.push(Step::Left);
+        }
+        cells += first.scores.len();
+        rows.push(first);
+
+        for i in 1..=query.len() {
+            let prev = rows.last().unwrap();
+            let Some(lo) = (0..prev.scores.len())
+                .find(|&k| prev.scores[k] != DROPPED)
+                .map(|k| prev.lo + k)
+            else {
+                break;
+            };
+            let prev_hi = prev.lo + prev.scores.len();
+
+            let mut row = BandRow {
+                lo,
+                scores: Vec::new(),
+                steps: Vec::new(),
+            };
+            let mut j = lo;
+            while j <= subject.len() {
+                let left = if j > lo { row.get(j - 1) } else { None };
+                // Past the previous band only a run of left gaps can stay alive
+                if j > prev_hi && left.is_none() {
+                    break;
+                }
+                let diag = if j > 0 { prev.get(j - 1) } else { None }
+                    .map(|d| d + self.scores.pair_score(query[i - 1], subject[j - 1]));
+                let candidates = [
+                    (diag, Step::Match),
+                    (prev.get(j).map(|u| u - gap), Step::Up),
+                    (left.map(|l| l - gap), Step::Left),
+                ];
+                // Ties prefer the diagonal, then the upper cell
+                let mut cell = (DROPPED, Step::Match);
+                for (score, step) in candidates {
+                    if let Some(score) = score
+                        && score > cell.0
+                    {
+                        cell = (score, step);
+                    }
+                }
+                cells += 1;
+
+                if cell.0 == DROPPED || cell.0 < best - self.x_drop {
+                    cell.0 = DROPPED;
+                } else if cell.0 > best {
+                    best = cell.0;
+                    end = (i, j);
+                }
+                row.scores.push(cell.0);
+                row.steps.push(cell.1);
+                j += 1;
+            }
+
+            // Trim dropped cells from the end of the band
+            while row.scores.last() == Some(&DROPPED) {
+                row.scores.pop();
+                row.steps.pop();
+            }
+            if row.scores.is_empty() {
+                break;
+            }
+            rows.push(row);
+        }
+
+        // Trace back from the best cell to the anchor
+        let mut steps = Vec::new();
+        let (mut i, mut j) = end;
+        while (i, j) != (0, 0) {
+            let row = &rows[i];
+            let step = row.steps[j - row.lo];
+            steps.push(step);
+            match step {
+                Step::Match => {
+                    i -= 1;
+                    j -= 1;
+                }
+                Step::Up => i -= 1,
+                Step::Left => j -= 1,
+            }
+        }
+
+        Extension { end, steps, cells }
+    }
+
+    fn build(
+        &self,
+        query: &[char],
+        subject: &[char],
+        start: (usize, usize),
+        path: &[Step],
+        cells_computed: usize,
+    ) -> XDropExtension {
+        let gap = self.scores.get_gap_score() as i32;
+        // Walk the joined path once to collect the visited cells and running scores
+        let mut positions = vec![start];
+        let mut prefix = vec![0];
+        let (mut i, mut j) = start;
+        for &step in path {
+            let score = match step {
+                Step::Match => {
+                    i += 1;
+                    j += 1;
+                    self.scores.pair_score(query[i - 1], subject[j - 1])
+                }
+                Step::Up => {
+                    i += 1;
+                    -gap
+                }
+                Step::Left => {
+                    j += 1;
+                    -gap
+                }
+            };
+            positions.push((i, j));
+            prefix.push(prefix.last().unwrap() + score);
+        }
+
+        // A local alignment starts where the running score is lowest, so the
+        // path before its last minimum is dropped. That can only raise the
+        // score, and keeps traceback from stopping early.
+        let lowest = prefix.iter().copied().min().unwrap();
+        let first = prefix.iter().rposition(|&score| score == lowest).unwrap();
+        let (query_start, subject_start) = positions[first];
+        let (query_end, subject_end) = *positions.last().unwrap();
+
+        XDropExtension {
+            query_start,
+            query_end,
+            subject_start,
+            subject_end,
+            cells_computed,
+            score: prefix[prefix.len() - 1] - lowest,
+            query: query[query_start..query_end].to_vec(),
+            subject: subject[subject_start..subject_end].to_vec(),
+            path: path[first..].to_vec(),
+            running: prefix[first + 1..]
+                .iter()
+                .map(|score| score - lowest)
+                .collect(),
+            identity: self.scores.get_match_score(),
+            mismatch: self.scores.get_mismatch_score(),
+            gap: self.scores.get_gap_score(),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::align::edit::wagner_fischer::WagnerFischer;
+use crate::align::scoring::LevenshteinScoring;
+use crate::align::{GlobalAlignmentMatrix, Scoring};
+use std::collections::BTreeMap;
+
+// Place in the text where the pattern occurs with at most `max_distance` edits.
+// Coordinates are 0-based and half-open, e.g. text[start..end].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub start: usize,
+    pub end: usize,
+    pub distance: i32,
+    pub alignment: Option<String>,
+}
+
+// Approximate substring search. Runs the `WagnerFischer` recurrence with the
+// pattern down the rows and the text across the columns, but with a free
+// start row so a match may begin anywhere in the text. Only one column of the
+// matrix is kept, so memory grows with the pattern and not the text.
+pub struct FuzzySearch<S: Scoring + Clone> {
+    pub scores: S,
+    pub max_distance: i32,
+    pub with_alignment: bool,
+}
+
+impl Default for FuzzySearch<LevenshteinScoring> {
+    fn default() -> Self {
+        let scores = LevenshteinScoring {
+            substitution: 1,
+            gap: 1,
+        };
+        Self {
+            scores,
+            max_distance: 1,
+            with_alignment: false,
+        }
+    }
+}
+
+impl<S: Scoring + Clone> FuzzySearch<S>
+where
+    WagnerFischer<S>: GlobalAlignmentMatrix<S>,
+{
+    pub fn set_scores(scores: &S) -> Self {
+        Self {
+            scores: scores.clone(),
+            max_distance: 1,
+            with_alignment: false,
+        }
+    }
+
+    pub fn max_distance(&self, value: i32) -> Self {
+        Self {
+            scores: self.scores.clone(),
+            max_distance: value,
+            with_alignment: self.with_alignment,
+        }
+    }
+
+    pub fn with_alignment(&self, value: bool) -> Self {
+        Self {
+            scores: self.scores.clone(),
+            max_distance: self.max_distance,
+            with_alignment: value,
+        }
+    }
+
+    // (start, end, distance) of the best match ending at each text position,
+    // for every end within `max_distance`, in order of `end`
+    pub fn candidates(&self, pattern: &str, text: &str) -> Vec<(usize, usize, i32)> {
+        let pattern: Vec<char> = pattern.to_uppercase().chars().collect();
+        let text: Vec<char> = text.to_uppercase().chars().collect();
+        let mut candidates = Vec::new();
+        if pattern.is_empty() {
+            return candidates;
+        }
+
+        // Distances are negated scores, so the lowest distance scores highest
+        let pair = |a, b| -self.scores.pair_cost(a, b);
+        let gap = self.scores.get_gap_score() as i32;
+        free_start_scan(&pattern, &text, pair, gap, |start, end, score| {
+            // Empty matches only come from deleting the whole pattern
+            if -score <= self.max_distance && start < end {
+                candidates.push((start, end, -score));
+            }
+        });
+        candidates
+    }
+
+    // Non-overlapping matches sorted by start. Where candidate matches overlap
+    // the lowest distance wins, then the earliest start, then the shortest.
+    pub fn find(&self, pattern: &str, text: &str) -> Vec<FuzzyMatch> {
+        let mut candidates = self.candidates(pattern, text);
+        candidates.sort_by_key(|&(start, end, distance)| (distance, start, end));
+
+        // Accepted matches keyed by start. They never overlap, so only the
+        // neighbours on either side of a new start can collide with it.
+        let mut chosen: BTreeMap<usize, (usize, i32)> = BTreeMap::new();
+        for (start, end, distance) in candidates {
+            let before = chosen.range(..=start).next_back();
+            let after = chosen.range(start..).next();
+            let overlaps = before.is_some_and(|(_, &(e, _))| start < e)
+                || after.is_some_and(|(&s, _)| s < end);
+            if !overlaps {
+                chosen.insert(start, (end, distance));
+            }
+        }
+
+        let text: Vec<char> = text.to_uppercase().chars().collect();
+        let aligner = WagnerFischer::set_scores(&self.scores);
+        chosen
+            .into_iter()
+            .map(|(start, (end, distance))| {
+                let alignment = self.with_alignment.then(|| {
+                    let window: String = text[start..end].iter().collect();
+                    aligner.calculate_matrix(pattern, &window).align().remove(0)
+                });
+                FuzzyMatch {
+                    start,
+                    end,
+                    distance,
+                    alignment,
+                }
+            })
+            .collect()
+    }
+}
+
+// Global recurrence with the pattern down the rows and the text across the
+// columns, but with a free first row so an alignment may start at any text
+// position. Only one column is kept. After each text position `end` it calls
+// `visit(start, end, score)` with the best score of the whole pattern ending
+// there and the text position that alignment starts at. `pair` scores two
+// aligned residues and every gap costs `gap`; higher scores are better.
+// Ties follow the pointer order: match, then up, then left.
+pub(crate) fn free_start_scan<P, V>(
+    pattern: &[char],
+    text: &[char],
+    pair: P,
+    gap: i32,
+    mut visit: V,
+) where
+    P: Fn(char, char) -> i32,
+    V: FnMut(usize, usize, i32),
+{
+    let m = pattern.len();
+    let mut scores: Vec<i32> = (0..=m).map(|i| -(i as i32) * gap).collect();
+    let mut starts: Vec<usize> = vec![0; m + 1];
+    for j in 1..=text.len() {
+        // Skipping any prefix of the text costs nothing
+        let mut diag = (scores[0], starts[0]);
+        scores[0] = 0;
+        starts[0] = j;
+        for i in 1..=m {
+            let identity = diag.0 + pair(pattern[i - 1], text[j - 1]);
+            let ugap = scores[i - 1] - gap;
+            let lgap = scores[i] - gap;
+
+            let left = (scores[i], starts[i]);
+            let mut cell = (identity, diag.1);
+            if ugap > cell.0 {
+                cell = (ugap, starts[i - 1]);
+            }
+            if lgap > cell.0 {
+                cell = (lgap, left.1);
+            }
+            diag = left;
+            scores[i] = cell.0;
+            starts[i] = cell.1;
+        }
+        visit(starts[m], j, scores[m]);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::align::scoring::iupac_bases;
+use crate::align::strand::reverse_complement;
+
+// Codon to amino acid table.
+// Tables use the NCBI layout: 64 amino acids for the codons in TCAG order,
+// i.e. TTT, TTC, TTA, TTG, TCT, ... GGG, with '*' for stop codons.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneticCode {
+    amino_acids: [char; 64],
+}
+
+impl Default for GeneticCode {
+    fn default() -> Self {
+        GeneticCode::standard()
+    }
+}
+
+impl GeneticCode {
+    pub fn from_table(amino_acids: &str) -> Self {
+        let chars: Vec<char> = amino_acids.to_uppercase().chars().collect();
+        assert_eq!(
+            chars.len(),
+            64,
+            "a genetic code table needs one amino acid for each of the 64 codons"
+        );
+        let mut table = ['X'; 64];
+        table.copy_from_slice(&chars);
+        GeneticCode { amino_acids: table }
+    }
+
+    // NCBI translation table 1
+    pub fn standard() -> Self {
+        GeneticCode::from_table("FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG")
+    }
+
+    // NCBI translation tables by number. Table 11 (bacterial, archaeal and
+    // plastid) only differs from the standard code in its start codons.
+    pub fn ncbi(id: u8) -> Option<Self> {
+        let table = match id {
+            1 | 11 => "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            2 => "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+            3 => "FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            4 => "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            5 => "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+            6 => "FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+            _ => return None,
+        };
+        Some(GeneticCode::from_table(table))
+    }
+
+    // Amino acid for one codon. Ambiguity codes are resolved when every base
+    // they stand for gives the same amino acid (CTN is L); otherwise, and for
+    // anything that is not a nucleotide, the result is 'X'.
+    pub fn codon(&self, codon: &[char]) -> char {
+        if codon.len() != 3 {
+            return 'X';
+        }
+        let bases: Option<Vec<u8>> = codon.iter().map(|&c| iupac_bases(c)).collect();
+        let Some(bases) = bases else {
+            return 'X';
+        };
+        // Bits A=1, C=2, G=4, T=8 map to the TCAG index T=0, C=1, A=2, G=3
+        let tcag = |bit: u8| match bit {
+            0b0001 => 2,
+            0b0010 => 1,
+            0b0100 => 3,
+            _ => 0,
+        };
+        let mut amino_acid = None;
+        for first in (0..4).map(|b| 1 << b).filter(|b| bases[0] & b != 0) {
+            for second in (0..4).map(|b| 1 << b).filter(|b| bases[1] & b != 0) {
+                for third in (0..4).map(|b| 1 << b).filter(|b| bases[2] & b != 0) {
+                    let index = tcag(first) * 16 + tcag(second) * 4 + tcag(third);
+                    let translated = self.amino_acids[index];
+                    match amino_acid {
+                        None => amino_acid = Some(translated),
+                        Some(previous) if previous != translated => return 'X',
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+        amino_acid.unwrap_or('X')
+    }
+
+    // Translates whole codons from the start of the sequence, dropping any
+    // trailing partial codon
+    pub fn translate(&self, dna: &str) -> String {
+        let dna: Vec<char> = dna.to_uppercase().chars().collect();
+        dna.chunks_exact(3).map(|codon| self.codon(codon)).collect()
+    }
+
+    // Translations of the six reading frames, labelled +1, +2, +3 for the
+    // forward strand starting at offsets 0, 1, 2, and -1, -2, -3 for the
+    // same offsets into the reverse complement
+    pub fn six_frames(&self, dna: &str) -> Vec<(i8, String)> {
+        let forward: Vec<char> = dna.chars().collect();
+        let reverse: Vec<char> = reverse_complement(dna).chars().collect();
+        let mut frames = Vec::with_capacity(6);
+        for (sign, strand) in [(1, &forward), (-1, &reverse)] {
+            for offset in 0..3 {
+                let shifted: String = strand.iter().skip(offset).collect();
+                frames.push((sign * (offset as i8 + 1), self.translate(&shifted)));
+            }
+        }
+        frames
+    }
+}
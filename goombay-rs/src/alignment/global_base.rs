@@ -1,5 +1,6 @@
 use crate::align::{AlignmentData, PointerValues};
 use spindalis::utils::Arr2D;
+use std::ops::Index;
 
 #[derive(Clone)]
 pub enum GlobalAlgorithm {
@@ -65,18 +66,57 @@ impl GlobalAlignmentModel {
         aligned_results
     }
 
+    fn scores(&self) -> GlobalScores<'_> {
+        let i = self.data.query.len();
+        let j = self.data.subject.len();
+        GlobalScores {
+            query_len: i,
+            subject_len: j,
+            final_score: self.data.score_matrix()[i][j],
+            metric: &self.metric,
+            identity: self.identity,
+            mismatch: self.mismatch,
+            gap: self.gap,
+        }
+    }
+
     pub fn similarity(&self) -> i32 {
-        if self.data.query.is_empty() && self.data.subject.is_empty() {
+        self.scores().similarity()
+    }
+
+    pub fn distance(&self) -> i32 {
+        self.scores().distance()
+    }
+
+    pub fn normalized_similarity(&self) -> f64 {
+        self.scores().normalized_similarity()
+    }
+
+    pub fn normalized_distance(&self) -> f64 {
+        self.scores().normalized_distance()
+    }
+}
+
+// Score calculations shared by models that own their matrices and
+// workspace views that borrow them
+pub(crate) struct GlobalScores<'a> {
+    pub query_len: usize,
+    pub subject_len: usize,
+    pub final_score: i32,
+    pub metric: &'a Metric,
+    pub identity: usize,
+    pub mismatch: usize,
+    pub gap: usize,
+}
+
+impl GlobalScores<'_> {
+    pub(crate) fn similarity(&self) -> i32 {
+        if self.query_len == 0 && self.subject_len == 0 {
             return 1;
         }
         match self.metric {
-            Metric::Similarity => {
-                let score_matrix = self.data.score_matrix();
-                let i = self.data.query.len();
-                let j = self.data.subject.len();
-                score_matrix[i][j]
-            }
-            Metric::Distance => [self.data.query.len(), self.data.subject.len()]
+            Metric::Similarity => self.final_score,
+            Metric::Distance => [self.query_len, self.subject_len]
                 .iter()
                 .max()
                 .copied()
@@ -88,14 +128,14 @@ impl GlobalAlignmentModel {
         }
     }
 
-    pub fn distance(&self) -> i32 {
-        if self.data.query.is_empty() && self.data.subject.is_empty() {
+    pub(crate) fn distance(&self) -> i32 {
+        if self.query_len == 0 && self.subject_len == 0 {
             return 0;
         }
         match self.metric {
             Metric::Similarity => {
-                if self.data.query.is_empty() || self.data.subject.is_empty() {
-                    let max_len = [self.data.query.len(), self.data.subject.len()]
+                if self.query_len == 0 || self.subject_len == 0 {
+                    let max_len = [self.query_len, self.subject_len]
                         .iter()
                         .max()
                         .copied()
@@ -103,7 +143,7 @@ impl GlobalAlignmentModel {
                     return (max_len * self.mismatch) as i32;
                 }
                 let similarity = self.similarity();
-                let max_possible = [self.data.query.len(), self.data.subject.len()]
+                let max_possible = [self.query_len, self.subject_len]
                     .iter()
                     .max()
                     .copied()
@@ -111,25 +151,20 @@ impl GlobalAlignmentModel {
                     * self.identity;
                 max_possible as i32 - similarity.abs()
             }
-            Metric::Distance => {
-                let score_matrix = self.data.score_matrix();
-                let i = self.data.query.len();
-                let j = self.data.subject.len();
-                score_matrix[i][j]
-            }
+            Metric::Distance => self.final_score,
         }
     }
 
-    pub fn normalized_similarity(&self) -> f64 {
+    pub(crate) fn normalized_similarity(&self) -> f64 {
         match self.metric {
             Metric::Similarity => {
                 let raw_sim = (self.similarity()) as f64;
-                let max_length = [self.data.query.len(), self.data.subject.len()]
+                let max_length = [self.query_len, self.subject_len]
                     .iter()
                     .max()
                     .copied()
                     .unwrap();
-                let min_length = [self.data.query.len(), self.data.subject.len()]
+                let min_length = [self.query_len, self.subject_len]
                     .iter()
                     .min()
                     .copied()
@@ -148,11 +183,11 @@ impl GlobalAlignmentModel {
         }
     }
 
-    pub fn normalized_distance(&self) -> f64 {
+    pub(crate) fn normalized_distance(&self) -> f64 {
         match self.metric {
             Metric::Similarity => 1_f64 - self.normalized_similarity(),
             Metric::Distance => {
-                let max_poss_dist = [self.data.query.len(), self.data.subject.len()]
+                let max_poss_dist = [self.query_len, self.subject_len]
                     .iter()
                     .max()
                     .copied()
@@ -164,10 +199,10 @@ impl GlobalAlignmentModel {
 }
 
 // This struct does the actual alignment
-pub struct GlobalAligner<'a> {
+pub struct GlobalAligner<'a, M: Index<usize, Output = [i32]> = Arr2D<i32>> {
     pub query_chars: &'a [char],
     pub subject_chars: &'a [char],
-    pub pointer_matrix: &'a Arr2D<i32>,
+    pub pointer_matrix: &'a M,
     pub stack: Vec<(Vec<char>, Vec<char>, usize, usize)>,
     pub all_alignments: bool,
     pub match_val: i32,
@@ -175,7 +210,7 @@ pub struct GlobalAligner<'a> {
     pub left_val: i32,
 }
 
-impl<'a> Iterator for GlobalAligner<'a> {
+impl<'a, M: Index<usize, Output = [i32]>> Iterator for GlobalAligner<'a, M> {
     type Item = (String, String);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+// Exact k-mer match between the query and one indexed target.
+// Positions are 0-based char offsets of the first residue of the k-mer, so
+// (query_pos, target_pos) can be handed straight to `XDrop::extend`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DiagonalSeed {
+    pub query_pos: usize,
+    pub target_pos: usize,
+}
+
+impl DiagonalSeed {
+    // Offset of the seed's diagonal, target_pos - query_pos
+    pub fn diagonal(&self) -> isize {
+        self.target_pos as isize - self.query_pos as isize
+    }
+}
+
+// Indexed target that shares at least one k-mer with the query.
+// `diagonal_hits` counts the seeds on `best_diagonal`, the diagonal holding
+// the most seeds (the lowest one on ties). Seeds are sorted by diagonal,
+// then by query position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate<I> {
+    pub id: I,
+    pub target: usize,
+    pub hits: usize,
+    pub diagonal_hits: usize,
+    pub best_diagonal: isize,
+    pub seeds: Vec<DiagonalSeed>,
+}
+
+// In-memory k-mer index over a collection of sequences.
+// With a window of 1 every k-mer is indexed. Larger windows keep only the
+// minimizer of each run of `window` consecutive k-mers, which shrinks the
+// index while any shared stretch of window + k - 1 residues still yields a
+// seed. Queries are sampled with the same scheme as the targets.
+pub struct KmerIndex<I> {
+    pub k: usize,
+    pub window: usize,
+    ids: Vec<I>,
+    lengths: Vec<usize>,
+    postings: HashMap<String, Vec<(usize, usize)>>,
+}
+
+impl<I: Clone> KmerIndex<I> {
+    pub fn new<Q, R>(k: usize, records: R) -> Self
+    where
+        R: IntoIterator<Item = (I, Q)>,
+        Q: AsRef<str>,
+    {
+        Self::with_minimizers(k, 1, records)
+    }
+
+    pub fn with_minimizers<Q, R>(k: usize, window: usize, records: R) -> Self
+    where
+        R: IntoIterator<Item = (I, Q)>,
+        Q: AsRef<str>,
+    {
+        assert!(k > 0, "k-mer length must be at least 1");
+        assert!(window > 0, "minimizer window must be at least 1");
+        let mut index = KmerIndex {
+            k,
+            window,
+            ids: Vec::new(),
+            lengths: Vec::new(),
+            postings: HashMap::new(),
+        };
+        for (target, (id, sequence)) in records.into_iter().enumerate() {
+            let sequence: Vec<char> = sequence.as_ref().to_uppercase().chars().collect();
+            for position in index.sample(&sequence) {
+                let kmer: String = sequence[position..position + k].iter().collect();
+                index
+                    .postings
+                    .entry(kmer)
+                    .or_default()
+                    .push((target, position));
+            }
+            index.ids.push(id);
+            index.lengths.push(sequence.len());
+        }
+        index
+    }
+
+    // Number of indexed targets
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn id(&self, target: usize) -> &I {
+        &self.ids[target]
+    }
+
+    pub fn target_len(&self, target: usize) -> usize {
+        self.lengths[target]
+    }
+
+    // Number of distinct k-mers stored
+    pub fn kmer_count(&self) -> usize {
+        self.postings.len()
+    }
+
+    // Every (target, seed) pair for the query, sorted by target then seed
+    pub fn seeds(&self, query: &str) -> Vec<(usize, DiagonalSeed)> {
+        let query: Vec<char> = query.to_uppercase().chars().collect();
+        let mut seeds = Vec::new();
+        for query_pos in self.sample(&query) {
+            let kmer: String = query[query_pos..query_pos + self.k].iter().collect();
+            if let Some(postings) = self.postings.get(&kmer) {
+                seeds.extend(postings.iter().map(|&(target, target_pos)| {
+                    (
+                        target,
+                        DiagonalSeed {
+                            query_pos,
+                            target_pos,
+                        },
+                    )
+                }));
+            }
+        }
+        seeds.sort_unstable();
+        seeds
+    }
+
+    // Targets with at least `min_hits` seeds on their best diagonal, ranked by
+    // `diagonal_hits`, then total `hits`. Remaining ties keep index order.
+    pub fn candidates(&self, query: &str, min_hits: usize) -> Vec<Candidate<I>> {
+        let mut candidates = Vec::new();
+        let seeds = self.seeds(query);
+        for group in seeds.chunk_by(|a, b| a.0 == b.0) {
+            let target = group[0].0;
+            let mut seeds: Vec<DiagonalSeed> = group.iter().map(|(_, seed)| *seed).collect();
+            seeds.sort_unstable_by_key(|seed| (seed.diagonal(), seed.query_pos));
+
+            let (best_diagonal, diagonal_hits) = seeds
+                .chunk_by(|a, b| a.diagonal() == b.diagonal())
+                .map(|run| (run[0].diagonal(), run.len()))
+                .fold((0, 0), |best, run| if run.1 > best.1 { run } else { best });
+            if diagonal_hits < min_hits.max(1) {
+                continue;
+            }
+            candidates.push(Candidate {
+                id: self.ids[target].clone(),
+                target,
+                hits: seeds.len(),
+                diagonal_hits,
+                best_diagonal,
+                seeds,
+            });
+        }
+        candidates.sort_by(|a, b| {
+            (b.diagonal_hits, b.hits, a.target).cmp(&(a.diagonal_hits, a.hits, b.target))
+        });
+        candidates
+    }
+
+    // Start positions of the k-mers kept for a sequence, in increasing order
+    fn sample(&self, sequence: &[char]) -> Vec<usize> {
+        if sequence.len() < self.k {
+            return Vec::new();
+        }
+        let count = sequence.len() - self.k + 1;
+        if self.window == 1 {
+            return (0..count).collect();
+        }
+        let hashes: Vec<u64> = (0..count)
+            .map(|p| kmer_hash(&sequence[p..p + self.k]))
+            .collect();
+        // The chosen position never moves left as the window slides, so
+        // skipping repeats is enough to keep positions unique and sorted.
+        // Sequences shorter than one full window still get their minimizer.
+        let mut positions: Vec<usize> = Vec::new();
+        for start in 0..count.saturating_sub(self.window - 1).max(1) {
+            let end = (start + self.window).min(count);
+            let chosen = (start..end).min_by_key(|&p| (hashes[p], p)).unwrap();
+            if positions.last() != Some(&chosen) {
+                positions.push(chosen);
+            }
+        }
+        positions
+    }
+}
+
+// FNV-1a over the chars followed by a splitmix64 finaliser. Fixed constants
+// keep minimizer choice identical across runs and platforms.
+fn kmer_hash(kmer: &[char]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &c in kmer {
+        hash ^= c as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
@@ -1,5 +1,6 @@
 use crate::align::{AlignmentData, PointerValues};
 use spindalis::utils::Arr2D;
+use std::ops::Index;
 
 #[derive(Clone)]
 pub enum LocalAlgorithm {
@@ -72,16 +73,16 @@ impl LocalAlignmentModel {
     }
 }
 
-pub struct LocalAligner<'a> {
+pub struct LocalAligner<'a, M: Index<usize, Output = [i32]> = Arr2D<i32>> {
     pub query_chars: &'a [char],
     pub subject_chars: &'a [char],
-    pub pointer_matrix: &'a Arr2D<i32>,
-    pub score_matrix: &'a Arr2D<i32>,
+    pub pointer_matrix: &'a M,
+    pub score_matrix: &'a M,
     pub stack: Vec<(Vec<char>, Vec<char>, usize, usize)>,
     pub all_alignments: bool,
 }
 
-impl<'a> Iterator for LocalAligner<'a> {
+impl<'a, M: Index<usize, Output = [i32]>> Iterator for LocalAligner<'a, M> {
     type Item = (String, String);
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -3,9 +3,22 @@ use crate::align::local_base::LocalAlignmentModel;
 use spindalis::utils::Arr2D;
 pub mod scoring;
 
+pub mod batch;
+pub mod circular;
 pub mod edit;
+pub mod fuzzy;
+pub mod genetic_code;
 pub mod global_base;
+pub mod kmer_index;
 pub mod local_base;
+pub mod pair_hmm;
+pub mod search;
+pub mod shuffle;
+pub mod simd;
+pub mod statistics;
+pub mod strand;
+pub mod wavefront;
+pub mod workspace;
 
 pub use scoring::Scoring;
 
@@ -0,0 +1,651 @@
+use crate::align::global_base::{GlobalAlgorithm, GlobalAlignmentModel, Metric};
+use crate::align::scoring::GeneralScoring;
+use crate::align::{AlignmentData, PointerValues, Scoring};
+use spindalis::utils::Arr2D;
+
+// Three-state pair HMM for global alignment, as in Durbin et al. chapter 4.
+// The match state emits an aligned pair, insert-x emits a query residue
+// against a gap and insert-y a subject residue against a gap. Begin behaves
+// like the match state and every state reaches End with probability `end`.
+//
+//   M -> M  1 - 2 * gap_open - end      X -> X  gap_extend
+//   M -> X  gap_open                    X -> M  1 - gap_extend - end
+//   M -> Y  gap_open                    (Y mirrors X)
+//
+// Aligned pairs and residues against gaps are emitted as set by
+// `emissions`. All work is done in log space so long sequences do not
+// underflow. `scores` only grades the alignments that come out, as
+// `NeedlemanWunsch` would with the same scoring.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PairHmm<S: Scoring + Clone> {
+    pub gap_open: f64,
+    pub gap_extend: f64,
+    pub end: f64,
+    pub emissions: Emissions,
+    pub scores: S,
+}
+
+// Emission probabilities of a pair HMM
+#[derive(Clone, Debug, PartialEq)]
+pub enum Emissions {
+    // Any two equal residues are emitted with `identical`, two different ones
+    // with `different`, and any residue against a gap with `background`
+    Uniform {
+        identical: f64,
+        different: f64,
+        background: f64,
+    },
+    // `pairs[a][b]` is the probability of emitting the a-th residue of the
+    // alphabet in the query aligned to the b-th in the subject, and
+    // `background[a]` of emitting the a-th against a gap. A residue outside
+    // the alphabet, such as N in DNA, stands for any residue: its emissions
+    // are summed over the alphabet, so it adds no information of its own.
+    Table {
+        alphabet: Vec<char>,
+        pairs: Arr2D<f64>,
+        background: Vec<f64>,
+    },
+}
+
+impl Default for PairHmm<GeneralScoring> {
+    fn default() -> Self {
+        let scores = GeneralScoring {
+            identity: 2,
+            mismatch: 1,
+            gap: 2,
+        };
+        Self::set_scores(&scores)
+    }
+}
+
+// States of the model, also used as traceback pointers
+const MATCH: usize = 0;
+const INSERT_X: usize = 1;
+const INSERT_Y: usize = 2;
+
+// Viterbi path of a pair HMM. `model` holds the alignment in the same form as
+// `NeedlemanWunsch` with the HMM's `scores`, and its `similarity` and
+// `distance` score the path under that scoring. The path need not be the
+// best `NeedlemanWunsch` alignment, so it may score worse than one.
+pub struct PairHmmAlignment {
+    pub log_probability: f64,
+    pub model: GlobalAlignmentModel,
+}
+
+// Maximum expected accuracy alignment. `expected_pairs` is the sum of the
+// posterior probabilities of the aligned pairs, i.e. the expected number of
+// correctly aligned pairs, and `expected_accuracy` divides it by the length
+// of the shorter sequence (1.0 when either sequence is empty). `model` is
+// laid out and scored as for `viterbi`.
+pub struct MeaAlignment {
+    pub expected_pairs: f64,
+    pub expected_accuracy: f64,
+    pub model: GlobalAlignmentModel,
+}
+
+// Posterior probabilities that residues are aligned to each other.
+// `matched` has the shape of an alignment score matrix: matched[i][j] is the
+// probability that query[i - 1] is aligned to subject[j - 1], and row and
+// column 0 are zero. `log_likelihood` is the forward log probability.
+pub struct PosteriorMatrix {
+    pub log_likelihood: f64,
+    pub matched: Arr2D<f64>,
+}
+
+impl PosteriorMatrix {
+    // Probability that query[i - 1] is aligned to a gap
+    pub fn query_gap(&self, i: usize) -> f64 {
+        let aligned: f64 = self.matched[i].iter().sum();
+        (1.0 - aligned).max(0.0)
+    }
+
+    // Probability that subject[j - 1] is aligned to a gap
+    pub fn subject_gap(&self, j: usize) -> f64 {
+        let aligned: f64 = (0..self.matched.height).map(|i| self.matched[i][j]).sum();
+        (1.0 - aligned).max(0.0)
+    }
+
+    // Posterior probability of each column of the model's first alignment,
+    // which must align the same query and subject
+    pub fn column_confidence(&self, model: &GlobalAlignmentModel) -> Vec<f64> {
+        let Some(alignment) = model.align().into_iter().next() else {
+            return Vec::new();
+        };
+        let (query, subject) = alignment.split_once('\n').unwrap_or((&alignment, ""));
+        let (mut i, mut j) = (0, 0);
+        query
+            .chars()
+            .zip(subject.chars())
+            .map(|(q, s)| match (q, s) {
+                (_, '-') => {
+                    i += 1;
+                    self.query_gap(i)
+                }
+                ('-', _) => {
+                    j += 1;
+                    self.subject_gap(j)
+                }
+                _ => {
+                    i += 1;
+                    j += 1;
+                    self.matched[i][j]
+                }
+            })
+            .collect()
+    }
+}
+
+// Natural logs of the model parameters. Emissions are indexed by residue,
+// with the sequences encoded as indices into the alphabet.
+struct LogParams {
+    match_to_match: f64,
+    gap_to_match: f64,
+    gap_open: f64,
+    gap_extend: f64,
+    end: f64,
+    pairs: Arr2D<f64>,
+    background: Vec<f64>,
+}
+
+impl LogParams {
+    fn pair(&self, query: usize, subject: usize) -> f64 {
+        self.pairs[query][subject]
+    }
+}
+
+// ln(sum(exp(values))), treating an all -inf input as probability zero
+fn log_sum(values: &[f64]) -> f64 {
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values.iter().map(|v| (v - max).exp()).sum::<f64>().ln()
+}
+
+impl<S: Scoring + Clone> PairHmm<S> {
+    // Uniform DNA background, with 80% of aligned pairs identical
+    pub fn set_scores(scores: &S) -> Self {
+        Self {
+            gap_open: 0.05,
+            gap_extend: 0.4,
+            end: 0.001,
+            emissions: Emissions::Uniform {
+                identical: 0.8 / 4.0,
+                different: 0.2 / 12.0,
+                background: 0.25,
+            },
+            scores: scores.clone(),
+        }
+    }
+
+    pub fn gap_open(&self, value: f64) -> Self {
+        Self {
+            gap_open: value,
+            gap_extend: self.gap_extend,
+            end: self.end,
+            emissions: self.emissions.clone(),
+            scores: self.scores.clone(),
+        }
+    }
+
+    pub fn gap_extend(&self, value: f64) -> Self {
+        Self {
+            gap_open: self.gap_open,
+            gap_extend: value,
+            end: self.end,
+            emissions: self.emissions.clone(),
+            scores: self.scores.clone(),
+        }
+    }
+
+    pub fn end(&self, value: f64) -> Self {
+        Self {
+            gap_open: self.gap_open,
+            gap_extend: self.gap_extend,
+            end: value,
+            emissions: self.emissions.clone(),
+            scores: self.scores.clone(),
+        }
+    }
+
+    // Same emissions for every residue: `match_emission` for an identical
+    // pair, `mismatch_emission` for any other pair, and `background` against
+    // a gap
+    pub fn emissions(&self, match_emission: f64, mismatch_emission: f64, background: f64) -> Self {
+        Self {
+            gap_open: self.gap_open,
+            gap_extend: self.gap_extend,
+            end: self.end,
+            emissions: Emissions::Uniform {
+                identical: match_emission,
+                different: mismatch_emission,
+                background,
+            },
+            scores: self.scores.clone(),
+        }
+    }
+
+    // Emissions over an alphabet: `pairs[a][b]` for the a-th residue of the
+    // query aligned to the b-th of the subject, and `background[a]` for the
+    // a-th against a gap, e.g. a substitution matrix's joint probabilities
+    // with its residue frequencies
+    pub fn emission_table(&self, alphabet: &str, pairs: Arr2D<f64>, background: &[f64]) -> Self {
+        let alphabet: Vec<char> = alphabet.to_uppercase().chars().collect();
+        let n = alphabet.len();
+        assert!(
+            pairs.height == n && pairs.width == n && background.len() == n,
+            "emission table must be {n} x {n} with {n} background frequencies to match the alphabet"
+        );
+        Self {
+            gap_open: self.gap_open,
+            gap_extend: self.gap_extend,
+            end: self.end,
+            emissions: Emissions::Table {
+                alphabet,
+                pairs,
+                background: background.to_vec(),
+            },
+            scores: self.scores.clone(),
+        }
+    }
+
+    // Most probable alignment
+    pub fn viterbi(&self, query: &str, subject: &str) -> PairHmmAlignment {
+        let query: Vec<char> = query.to_uppercase().chars().collect();
+        let subject: Vec<char> = subject.to_uppercase().chars().collect();
+        let (p, x, y) = self.log_params(&query, &subject);
+        let (m, n) = (query.len(), subject.len());
+
+        let mut scores = [(); 3].map(|_| Arr2D::full(f64::NEG_INFINITY, m + 1, n + 1));
+        let mut pointers = [(); 3].map(|_| Arr2D::full(MATCH, m + 1, n + 1));
+        scores[MATCH][0][0] = 0.0;
+        for i in 0..=m {
+            for j in 0..=n {
+                if i > 0 && j > 0 {
+                    let (best, state) = best_of([
+                        p.match_to_match + scores[MATCH][i - 1][j - 1],
+                        p.gap_to_match + scores[INSERT_X][i - 1][j - 1],
+                        p.gap_to_match + scores[INSERT_Y][i - 1][j - 1],
+                    ]);
+                    scores[MATCH][i][j] = p.pair(x[i - 1], y[j - 1]) + best;
+                    pointers[MATCH][i][j] = state;
+                }
+                if i > 0 {
+                    let (best, state) = best_of([
+                        p.gap_open + scores[MATCH][i - 1][j],
+                        p.gap_extend + scores[INSERT_X][i - 1][j],
+                        f64::NEG_INFINITY,
+                    ]);
+                    scores[INSERT_X][i][j] = p.background[x[i - 1]] + best;
+                    pointers[INSERT_X][i][j] = state;
+                }
+                if j > 0 {
+                    let (best, state) = best_of([
+                        p.gap_open + scores[MATCH][i][j - 1],
+                        f64::NEG_INFINITY,
+                        p.gap_extend + scores[INSERT_Y][i][j - 1],
+                    ]);
+                    scores[INSERT_Y][i][j] = p.background[y[j - 1]] + best;
+                    pointers[INSERT_Y][i][j] = state;
+                }
+            }
+        }
+
+        let (best, mut state) = best_of([
+            scores[MATCH][m][n],
+            scores[INSERT_X][m][n],
+            scores[INSERT_Y][m][n],
+        ]);
+        let mut steps = Vec::with_capacity(m + n);
+        let (mut i, mut j) = (m, n);
+        while i > 0 || j > 0 {
+            let previous = pointers[state][i][j];
+            match state {
+                MATCH => {
+                    steps.push(PointerValues::Match as i32);
+                    i -= 1;
+                    j -= 1;
+                }
+                INSERT_X => {
+                    steps.push(PointerValues::Up as i32);
+                    i -= 1;
+                }
+                _ => {
+                    steps.push(PointerValues::Left as i32);
+                    j -= 1;
+                }
+            }
+            state = previous;
+        }
+        steps.reverse();
+
+        PairHmmAlignment {
+            log_probability: p.end + best,
+            model: path_model(query, subject, &steps, &self.scores),
+        }
+    }
+
+    // Log probability of the two sequences summed over every alignment
+    pub fn forward(&self, query: &str, subject: &str) -> f64 {
+        let query: Vec<char> = query.to_uppercase().chars().collect();
+        let subject: Vec<char> = subject.to_uppercase().chars().collect();
+        let (p, x, y) = self.log_params(&query, &subject);
+        let forward = self.forward_matrices(&x, &y, &p);
+        let (m, n) = (query.len(), subject.len());
+        p.end
+            + log_sum(&[
+                forward[MATCH][m][n],
+                forward[INSERT_X][m][n],
+                forward[INSERT_Y][m][n],
+            ])
+    }
+
+    // Alignment with the highest sum of posterior match probabilities, as in
+    // ProbCons. Gaps score nothing, so the recurrence is
+    //   A[i][j] = max(A[i-1][j-1] + P[i][j], A[i-1][j], A[i][j-1])
+    // with the usual match, then up, then left preference on ties.
+    pub fn mea(&self, query: &str, subject: &str) -> MeaAlignment {
+        let posterior = self.posterior(query, subject);
+        let query: Vec<char> = query.to_uppercase().chars().collect();
+        let subject: Vec<char> = subject.to_uppercase().chars().collect();
+        let (m, n) = (query.len(), subject.len());
+
+        let mut scores = Arr2D::full(0.0, m + 1, n + 1);
+        let mut pointers = Arr2D::full(PointerValues::Left as i32, m + 1, n + 1);
+        for i in 1..=m {
+            pointers[i][0] = PointerValues::Up as i32;
+        }
+        for i in 1..=m {
+            for j in 1..=n {
+                let identity = scores[i - 1][j - 1] + posterior.matched[i][j];
+                let up = scores[i - 1][j];
+                let left = scores[i][j - 1];
+                let mut cell = (identity, PointerValues::Match as i32);
+                if up > cell.0 {
+                    cell = (up, PointerValues::Up as i32);
+                }
+                if left > cell.0 {
+                    cell = (left, PointerValues::Left as i32);
+                }
+                scores[i][j] = cell.0;
+                pointers[i][j] = cell.1;
+            }
+        }
+
+        let mut steps = Vec::with_capacity(m + n);
+        let (mut i, mut j) = (m, n);
+        while i > 0 || j > 0 {
+            let step = pointers[i][j];
+            steps.push(step);
+            if step != PointerValues::Left as i32 {
+                i -= 1;
+            }
+            if step != PointerValues::Up as i32 {
+                j -= 1;
+            }
+        }
+        steps.reverse();
+
+        let expected_pairs = scores[m][n];
+        let shorter = m.min(n);
+        MeaAlignment {
+            expected_pairs,
+            expected_accuracy: if shorter == 0 {
+                1.0
+            } else {
+                expected_pairs / shorter as f64
+            },
+            model: path_model(query, subject, &steps, &self.scores),
+        }
+    }
+
+    // Posterior match probabilities from the forward and backward algorithms
+    pub fn posterior(&self, query: &str, subject: &str) -> PosteriorMatrix {
+        let query: Vec<char> = query.to_uppercase().chars().collect();
+        let subject: Vec<char> = subject.to_uppercase().chars().collect();
+        let (p, x, y) = self.log_params(&query, &subject);
+        let (m, n) = (query.len(), subject.len());
+        let forward = self.forward_matrices(&x, &y, &p);
+        let backward = self.backward_matrices(&x, &y, &p);
+        let log_likelihood = p.end
+            + log_sum(&[
+                forward[MATCH][m][n],
+                forward[INSERT_X][m][n],
+                forward[INSERT_Y][m][n],
+            ]);
+
+        let mut matched = Arr2D::full(0.0, m + 1, n + 1);
+        for i in 1..=m {
+            for j in 1..=n {
+                let log_posterior = forward[MATCH][i][j] + backward[MATCH][i][j] - log_likelihood;
+                matched[i][j] = log_posterior.exp().min(1.0);
+            }
+        }
+        PosteriorMatrix {
+            log_likelihood,
+            matched,
+        }
+    }
+
+    // Log parameters, and the query and subject as indices into the
+    // emission alphabet. Residues outside it get the extra index n, whose
+    // emissions are the sums over the alphabet.
+    fn log_params(&self, query: &[char], subject: &[char]) -> (LogParams, Vec<usize>, Vec<usize>) {
+        let transitions = [self.gap_open, self.gap_extend, self.end];
+        assert!(
+            transitions.iter().all(|p| (0.0..=1.0).contains(p)),
+            "pair HMM parameters must be probabilities"
+        );
+        assert!(
+            2.0 * self.gap_open + self.end <= 1.0 && self.gap_extend + self.end <= 1.0,
+            "pair HMM transitions out of a state must not exceed 1"
+        );
+
+        let (alphabet, pairs, background) = match &self.emissions {
+            Emissions::Uniform {
+                identical,
+                different,
+                background,
+            } => {
+                // Only the residues that occur matter
+                let mut alphabet: Vec<char> = query.iter().chain(subject).copied().collect();
+                alphabet.sort_unstable();
+                alphabet.dedup();
+                let n = alphabet.len();
+                let mut pairs = Arr2D::full(*different, n, n);
+                for a in 0..n {
+                    pairs[a][a] = *identical;
+                }
+                (alphabet, pairs, vec![*background; n])
+            }
+            Emissions::Table {
+                alphabet,
+                pairs,
+                background,
+            } => (alphabet.clone(), pairs.clone(), background.clone()),
+        };
+        let n = alphabet.len();
+        assert!(
+            (0..n).all(|a| (0..n).all(|b| (0.0..=1.0).contains(&pairs[a][b])))
+                && background.iter().all(|p| (0.0..=1.0).contains(p)),
+            "pair HMM parameters must be probabilities"
+        );
+        let mut any = Arr2D::full(0.0, n + 1, n + 1);
+        for a in 0..n {
+            for b in 0..n {
+                any[a][b] = pairs[a][b];
+                any[a][n] += pairs[a][b];
+                any[n][b] += pairs[a][b];
+                any[n][n] += pairs[a][b];
+            }
+        }
+        let mut log_pairs = Arr2D::full(0.0, n + 1, n + 1);
+        for a in 0..=n {
+            for b in 0..=n {
+                log_pairs[a][b] = any[a][b].ln();
+            }
+        }
+        let mut background = background;
+        background.push(background.iter().sum());
+        let encode = |sequence: &[char]| -> Vec<usize> {
+            sequence
+                .iter()
+                .map(|c| alphabet.iter().position(|a| a == c).unwrap_or(n))
+                .collect()
+        };
+
+        let params = LogParams {
+            match_to_match: (1.0 - 2.0 * self.gap_open - self.end).ln(),
+            gap_to_match: (1.0 - self.gap_extend - self.end).ln(),
+            gap_open: self.gap_open.ln(),
+            gap_extend: self.gap_extend.ln(),
+            end: self.end.ln(),
+            pairs: log_pairs,
+            background: background.iter().map(|p| p.ln()).collect(),
+        };
+        (params, encode(query), encode(subject))
+    }
+
+    // f[state][i][j]: log probability of emitting query[..i] and subject[..j]
+    // and ending in `state`
+    fn forward_matrices(
+        &self,
+        query: &[usize],
+        subject: &[usize],
+        p: &LogParams,
+    ) -> [Arr2D<f64>; 3] {
+        let (m, n) = (query.len(), subject.len());
+        let mut f = [(); 3].map(|_| Arr2D::full(f64::NEG_INFINITY, m + 1, n + 1));
+        f[MATCH][0][0] = 0.0;
+        for i in 0..=m {
+            for j in 0..=n {
+                if i > 0 && j > 0 {
+                    f[MATCH][i][j] = p.pair(query[i - 1], subject[j - 1])
+                        + log_sum(&[
+                            p.match_to_match + f[MATCH][i - 1][j - 1],
+                            p.gap_to_match + f[INSERT_X][i - 1][j - 1],
+                            p.gap_to_match + f[INSERT_Y][i - 1][j - 1],
+                        ]);
+                }
+                if i > 0 {
+                    f[INSERT_X][i][j] = p.background[query[i - 1]]
+                        + log_sum(&[
+                            p.gap_open + f[MATCH][i - 1][j],
+                            p.gap_extend + f[INSERT_X][i - 1][j],
+                        ]);
+                }
+                if j > 0 {
+                    f[INSERT_Y][i][j] = p.background[subject[j - 1]]
+                        + log_sum(&[
+                            p.gap_open + f[MATCH][i][j - 1],
+                            p.gap_extend + f[INSERT_Y][i][j - 1],
+                        ]);
+                }
+            }
+        }
+        f
+    }
+
+    // b[state][i][j]: log probability of emitting query[i..] and subject[j..]
+    // and reaching End, given the model is in `state` at (i, j)
+    fn backward_matrices(
+        &self,
+        query: &[usize],
+        subject: &[usize],
+        p: &LogParams,
+    ) -> [Arr2D<f64>; 3] {
+        let (m, n) = (query.len(), subject.len());
+        let mut b = [(); 3].map(|_| Arr2D::full(f64::NEG_INFINITY, m + 1, n + 1));
+        for i in (0..=m).rev() {
+            for j in (0..=n).rev() {
+                if i == m && j == n {
+                    for state in [MATCH, INSERT_X, INSERT_Y] {
+                        b[state][i][j] = p.end;
+                    }
+                    continue;
+                }
+                let diagonal = if i < m && j < n {
+                    p.pair(query[i], subject[j]) + b[MATCH][i + 1][j + 1]
+                } else {
+                    f64::NEG_INFINITY
+                };
+                let down = if i < m {
+                    p.background[query[i]] + b[INSERT_X][i + 1][j]
+                } else {
+                    f64::NEG_INFINITY
+                };
+                let right = if j < n {
+                    p.background[subject[j]] + b[INSERT_Y][i][j + 1]
+                } else {
+                    f64::NEG_INFINITY
+                };
+                b[MATCH][i][j] = log_sum(&[
+                    p.match_to_match + diagonal,
+                    p.gap_open + down,
+                    p.gap_open + right,
+                ]);
+                b[INSERT_X][i][j] = log_sum(&[p.gap_to_match + diagonal, p.gap_extend + down]);
+                b[INSERT_Y][i][j] = log_sum(&[p.gap_to_match + diagonal, p.gap_extend + right]);
+            }
+        }
+        b
+    }
+}
+
+// Highest value and its state. Ties prefer match, then insert-x, then
+// insert-y, the same order as the pointer preference elsewhere.
+fn best_of(values: [f64; 3]) -> (f64, usize) {
+    let mut best = (values[MATCH], MATCH);
+    for state in [INSERT_X, INSERT_Y] {
+        if values[state] > best.0 {
+            best = (values[state], state);
+        }
+    }
+    best
+}
+
+// Global model for a fixed alignment path, given as the `PointerValues` of
+// each step from (0, 0) to the end. Only the cells on the path are set; the
+// score matrix holds the running score of the path under `scores`, so the
+// model scores like `NeedlemanWunsch` output that took this path.
+pub(crate) fn path_model<S: Scoring>(
+    query: Vec<char>,
+    subject: Vec<char>,
+    steps: &[i32],
+    scores: &S,
+) -> GlobalAlignmentModel {
+    let gap = scores.get_gap_score() as i32;
+    let (m, n) = (query.len(), subject.len());
+    let mut score_matrix = Arr2D::full(0, m + 1, n + 1);
+    let mut pointer_matrix = Arr2D::full(0, m + 1, n + 1);
+    let (mut i, mut j, mut score) = (0, 0, 0);
+    for &step in steps {
+        if step == PointerValues::Match as i32 {
+            i += 1;
+            j += 1;
+            score += scores.pair_score(query[i - 1], subject[j - 1]);
+        } else if step == PointerValues::Up as i32 {
+            i += 1;
+            score -= gap;
+        } else {
+            j += 1;
+            score -= gap;
+        }
+        score_matrix[i][j] = score;
+        pointer_matrix[i][j] = step;
+    }
+    GlobalAlignmentModel {
+        data: AlignmentData {
+            query,
+            subject,
+            score_matrix: vec![score_matrix],
+            pointer_matrix: vec![pointer_matrix],
+        },
+        aligner: GlobalAlgorithm::NeedlemanWunsch,
+        metric: Metric::Similarity,
+        identity: scores.get_match_score(),
+        mismatch: scores.get_mismatch_score(),
+        gap: scores.get_gap_score(),
+        all_alignments: false,
+    }
+}
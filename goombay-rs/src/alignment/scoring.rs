@@ -4,6 +4,22 @@ pub trait Scoring {
     fn get_gap_score(&self) -> usize;
     fn get_extended_gap_score(&self) -> usize;
     fn get_transpose_score(&self) -> usize;
+
+    // Score for aligning residue `a` against `b`. Plain scorings compare the
+    // chars directly; alphabet-aware scorings override this.
+    fn pair_score(&self, a: char, b: char) -> i32 {
+        if a == b {
+            self.get_match_score() as i32
+        } else {
+            -(self.get_mismatch_score() as i32)
+        }
+    }
+
+    // Cost of aligning `a` against `b` for distance-based aligners, zero for
+    // an exact match when the match score is zero
+    fn pair_cost(&self, a: char, b: char) -> i32 {
+        self.get_match_score() as i32 - self.pair_score(a, b)
+    }
 }
 
 #[derive(Clone)]
@@ -106,3 +122,85 @@ impl Scoring for ExtendedGapScoring {
         0_usize
     }
 }
+
+// Nucleotide scoring that understands IUPAC ambiguity codes.
+// Two codes score the expected score of resolving each to one of its bases
+// uniformly at random, rounded to the nearest integer. So A vs R scores half
+// way between a match and a mismatch, while A vs Y is a plain mismatch.
+// N against anything scores `n_score`. U is read as T, and chars that are
+// not nucleotide codes fall back to a plain comparison.
+#[derive(Clone)]
+pub struct NucleotideScoring {
+    pub identity: usize,
+    pub mismatch: usize,
+    pub gap: usize,
+    pub n_score: i32,
+}
+
+impl Default for NucleotideScoring {
+    fn default() -> Self {
+        Self {
+            identity: 2,
+            mismatch: 1,
+            gap: 2,
+            n_score: 0,
+        }
+    }
+}
+
+impl Scoring for NucleotideScoring {
+    fn get_match_score(&self) -> usize {
+        self.identity
+    }
+    fn get_mismatch_score(&self) -> usize {
+        self.mismatch
+    }
+    fn get_gap_score(&self) -> usize {
+        self.gap
+    }
+    fn get_extended_gap_score(&self) -> usize {
+        0_usize
+    }
+    fn get_transpose_score(&self) -> usize {
+        0_usize
+    }
+    fn pair_score(&self, a: char, b: char) -> i32 {
+        let (Some(a_bases), Some(b_bases)) = (iupac_bases(a), iupac_bases(b)) else {
+            return if a == b {
+                self.identity as i32
+            } else {
+                -(self.mismatch as i32)
+            };
+        };
+        if a_bases == 0b1111 || b_bases == 0b1111 {
+            return self.n_score;
+        }
+        // Chance that both codes resolve to the same base
+        let shared = (a_bases & b_bases).count_ones() as f64;
+        let p = shared / (a_bases.count_ones() * b_bases.count_ones()) as f64;
+        (p * self.identity as f64 - (1.0 - p) * self.mismatch as f64).round() as i32
+    }
+}
+
+// Set of bases an IUPAC code stands for, as bits A=1, C=2, G=4, T=8
+pub fn iupac_bases(code: char) -> Option<u8> {
+    let bases = match code.to_ascii_uppercase() {
+        'A' => 0b0001,
+        'C' => 0b0010,
+        'G' => 0b0100,
+        'T' | 'U' => 0b1000,
+        'R' => 0b0101,
+        'Y' => 0b1010,
+        'S' => 0b0110,
+        'W' => 0b1001,
+        'K' => 0b1100,
+        'M' => 0b0011,
+        'B' => 0b1110,
+        'D' => 0b1101,
+        'H' => 0b1011,
+        'V' => 0b0111,
+        'N' => 0b1111,
+        _ => return None,
+    };
+    Some(bases)
+}
@@ -0,0 +1,218 @@
+use crate::align::edit::smith_waterman::SmithWaterman;
+use crate::align::edit::striped_smith_waterman::StripedSmithWaterman;
+use crate::align::local_base::LocalAlignmentModel;
+use crate::align::scoring::GeneralScoring;
+use crate::align::{LocalAlignmentMatrix, Scoring};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+// One database record that scored at or above the cutoff.
+// Coordinates are 0-based and half-open, e.g. query[query_start..query_end].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchHit<I> {
+    pub id: I,
+    pub score: i32,
+    pub query_start: usize,
+    pub query_end: usize,
+    pub subject_start: usize,
+    pub subject_end: usize,
+    pub alignment: Option<String>,
+}
+
+// Scans one query against a stream of (id, sequence) records.
+// Every record is scored with the striped engine; the full Smith-Waterman
+// matrix is only built for the hits that are reported.
+pub struct LocalSearch<S: Scoring + Clone> {
+    pub scores: S,
+    pub top_n: usize,
+    pub min_score: i32,
+    pub with_alignment: bool,
+}
+
+impl Default for LocalSearch<GeneralScoring> {
+    fn default() -> Self {
+        let scores = GeneralScoring {
+            identity: 2,
+            mismatch: 1,
+            gap: 2,
+        };
+        Self {
+            scores,
+            top_n: 10,
+            min_score: 1,
+            with_alignment: false,
+        }
+    }
+}
+
+impl<S: Scoring + Clone> LocalSearch<S>
+where
+    SmithWaterman<S>: LocalAlignmentMatrix<S>,
+{
+    pub fn set_scores(scores: &S) -> Self {
+        Self {
+            scores: scores.clone(),
+            top_n: 10,
+            min_score: 1,
+            with_alignment: false,
+        }
+    }
+
+    pub fn top_n(&self, value: usize) -> Self {
+        Self {
+            scores: self.scores.clone(),
+            top_n: value,
+            min_score: self.min_score,
+            with_alignment: self.with_alignment,
+        }
+    }
+
+    pub fn min_score(&self, value: i32) -> Self {
+        Self {
+            scores: self.scores.clone(),
+            top_n: self.top_n,
+            min_score: value,
+            with_alignment: self.with_alignment,
+        }
+    }
+
+    pub fn with_alignment(&self, value: bool) -> Self {
+        Self {
+            scores: self.scores.clone(),
+            top_n: self.top_n,
+            min_score: self.min_score,
+            with_alignment: value,
+        }
+    }
+
+    // Returns the best `top_n` hits sorted by descending score. Ties keep the
+    // order in which the records were read. Only `top_n` records are held in
+    // memory at any time.
+    pub fn search<I, Q, R>(&self, query: &str, records: R) -> Vec<SearchHit<I>>
+    where
+        R: IntoIterator<Item = (I, Q)>,
+        Q: AsRef<str>,
+    {
+        if self.top_n == 0 {
+            return Vec::new();
+        }
+        let mut profile = StripedSmithWaterman::set_scores(&self.scores).profile(query);
+        // Min-heap on (score, earliest record) so the weakest kept hit is on top
+        let mut heap: BinaryHeap<Reverse<Ranked<I>>> = BinaryHeap::with_capacity(self.top_n + 1);
+
+        for (index, (id, sequence)) in records.into_iter().enumerate() {
+            let score = profile.score(sequence.as_ref());
+            if score < self.min_score {
+                continue;
+            }
+            if heap.len() == self.top_n {
+                let weakest = &heap.peek().unwrap().0;
+                if (score, Reverse(index)) <= (weakest.score, Reverse(weakest.index)) {
+                    continue;
+                }
+                heap.pop();
+            }
+            heap.push(Reverse(Ranked {
+                score,
+                index,
+                id,
+                sequence: sequence.as_ref().to_string(),
+            }));
+        }
+
+        let mut ranked: Vec<Ranked<I>> = heap.into_iter().map(|Reverse(r)| r).collect();
+        ranked.sort_by(|a, b| b.cmp(a));
+        ranked
+            .into_iter()
+            .map(|r| self.build_hit(query, r.id, &r.sequence, r.score))
+            .collect()
+    }
+
+    // Lazily yields every hit at or above `min_score` in database order.
+    // Nothing is ranked or retained, so `top_n` is ignored.
+    pub fn scan<'a, I, Q, R>(
+        &'a self,
+        query: &'a str,
+        records: R,
+    ) -> impl Iterator<Item = SearchHit<I>> + 'a
+    where
+        R: IntoIterator<Item = (I, Q)>,
+        R::IntoIter: 'a,
+        Q: AsRef<str>,
+    {
+        let mut profile = StripedSmithWaterman::set_scores(&self.scores).profile(query);
+        records.into_iter().filter_map(move |(id, sequence)| {
+            let score = profile.score(sequence.as_ref());
+            if score < self.min_score {
+                return None;
+            }
+            Some(self.build_hit(query, id, sequence.as_ref(), score))
+        })
+    }
+
+    fn build_hit<I>(&self, query: &str, id: I, subject: &str, score: i32) -> SearchHit<I> {
+        let model = SmithWaterman::set_scores(&self.scores).calculate_matrix(query, subject);
+        let (query_start, query_end, subject_start, subject_end, alignment) =
+            hit_coordinates(&model);
+        SearchHit {
+            id,
+            score,
+            query_start,
+            query_end,
+            subject_start,
+            subject_end,
+            alignment: if self.with_alignment { alignment } else { None },
+        }
+    }
+}
+
+// Recovers start and end coordinates from the first traceback of a model
+pub(crate) fn hit_coordinates(
+    model: &LocalAlignmentModel,
+) -> (usize, usize, usize, usize, Option<String>) {
+    // `align` pops start indices from the back, so the first alignment ends there
+    let Some(&(query_end, subject_end)) = model.start_indices.last() else {
+        return (0, 0, 0, 0, None);
+    };
+    let Some(alignment) = model.align().into_iter().next() else {
+        return (query_end, query_end, subject_end, subject_end, None);
+    };
+    let (qs, ss) = alignment.split_once('\n').unwrap();
+    let query_span = qs.chars().filter(|&c| c != '-').count();
+    let subject_span = ss.chars().filter(|&c| c != '-').count();
+    (
+        query_end - query_span,
+        query_end,
+        subject_end - subject_span,
+        subject_end,
+        Some(alignment),
+    )
+}
+
+struct Ranked<I> {
+    score: i32,
+    index: usize,
+    id: I,
+    sequence: String,
+}
+
+// Higher scores rank first, earlier records win ties
+impl<I> Ord for Ranked<I> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.score, Reverse(self.index)).cmp(&(other.score, Reverse(other.index)))
+    }
+}
+
+impl<I> PartialOrd for Ranked<I> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<I> PartialEq for Ranked<I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<I> Eq for Ranked<I> {}
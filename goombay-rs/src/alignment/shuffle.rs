@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+// Small seedable generator (SplitMix64). It is fast and has no dependencies,
+// and a given seed produces the same stream on every platform.
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        SeededRng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform integer in 0..bound
+    pub fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "bound must be at least 1");
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+}
+
+// What a shuffle keeps from the original sequence.
+// `Mononucleotide` keeps the count of each residue. `Dinucleotide` also keeps
+// the count of each adjacent pair, along with the first and last residue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shuffle {
+    Mononucleotide,
+    Dinucleotide,
+}
+
+pub fn shuffle(sequence: &str, kind: Shuffle, rng: &mut SeededRng) -> String {
+    let chars: Vec<char> = sequence.chars().collect();
+    match kind {
+        Shuffle::Mononucleotide => {
+            let mut chars = chars;
+            shuffle_slice(&mut chars, rng);
+            chars.into_iter().collect()
+        }
+        Shuffle::Dinucleotide => dinucleotide_shuffle(&chars, rng),
+    }
+}
+
+// Fisher-Yates
+fn shuffle_slice<T>(items: &mut [T], rng: &mut SeededRng) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, rng.below(i + 1));
+    }
+}
+
+// Altschul and Erickson (1985). Each adjacent pair is an edge between
+// residues, and the sequence is an Eulerian path through them. A random
+// spanning tree of "last exits" towards the final residue is drawn with
+// Wilson's algorithm; the remaining edges out of each residue are shuffled
+// with its last exit kept at the end, and walking the edges in that order
+// spells a random sequence with the same pair counts.
+fn dinucleotide_shuffle(chars: &[char], rng: &mut SeededRng) -> String {
+    if chars.len() <= 2 {
+        return chars.iter().collect();
+    }
+    let mut edges: BTreeMap<char, Vec<char>> = BTreeMap::new();
+    for pair in chars.windows(2) {
+        edges.entry(pair[0]).or_default().push(pair[1]);
+    }
+    let last = chars[chars.len() - 1];
+
+    // Index of the last exit edge of every residue in the tree
+    let mut exits: BTreeMap<char, usize> = BTreeMap::new();
+    let mut in_tree: BTreeMap<char, bool> = BTreeMap::new();
+    in_tree.insert(last, true);
+    let residues: Vec<char> = edges.keys().copied().collect();
+    for &start in &residues {
+        // Loop-erased random walk: later choices overwrite earlier ones, so
+        // only the last exit taken from each residue survives
+        let mut residue = start;
+        while !in_tree.get(&residue).copied().unwrap_or(false) {
+            let out = &edges[&residue];
+            let exit = rng.below(out.len());
+            exits.insert(residue, exit);
+            residue = out[exit];
+        }
+        let mut residue = start;
+        while !in_tree.get(&residue).copied().unwrap_or(false) {
+            in_tree.insert(residue, true);
+            residue = edges[&residue][exits[&residue]];
+        }
+    }
+
+    for (residue, out) in edges.iter_mut() {
+        match exits.get(residue) {
+            Some(&exit) => {
+                let kept = out.remove(exit);
+                shuffle_slice(out, rng);
+                out.push(kept);
+            }
+            None => shuffle_slice(out, rng),
+        }
+    }
+
+    let mut shuffled = String::with_capacity(chars.len());
+    let mut next: BTreeMap<char, usize> = BTreeMap::new();
+    let mut residue = chars[0];
+    shuffled.push(residue);
+    for _ in 1..chars.len() {
+        let position = next.entry(residue).or_default();
+        residue = edges[&residue][*position];
+        *position += 1;
+        shuffled.push(residue);
+    }
+    shuffled
+}
@@ -0,0 +1,368 @@
+// Vector lane abstractions used by the striped aligners.
+//
+// Each implementation wraps one register width and element type. Every
+// operation saturates instead of wrapping so that a kernel can detect when a
+// narrow lane type has overflowed and retry with a wider one.
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimdEngine {
+    Avx2,
+    Sse2,
+    Portable,
+}
+
+impl SimdEngine {
+    // Picks the widest instruction set the running CPU supports
+    pub fn detect() -> SimdEngine {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return SimdEngine::Avx2;
+            }
+            SimdEngine::Sse2
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            SimdEngine::Portable
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            SimdEngine::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "x86_64")]
+            SimdEngine::Sse2 => true,
+            #[cfg(not(target_arch = "x86_64"))]
+            SimdEngine::Avx2 | SimdEngine::Sse2 => false,
+            SimdEngine::Portable => true,
+        }
+    }
+
+    // Number of 8-bit lanes per register, the 16-bit lane count is half of this
+    pub fn byte_lanes(&self) -> usize {
+        match self {
+            SimdEngine::Avx2 => 32,
+            SimdEngine::Sse2 | SimdEngine::Portable => 16,
+        }
+    }
+}
+
+pub(crate) trait Lanes: Copy {
+    type Elem: Copy + Default;
+    const LANES: usize;
+
+    unsafe fn splat(value: Self::Elem) -> Self;
+    // `src` must hold at least `LANES` elements
+    unsafe fn load(src: &[Self::Elem]) -> Self;
+    unsafe fn adds(self, other: Self) -> Self;
+    unsafe fn subs(self, other: Self) -> Self;
+    unsafe fn max(self, other: Self) -> Self;
+    // Moves lane k into lane k + 1 and shifts a zero into lane 0
+    unsafe fn shift_lanes(self) -> Self;
+    // True when any lane of self is strictly greater than the same lane of other
+    unsafe fn any_gt(self, other: Self) -> bool;
+    unsafe fn hmax(self) -> i32;
+}
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy)]
+pub(crate) struct Sse2U8(__m128i);
+
+#[cfg(target_arch = "x86_64")]
+impl Lanes for Sse2U8 {
+    type Elem = u8;
+    const LANES: usize = 16;
+
+    #[inline(always)]
+    unsafe fn splat(value: u8) -> Self {
+        unsafe { Sse2U8(_mm_set1_epi8(value as i8)) }
+    }
+    #[inline(always)]
+    unsafe fn load(src: &[u8]) -> Self {
+        debug_assert!(src.len() >= Self::LANES);
+        unsafe { Sse2U8(_mm_loadu_si128(src.as_ptr() as *const __m128i)) }
+    }
+    #[inline(always)]
+    unsafe fn adds(self, other: Self) -> Self {
+        unsafe { Sse2U8(_mm_adds_epu8(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn subs(self, other: Self) -> Self {
+        unsafe { Sse2U8(_mm_subs_epu8(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn max(self, other: Self) -> Self {
+        unsafe { Sse2U8(_mm_max_epu8(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn shift_lanes(self) -> Self {
+        unsafe { Sse2U8(_mm_slli_si128::<1>(self.0)) }
+    }
+    #[inline(always)]
+    unsafe fn any_gt(self, other: Self) -> bool {
+        unsafe {
+            // SSE2 has no unsigned compare, a saturating subtract is zero unless a > b
+            let diff = _mm_subs_epu8(self.0, other.0);
+            _mm_movemask_epi8(_mm_cmpeq_epi8(diff, _mm_setzero_si128())) != 0xFFFF
+        }
+    }
+    #[inline(always)]
+    unsafe fn hmax(self) -> i32 {
+        let mut out = [0_u8; 16];
+        unsafe { _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, self.0) };
+        out.iter().copied().max().unwrap() as i32
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy)]
+pub(crate) struct Sse2I16(__m128i);
+
+#[cfg(target_arch = "x86_64")]
+impl Lanes for Sse2I16 {
+    type Elem = i16;
+    const LANES: usize = 8;
+
+    #[inline(always)]
+    unsafe fn splat(value: i16) -> Self {
+        unsafe { Sse2I16(_mm_set1_epi16(value)) }
+    }
+    #[inline(always)]
+    unsafe fn load(src: &[i16]) -> Self {
+        debug_assert!(src.len() >= Self::LANES);
+        unsafe { Sse2I16(_mm_loadu_si128(src.as_ptr() as *const __m128i)) }
+    }
+    #[inline(always)]
+    unsafe fn adds(self, other: Self) -> Self {
+        unsafe { Sse2I16(_mm_adds_epi16(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn subs(self, other: Self) -> Self {
+        unsafe { Sse2I16(_mm_subs_epi16(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn max(self, other: Self) -> Self {
+        unsafe { Sse2I16(_mm_max_epi16(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn shift_lanes(self) -> Self {
+        unsafe { Sse2I16(_mm_slli_si128::<2>(self.0)) }
+    }
+    #[inline(always)]
+    unsafe fn any_gt(self, other: Self) -> bool {
+        unsafe { _mm_movemask_epi8(_mm_cmpgt_epi16(self.0, other.0)) != 0 }
+    }
+    #[inline(always)]
+    unsafe fn hmax(self) -> i32 {
+        let mut out = [0_i16; 8];
+        unsafe { _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, self.0) };
+        out.iter().copied().max().unwrap() as i32
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy)]
+pub(crate) struct Avx2U8(__m256i);
+
+#[cfg(target_arch = "x86_64")]
+impl Lanes for Avx2U8 {
+    type Elem = u8;
+    const LANES: usize = 32;
+
+    #[inline(always)]
+    unsafe fn splat(value: u8) -> Self {
+        unsafe { Avx2U8(_mm256_set1_epi8(value as i8)) }
+    }
+    #[inline(always)]
+    unsafe fn load(src: &[u8]) -> Self {
+        debug_assert!(src.len() >= Self::LANES);
+        unsafe { Avx2U8(_mm256_loadu_si256(src.as_ptr() as *const __m256i)) }
+    }
+    #[inline(always)]
+    unsafe fn adds(self, other: Self) -> Self {
+        unsafe { Avx2U8(_mm256_adds_epu8(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn subs(self, other: Self) -> Self {
+        unsafe { Avx2U8(_mm256_subs_epu8(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn max(self, other: Self) -> Self {
+        unsafe { Avx2U8(_mm256_max_epu8(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn shift_lanes(self) -> Self {
+        // _mm256_slli_si256 shifts each 128-bit half separately, so the byte
+        // leaving the low half is carried across with a permute
+        unsafe {
+            let carry = _mm256_permute2x128_si256::<0x08>(self.0, self.0);
+            Avx2U8(_mm256_alignr_epi8::<15>(self.0, carry))
+        }
+    }
+    #[inline(always)]
+    unsafe fn any_gt(self, other: Self) -> bool {
+        unsafe {
+            let diff = _mm256_subs_epu8(self.0, other.0);
+            _mm256_movemask_epi8(_mm256_cmpeq_epi8(diff, _mm256_setzero_si256())) != -1
+        }
+    }
+    #[inline(always)]
+    unsafe fn hmax(self) -> i32 {
+        let mut out = [0_u8; 32];
+        unsafe { _mm256_storeu_si256(out.as_mut_ptr() as *mut __m256i, self.0) };
+        out.iter().copied().max().unwrap() as i32
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy)]
+pub(crate) struct Avx2I16(__m256i);
+
+#[cfg(target_arch = "x86_64")]
+impl Lanes for Avx2I16 {
+    type Elem = i16;
+    const LANES: usize = 16;
+
+    #[inline(always)]
+    unsafe fn splat(value: i16) -> Self {
+        unsafe { Avx2I16(_mm256_set1_epi16(value)) }
+    }
+    #[inline(always)]
+    unsafe fn load(src: &[i16]) -> Self {
+        debug_assert!(src.len() >= Self::LANES);
+        unsafe { Avx2I16(_mm256_loadu_si256(src.as_ptr() as *const __m256i)) }
+    }
+    #[inline(always)]
+    unsafe fn adds(self, other: Self) -> Self {
+        unsafe { Avx2I16(_mm256_adds_epi16(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn subs(self, other: Self) -> Self {
+        unsafe { Avx2I16(_mm256_subs_epi16(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn max(self, other: Self) -> Self {
+        unsafe { Avx2I16(_mm256_max_epi16(self.0, other.0)) }
+    }
+    #[inline(always)]
+    unsafe fn shift_lanes(self) -> Self {
+        unsafe {
+            let carry = _mm256_permute2x128_si256::<0x08>(self.0, self.0);
+            Avx2I16(_mm256_alignr_epi8::<14>(self.0, carry))
+        }
+    }
+    #[inline(always)]
+    unsafe fn any_gt(self, other: Self) -> bool {
+        unsafe { _mm256_movemask_epi8(_mm256_cmpgt_epi16(self.0, other.0)) != 0 }
+    }
+    #[inline(always)]
+    unsafe fn hmax(self) -> i32 {
+        let mut out = [0_i16; 16];
+        unsafe { _mm256_storeu_si256(out.as_mut_ptr() as *mut __m256i, self.0) };
+        out.iter().copied().max().unwrap() as i32
+    }
+}
+
+// Plain arrays with the same lane layout as SSE2. Used on targets without a
+// dedicated implementation; the loops are simple enough to auto-vectorise.
+#[derive(Clone, Copy)]
+pub(crate) struct PortableU8([u8; 16]);
+
+impl Lanes for PortableU8 {
+    type Elem = u8;
+    const LANES: usize = 16;
+
+    #[inline(always)]
+    unsafe fn splat(value: u8) -> Self {
+        PortableU8([value; 16])
+    }
+    #[inline(always)]
+    unsafe fn load(src: &[u8]) -> Self {
+        let mut out = [0_u8; 16];
+        out.copy_from_slice(&src[..16]);
+        PortableU8(out)
+    }
+    #[inline(always)]
+    unsafe fn adds(self, other: Self) -> Self {
+        PortableU8(std::array::from_fn(|k| {
+            self.0[k].saturating_add(other.0[k])
+        }))
+    }
+    #[inline(always)]
+    unsafe fn subs(self, other: Self) -> Self {
+        PortableU8(std::array::from_fn(|k| {
+            self.0[k].saturating_sub(other.0[k])
+        }))
+    }
+    #[inline(always)]
+    unsafe fn max(self, other: Self) -> Self {
+        PortableU8(std::array::from_fn(|k| self.0[k].max(other.0[k])))
+    }
+    #[inline(always)]
+    unsafe fn shift_lanes(self) -> Self {
+        PortableU8(std::array::from_fn(
+            |k| if k == 0 { 0 } else { self.0[k - 1] },
+        ))
+    }
+    #[inline(always)]
+    unsafe fn any_gt(self, other: Self) -> bool {
+        self.0.iter().zip(other.0.iter()).any(|(a, b)| a > b)
+    }
+    #[inline(always)]
+    unsafe fn hmax(self) -> i32 {
+        self.0.iter().copied().max().unwrap() as i32
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct PortableI16([i16; 8]);
+
+impl Lanes for PortableI16 {
+    type Elem = i16;
+    const LANES: usize = 8;
+
+    #[inline(always)]
+    unsafe fn splat(value: i16) -> Self {
+        PortableI16([value; 8])
+    }
+    #[inline(always)]
+    unsafe fn load(src: &[i16]) -> Self {
+        let mut out = [0_i16; 8];
+        out.copy_from_slice(&src[..8]);
+        PortableI16(out)
+    }
+    #[inline(always)]
+    unsafe fn adds(self, other: Self) -> Self {
+        PortableI16(std::array::from_fn(|k| {
+            self.0[k].saturating_add(other.0[k])
+        }))
+    }
+    #[inline(always)]
+    unsafe fn subs(self, other: Self) -> Self {
+        PortableI16(std::array::from_fn(|k| {
+            self.0[k].saturating_sub(other.0[k])
+        }))
+    }
+    #[inline(always)]
+    unsafe fn max(self, other: Self) -> Self {
+        PortableI16(std::array::from_fn(|k| self.0[k].max(other.0[k])))
+    }
+    #[inline(always)]
+    unsafe fn shift_lanes(self) -> Self {
+        PortableI16(std::array::from_fn(
+            |k| if k == 0 { 0 } else { self.0[k - 1] },
+        ))
+    }
+    #[inline(always)]
+    unsafe fn any_gt(self, other: Self) -> bool {
+        self.0.iter().zip(other.0.iter()).any(|(a, b)| a > b)
+    }
+    #[inline(always)]
+    unsafe fn hmax(self) -> i32 {
+        self.0.iter().copied().max().unwrap() as i32
+    }
+}
@@ -0,0 +1,432 @@
+use crate::align::Scoring;
+use crate::align::local_base::LocalAlignmentModel;
+use crate::align::shuffle::{SeededRng, Shuffle, shuffle};
+use std::collections::BTreeMap;
+
+// Karlin-Altschul parameters for local alignment scores. With query length m
+// and database length n, the expected number of hits scoring at least S by
+// chance is E = K * m * n * exp(-lambda * S). `h` is the relative entropy of
+// the aligned pairs in nats. Lengths are used as given, without BLAST's
+// edge-effect correction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KarlinAltschul {
+    pub lambda: f64,
+    pub k: f64,
+    pub h: f64,
+}
+
+// Significance of one local hit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HitSignificance {
+    pub score: i32,
+    pub bit_score: f64,
+    pub e_value: f64,
+    pub p_value: f64,
+}
+
+// Gapped parameters for nucleotide scoring with a uniform background, as
+// (reward, penalty, gap_open, gap_extend, lambda, K, H), from the
+// blastn_values_1_2, _1_3, _1_4 and _2_3 tables of NCBI BLAST+
+// (algo/blast/core/blast_stat.c). A gap of length k costs gap_open + k *
+// gap_extend, so the linear gaps used by the aligners in this crate are the
+// rows with gap_open 0.
+const GAPPED_NUCLEOTIDE: [(usize, usize, usize, usize, f64, f64, f64); 24] = [
+    (1, 2, 3, 3, 1.32, 0.57, 1.0),
+    (1, 2, 2, 2, 1.29, 0.49, 0.92),
+    (1, 2, 1, 2, 1.25, 0.42, 0.82),
+    (1, 2, 0, 2, 1.19, 0.34, 0.66),
+    (1, 2, 3, 1, 1.29, 0.49, 0.92),
+    (1, 2, 2, 1, 1.25, 0.42, 0.82),
+    (1, 2, 1, 1, 1.14, 0.26, 0.52),
+    (1, 3, 2, 2, 1.37, 0.70, 1.2),
+    (1, 3, 1, 2, 1.35, 0.64, 1.1),
+    (1, 3, 0, 2, 1.25, 0.42, 0.83),
+    (1, 3, 2, 1, 1.34, 0.60, 1.1),
+    (1, 3, 1, 1, 1.21, 0.34, 0.71),
+    (1, 4, 1, 2, 1.36, 0.67, 1.2),
+    (1, 4, 0, 2, 1.26, 0.43, 0.90),
+    (1, 4, 2, 1, 1.35, 0.61, 1.1),
+    (1, 4, 1, 1, 1.22, 0.35, 0.72),
+    (2, 3, 4, 4, 0.63, 0.42, 0.84),
+    (2, 3, 2, 4, 0.615, 0.37, 0.72),
+    (2, 3, 0, 4, 0.55, 0.21, 0.46),
+    (2, 3, 3, 3, 0.615, 0.37, 0.68),
+    (2, 3, 6, 2, 0.63, 0.42, 0.84),
+    (2, 3, 5, 2, 0.625, 0.41, 0.78),
+    (2, 3, 4, 2, 0.61, 0.35, 0.68),
+    (2, 3, 2, 2, 0.515, 0.14, 0.33),
+];
+
+// Gapped parameters for protein substitution matrices with the Robinson and
+// Robinson (1991) background, as (matrix, gap_open, gap_extend, lambda, K,
+// H), from the blosum62_values, blosum80_values, blosum45_values,
+// pam30_values and pam70_values tables of NCBI BLAST+ (blast_stat.c), which
+// BLAST estimated by simulation. Gaps cost as above, but every row has an
+// opening cost, so none of them fits the linear gaps of this crate's aligners.
+const GAPPED_PROTEIN: [(&str, usize, usize, f64, f64, f64); 41] = [
+    ("BLOSUM62", 11, 2, 0.297, 0.082, 0.27),
+    ("BLOSUM62", 10, 2, 0.291, 0.075, 0.23),
+    ("BLOSUM62", 9, 2, 0.279, 0.058, 0.19),
+    ("BLOSUM62", 8, 2, 0.264, 0.045, 0.15),
+    ("BLOSUM62", 7, 2, 0.239, 0.027, 0.10),
+    ("BLOSUM62", 6, 2, 0.201, 0.012, 0.061),
+    ("BLOSUM62", 13, 1, 0.292, 0.071, 0.23),
+    ("BLOSUM62", 12, 1, 0.283, 0.059, 0.19),
+    ("BLOSUM62", 11, 1, 0.267, 0.041, 0.14),
+    ("BLOSUM62", 10, 1, 0.243, 0.024, 0.10),
+    ("BLOSUM62", 9, 1, 0.206, 0.010, 0.052),
+    ("BLOSUM80", 25, 2, 0.342, 0.17, 0.66),
+    ("BLOSUM80", 13, 2, 0.336, 0.15, 0.57),
+    ("BLOSUM80", 9, 2, 0.319, 0.11, 0.42),
+    ("BLOSUM80", 8, 2, 0.308, 0.090, 0.35),
+    ("BLOSUM80", 7, 2, 0.293, 0.070, 0.27),
+    ("BLOSUM80", 6, 2, 0.268, 0.045, 0.19),
+    ("BLOSUM80", 11, 1, 0.314, 0.095, 0.35),
+    ("BLOSUM80", 10, 1, 0.299, 0.071, 0.27),
+    ("BLOSUM80", 9, 1, 0.279, 0.048, 0.20),
+    ("BLOSUM45", 13, 3, 0.207, 0.049, 0.14),
+    ("BLOSUM45", 12, 3, 0.199, 0.039, 0.11),
+    ("BLOSUM45", 11, 3, 0.190, 0.031, 0.095),
+    ("BLOSUM45", 10, 3, 0.179, 0.023, 0.075),
+    ("BLOSUM45", 16, 2, 0.210, 0.051, 0.14),
+    ("BLOSUM45", 15, 2, 0.203, 0.041, 0.12),
+    ("BLOSUM45", 14, 2, 0.195, 0.032, 0.10),
+    ("BLOSUM45", 13, 2, 0.185, 0.024, 0.084),
+    ("BLOSUM45", 12, 2, 0.171, 0.016, 0.061),
+    ("PAM30", 7, 2, 0.305, 0.15, 0.87),
+    ("PAM30", 6, 2, 0.287, 0.11, 0.68),
+    ("PAM30", 5, 2, 0.264, 0.079, 0.45),
+    ("PAM30", 10, 1, 0.309, 0.15, 0.88),
+    ("PAM30", 9, 1, 0.294, 0.11, 0.61),
+    ("PAM30", 8, 1, 0.270, 0.072, 0.40),
+    ("PAM70", 8, 2, 0.301, 0.12, 0.62),
+    ("PAM70", 7, 2, 0.286, 0.093, 0.48),
+    ("PAM70", 6, 2, 0.264, 0.064, 0.31),
+    ("PAM70", 11, 1, 0.305, 0.12, 0.60),
+    ("PAM70", 10, 1, 0.291, 0.091, 0.46),
+    ("PAM70", 9, 1, 0.270, 0.060, 0.28),
+];
+
+// Terms of the series for K. It converges geometrically for any scoring
+// with a negative expected score, usually within a few dozen terms.
+const MAX_SERIES_TERMS: usize = 500;
+const SERIES_TOLERANCE: f64 = 1e-12;
+
+impl KarlinAltschul {
+    pub fn new(lambda: f64, k: f64, h: f64) -> Self {
+        KarlinAltschul { lambda, k, h }
+    }
+
+    // Analytic parameters for ungapped alignment of residues drawn from the
+    // background frequencies, which are normalised to sum to 1. Returns None
+    // when the expected pair score is not negative or no pair scores above
+    // zero, since local scores then have no extreme value distribution.
+    pub fn ungapped<S: Scoring>(scores: &S, background: &[(char, f64)]) -> Option<Self> {
+        let total: f64 = background.iter().map(|(_, p)| p).sum();
+        assert!(
+            total > 0.0,
+            "background frequencies must sum to more than 0"
+        );
+
+        // Probability of each pair score
+        let mut distribution: BTreeMap<i32, f64> = BTreeMap::new();
+        for &(a, pa) in background {
+            for &(b, pb) in background {
+                let a = a.to_ascii_uppercase();
+                let b = b.to_ascii_uppercase();
+                *distribution.entry(scores.pair_score(a, b)).or_default() +=
+                    pa * pb / (total * total);
+            }
+        }
+        distribution.retain(|_, p| *p > 0.0);
+        let expected: f64 = distribution.iter().map(|(&s, p)| s as f64 * p).sum();
+        let highest = *distribution.keys().next_back()?;
+        if expected >= 0.0 || highest <= 0 {
+            return None;
+        }
+
+        // Work on the lattice of scores divided by their common divisor, then
+        // scale lambda back. K and H do not depend on the score unit.
+        let divisor = distribution
+            .keys()
+            .filter(|&&s| s != 0)
+            .fold(0, |d, &s| gcd(d, s.unsigned_abs() as i32));
+        let reduced: Vec<(i32, f64)> = distribution
+            .iter()
+            .map(|(&s, &p)| (s / divisor, p))
+            .collect();
+
+        let lambda = solve_lambda(&reduced);
+        let tilted: f64 = reduced
+            .iter()
+            .map(|&(s, p)| s as f64 * p * (lambda * s as f64).exp())
+            .sum();
+        let h = lambda * tilted;
+        let sigma = series_sum(&reduced, lambda);
+        let k = (-2.0 * sigma).exp() / (tilted * -(-lambda).exp_m1());
+        Some(KarlinAltschul {
+            lambda: lambda / divisor as f64,
+            k,
+            h,
+        })
+    }
+
+    // Precomputed gapped parameters for a nucleotide reward and penalty with
+    // BLAST-style gap costs, if the scheme is in the table
+    pub fn gapped(
+        reward: usize,
+        penalty: usize,
+        gap_open: usize,
+        gap_extend: usize,
+    ) -> Option<Self> {
+        GAPPED_NUCLEOTIDE
+            .iter()
+            .find(|row| (row.0, row.1, row.2, row.3) == (reward, penalty, gap_open, gap_extend))
+            .map(|row| KarlinAltschul::new(row.4, row.5, row.6))
+    }
+
+    // Precomputed gapped parameters for a protein substitution matrix by its
+    // NCBI name, e.g. "BLOSUM62" or "PAM30", with BLAST-style gap costs
+    pub fn gapped_protein(matrix: &str, gap_open: usize, gap_extend: usize) -> Option<Self> {
+        GAPPED_PROTEIN
+            .iter()
+            .find(|row| {
+                row.0.eq_ignore_ascii_case(matrix) && (row.1, row.2) == (gap_open, gap_extend)
+            })
+            .map(|row| KarlinAltschul::new(row.3, row.4, row.5))
+    }
+
+    // Precomputed gapped parameters for a linear gap scoring as used by
+    // `SmithWaterman`, where a gap of length k costs k * gap. Scorings that
+    // are a multiple of a table row, such as 2/6 with gaps of 4 for 1/3 with
+    // gaps of 2, use that row with lambda scaled down. Only nucleotide
+    // reward/penalty schemes are covered; the protein tables have no linear
+    // gap rows, so matrices go through `gapped_protein`. NCBI has no tables
+    // for a mismatch penalty below the reward, such as the 2/1 default of
+    // `GeneralScoring`, so those give None.
+    pub fn for_scoring<S: Scoring>(scores: &S) -> Option<Self> {
+        if scores.get_extended_gap_score() != 0 {
+            return None;
+        }
+        let (reward, penalty, gap) = (
+            scores.get_match_score(),
+            scores.get_mismatch_score(),
+            scores.get_gap_score(),
+        );
+        let divisor = gcd(gcd(reward as i32, penalty as i32), gap as i32).max(1) as usize;
+        KarlinAltschul::gapped(reward / divisor, penalty / divisor, 0, gap / divisor).map(
+            |params| KarlinAltschul {
+                lambda: params.lambda / divisor as f64,
+                k: params.k,
+                h: params.h,
+            },
+        )
+    }
+
+    // Score in bits, comparable across scoring schemes
+    pub fn bit_score(&self, score: i32) -> f64 {
+        (self.lambda * score as f64 - self.k.ln()) / std::f64::consts::LN_2
+    }
+
+    pub fn e_value(&self, score: i32, query_len: usize, database_len: usize) -> f64 {
+        self.k * query_len as f64 * database_len as f64 * (-self.lambda * score as f64).exp()
+    }
+
+    // Probability of at least one chance hit scoring `score` or more
+    pub fn p_value(&self, score: i32, query_len: usize, database_len: usize) -> f64 {
+        -(-self.e_value(score, query_len, database_len)).exp_m1()
+    }
+
+    pub fn significance(
+        &self,
+        score: i32,
+        query_len: usize,
+        database_len: usize,
+    ) -> HitSignificance {
+        HitSignificance {
+            score,
+            bit_score: self.bit_score(score),
+            e_value: self.e_value(score, query_len, database_len),
+            p_value: self.p_value(score, query_len, database_len),
+        }
+    }
+
+    // Significance of a local alignment's best score
+    pub fn hit(
+        &self,
+        model: &LocalAlignmentModel,
+        query_len: usize,
+        database_len: usize,
+    ) -> HitSignificance {
+        self.significance(model.similarity(), query_len, database_len)
+    }
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Positive root of sum(p * exp(lambda * s)) = 1. The sum is convex in lambda,
+// equals 1 at 0 and falls below it first, so bisection on a bracket that
+// ends above 1 finds the only other root.
+fn solve_lambda(distribution: &[(i32, f64)]) -> f64 {
+    let f = |lambda: f64| -> f64 {
+        distribution
+            .iter()
+            .map(|&(s, p)| p * (lambda * s as f64).exp())
+            .sum::<f64>()
+            - 1.0
+    };
+    let mut high = 1.0;
+    while f(high) < 0.0 {
+        high *= 2.0;
+    }
+    let mut low = 0.0;
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        if f(mid) < 0.0 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// sum over k >= 1 of (E[exp(lambda * S_k); S_k < 0] + P(S_k >= 0)) / k, where
+// S_k is the sum of k independent pair scores (Karlin and Altschul, 1990)
+fn series_sum(distribution: &[(i32, f64)], lambda: f64) -> f64 {
+    let low = distribution[0].0;
+    let mut offset = 0;
+    // probabilities[j] = P(S_k = j + offset)
+    let mut probabilities = vec![1.0];
+    let mut sum = 0.0;
+    for k in 1..=MAX_SERIES_TERMS {
+        let high = distribution.last().unwrap().0;
+        let mut next = vec![0.0; probabilities.len() + (high - low) as usize];
+        for (j, &p) in probabilities.iter().enumerate() {
+            if p == 0.0 {
+                continue;
+            }
+            for &(s, q) in distribution {
+                next[j + (s - low) as usize] += p * q;
+            }
+        }
+        probabilities = next;
+        offset += low;
+
+        let term: f64 = probabilities
+            .iter()
+            .enumerate()
+            .map(|(j, &p)| {
+                let score = j as i32 + offset;
+                if score < 0 {
+                    p * (lambda * score as f64).exp()
+                } else {
+                    p
+                }
+            })
+            .sum::<f64>()
+            / k as f64;
+        sum += term;
+        if term < SERIES_TOLERANCE {
+            break;
+        }
+    }
+    sum
+}
+
+// Empirical significance of an alignment score against shuffled subjects
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmpiricalSignificance {
+    pub score: i32,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub z_score: f64,
+    pub p_value: f64,
+}
+
+// Monte Carlo estimate of significance for scores with no analytic
+// distribution, such as global or custom-scored alignments. The subject is
+// shuffled `shuffles` times and realigned, and the observed score is compared
+// with the scores of the shuffles. The same seed always gives the same
+// shuffles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonteCarlo {
+    pub shuffles: usize,
+    pub shuffle: Shuffle,
+    pub seed: u64,
+}
+
+impl Default for MonteCarlo {
+    fn default() -> Self {
+        Self {
+            shuffles: 1000,
+            shuffle: Shuffle::Mononucleotide,
+            seed: 0,
+        }
+    }
+}
+
+impl MonteCarlo {
+    pub fn shuffles(&self, value: usize) -> Self {
+        Self {
+            shuffles: value,
+            shuffle: self.shuffle,
+            seed: self.seed,
+        }
+    }
+
+    pub fn shuffle(&self, value: Shuffle) -> Self {
+        Self {
+            shuffles: self.shuffles,
+            shuffle: value,
+            seed: self.seed,
+        }
+    }
+
+    pub fn seed(&self, value: u64) -> Self {
+        Self {
+            shuffles: self.shuffles,
+            shuffle: self.shuffle,
+            seed: value,
+        }
+    }
+
+    // `align` scores a query against a subject, e.g.
+    // |q, s| NeedlemanWunsch::compute(q, s).similarity()
+    // The p-value is the fraction of shuffles scoring at least as well, with
+    // the observed alignment counted as one of them, so it is never 0. The
+    // z-score is infinite when every shuffle scores the same.
+    pub fn significance<F>(&self, query: &str, subject: &str, mut align: F) -> EmpiricalSignificance
+    where
+        F: FnMut(&str, &str) -> i32,
+    {
+        assert!(self.shuffles > 0, "at least one shuffle is needed");
+        let score = align(query, subject);
+        let mut rng = SeededRng::new(self.seed);
+        let scores: Vec<f64> = (0..self.shuffles)
+            .map(|_| align(query, &shuffle(subject, self.shuffle, &mut rng)) as f64)
+            .collect();
+
+        let count = scores.len() as f64;
+        let mean = scores.iter().sum::<f64>() / count;
+        let variance = scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / count;
+        let std_dev = variance.sqrt();
+        let z_score = if std_dev > 0.0 {
+            (score as f64 - mean) / std_dev
+        } else if score as f64 == mean {
+            0.0
+        } else {
+            (score as f64 - mean).signum() * f64::INFINITY
+        };
+        let at_least = scores.iter().filter(|&&s| s >= score as f64).count();
+        EmpiricalSignificance {
+            score,
+            mean,
+            std_dev,
+            z_score,
+            p_value: (at_least + 1) as f64 / (count + 1.0),
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::align::edit::needleman_wunsch::NeedlemanWunsch;
+use crate::align::edit::smith_waterman::SmithWaterman;
+use crate::align::fuzzy::free_start_scan;
+use crate::align::global_base::GlobalAlignmentModel;
+use crate::align::local_base::LocalAlignmentModel;
+use crate::align::scoring::GeneralScoring;
+use crate::align::search::hit_coordinates;
+use crate::align::{GlobalAlignmentMatrix, LocalAlignmentMatrix, Scoring};
+
+// IUPAC nucleotide complement. Case is preserved, U pairs with A, and
+// anything that is not a nucleotide code (gaps, '*', ...) is returned as is.
+pub fn complement(base: char) -> char {
+    let upper = match base.to_ascii_uppercase() {
+        'A' => 'T',
+        'T' | 'U' => 'A',
+        'C' => 'G',
+        'G' => 'C',
+        'R' => 'Y',
+        'Y' => 'R',
+        'K' => 'M',
+        'M' => 'K',
+        'B' => 'V',
+        'V' => 'B',
+        'D' => 'H',
+        'H' => 'D',
+        // S, W and N are their own complements
+        other => other,
+    };
+    if base.is_ascii_lowercase() {
+        upper.to_ascii_lowercase()
+    } else {
+        upper
+    }
+}
+
+pub fn reverse_complement(sequence: &str) -> String {
+    sequence.chars().rev().map(complement).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strand {
+    Forward,
+    Reverse,
+}
+
+impl Strand {
+    pub fn symbol(&self) -> char {
+        match self {
+            Strand::Forward => '+',
+            Strand::Reverse => '-',
+        }
+    }
+}
+
+// Best alignment of a query against either strand of a subject.
+// On the reverse strand `model` aligns the reverse complement of the query
+// against the forward subject, so the subject coordinates always refer to
+// the forward subject. Query coordinates refer to the query as given.
+// All coordinates are 0-based and half-open.
+pub struct StrandedAlignment<M> {
+    pub strand: Strand,
+    pub score: i32,
+    pub query_start: usize,
+    pub query_end: usize,
+    pub subject_start: usize,
+    pub subject_end: usize,
+    pub model: M,
+}
+
+impl<M> StrandedAlignment<M> {
+    // Maps a span of the aligned query back onto the query as given
+    fn with_query_span(mut self, start: usize, end: usize, query_len: usize) -> Self {
+        (self.query_start, self.query_end) = match self.strand {
+            Strand::Forward => (start, end),
+            Strand::Reverse => (query_len - end, query_len - start),
+        };
+        self
+    }
+}
+
+// Aligns both the query and its reverse complement and keeps the better
+// scoring strand. Ties go to the forward strand.
+pub struct BothStrands<S: Scoring + Clone> {
+    pub scores: S,
+}
+
+impl Default for BothStrands<GeneralScoring> {
+    fn default() -> Self {
+        let scores = GeneralScoring {
+            identity: 2,
+            mismatch: 1,
+            gap: 2,
+        };
+        Self { scores }
+    }
+}
+
+impl<S: Scoring + Clone> BothStrands<S>
+where
+    SmithWaterman<S>: LocalAlignmentMatrix<S>,
+    NeedlemanWunsch<S>: GlobalAlignmentMatrix<S>,
+{
+    pub fn set_scores(scores: &S) -> Self {
+        Self {
+            scores: scores.clone(),
+        }
+    }
+
+    // Smith-Waterman on both strands
+    pub fn local(&self, query: &str, subject: &str) -> StrandedAlignment<LocalAlignmentModel> {
+        let sw = SmithWaterman::set_scores(&self.scores);
+        let query_len = query.chars().count();
+        let reverse = reverse_complement(query);
+
+        let forward = sw.calculate_matrix(query, subject);
+        let reverse = sw.calculate_matrix(&reverse, subject);
+        let (strand, model) = if reverse.similarity() > forward.similarity() {
+            (Strand::Reverse, reverse)
+        } else {
+            (Strand::Forward, forward)
+        };
+
+        let (query_start, query_end, subject_start, subject_end, _) = hit_coordinates(&model);
+        StrandedAlignment {
+            strand,
+            score: model.similarity(),
+            query_start: 0,
+            query_end: 0,
+            subject_start,
+            subject_end,
+            model,
+        }
+        .with_query_span(query_start, query_end, query_len)
+    }
+
+    // Semi-global alignment on both strands: the whole query is aligned, but
+    // gaps before and after it in the subject are free. `model` is the
+    // `NeedlemanWunsch` alignment of the query against
+    // subject[subject_start..subject_end], whose score is the semi-global score.
+    pub fn semi_global(
+        &self,
+        query: &str,
+        subject: &str,
+    ) -> StrandedAlignment<GlobalAlignmentModel> {
+        let query_len = query.chars().count();
+        let reverse = reverse_complement(query);
+        let subject_chars: Vec<char> = subject.to_uppercase().chars().collect();
+
+        let forward_span = self.semi_global_span(query, &subject_chars);
+        let reverse_span = self.semi_global_span(&reverse, &subject_chars);
+        let (strand, aligned, (subject_start, subject_end, score)) =
+            if reverse_span.2 > forward_span.2 {
+                (Strand::Reverse, reverse.as_str(), reverse_span)
+            } else {
+                (Strand::Forward, query, forward_span)
+            };
+
+        let window: String = subject_chars[subject_start..subject_end].iter().collect();
+        let model = NeedlemanWunsch::set_scores(&self.scores).calculate_matrix(aligned, &window);
+        StrandedAlignment {
+            strand,
+            score,
+            query_start: 0,
+            query_end: query_len,
+            subject_start,
+            subject_end,
+            model,
+        }
+    }
+
+    // (start, end, score) of the best placement of the whole query in the
+    // subject, with a free choice of start and end column. Ties prefer the
+    // earliest end.
+    fn semi_global_span(&self, query: &str, subject: &[char]) -> (usize, usize, i32) {
+        let query: Vec<char> = query.to_uppercase().chars().collect();
+        let gap = self.scores.get_gap_score() as i32;
+        let pair = |a, b| self.scores.pair_score(a, b);
+        let mut best = (0, 0, -(query.len() as i32) * gap);
+        free_start_scan(&query, subject, pair, gap, |start, end, score| {
+            if score > best.2 {
+                best = (start, end, score);
+            }
+        });
+        best
+    }
+}
@@ -0,0 +1,85 @@
+use spindalis::utils::Arr2D;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Edge length of the square tiles processed by one task
+pub const DEFAULT_TILE_SIZE: usize = 256;
+
+// Unsynchronised view of a matrix shared between tile tasks, holding a
+// pointer to each row. The wavefront order guarantees that a cell is never
+// written while another task reads or writes it, which is what makes the
+// Send/Sync impls sound.
+pub(crate) struct SharedGrid<'a> {
+    rows: Vec<*mut i32>,
+    width: usize,
+    matrix: PhantomData<&'a mut Arr2D<i32>>,
+}
+
+unsafe impl Send for SharedGrid<'_> {}
+unsafe impl Sync for SharedGrid<'_> {}
+
+impl<'a> SharedGrid<'a> {
+    pub(crate) fn new(matrix: &'a mut Arr2D<i32>) -> Self {
+        SharedGrid {
+            width: matrix.width,
+            rows: matrix.rows_mut().map(|row| row.as_mut_ptr()).collect(),
+            matrix: PhantomData,
+        }
+    }
+
+    // Safety: no other task may be writing cell (i, j)
+    #[inline(always)]
+    pub(crate) unsafe fn get(&self, i: usize, j: usize) -> i32 {
+        debug_assert!(j < self.width);
+        unsafe { *self.rows[i].add(j) }
+    }
+
+    // Safety: no other task may be reading or writing cell (i, j)
+    #[inline(always)]
+    pub(crate) unsafe fn set(&self, i: usize, j: usize, value: i32) {
+        debug_assert!(j < self.width);
+        unsafe { *self.rows[i].add(j) = value }
+    }
+}
+
+// Calls `fill_tile` on every tile of the matrix interior (rows and columns
+// from 1) one anti-diagonal of tiles at a time. A tile only depends on the
+// tiles above, to the left and diagonally up-left of it, which all belong to
+// earlier anti-diagonals. With the `parallel` feature the tiles of each
+// anti-diagonal run on the rayon pool, otherwise they run in order.
+// Results are returned in the order the tiles were scheduled.
+pub(crate) fn wavefront<T, F>(height: usize, width: usize, tile_size: usize, fill_tile: F) -> Vec<T>
+where
+    F: Fn(Range<usize>, Range<usize>) -> T + Sync,
+    T: Send,
+{
+    let tile_size = tile_size.max(1);
+    let tile_rows = height.saturating_sub(1).div_ceil(tile_size);
+    let tile_cols = width.saturating_sub(1).div_ceil(tile_size);
+    let tile_range = |t: usize, limit: usize| {
+        let start = 1 + t * tile_size;
+        start..(start + tile_size).min(limit)
+    };
+
+    let mut results = Vec::with_capacity(tile_rows * tile_cols);
+    if tile_rows == 0 || tile_cols == 0 {
+        return results;
+    }
+    for wave in 0..(tile_rows + tile_cols).saturating_sub(1) {
+        let first = wave.saturating_sub(tile_cols - 1);
+        let last = wave.min(tile_rows - 1);
+        let tiles: Vec<(usize, usize)> = (first..=last).map(|bi| (bi, wave - bi)).collect();
+        let run =
+            |&(bi, bj): &(usize, usize)| fill_tile(tile_range(bi, height), tile_range(bj, width));
+
+        #[cfg(feature = "parallel")]
+        let wave_results: Vec<T> = tiles.par_iter().map(run).collect();
+        #[cfg(not(feature = "parallel"))]
+        let wave_results: Vec<T> = tiles.iter().map(run).collect();
+        results.extend(wave_results);
+    }
+    results
+}
@@ -0,0 +1,423 @@
+use crate::align::edit::needleman_wunsch::NeedlemanWunsch;
+use crate::align::edit::smith_waterman::SmithWaterman;
+use crate::align::edit::wagner_fischer::WagnerFischer;
+use crate::align::global_base::{
+    GlobalAlgorithm, GlobalAligner, GlobalAlignmentModel, GlobalScores, Metric,
+};
+use crate::align::local_base::{LocalAlgorithm, LocalAligner, LocalAlignmentModel, LocalMetric};
+use crate::align::{AlignmentData, PointerValues, Scoring};
+use spindalis::utils::Arr2D;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+// Row-major matrix that keeps its allocation between alignments
+#[derive(Clone, Debug, Default)]
+pub struct ScratchMatrix {
+    data: Vec<i32>,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl ScratchMatrix {
+    pub fn shape(&self) -> (usize, usize) {
+        (self.height, self.width)
+    }
+
+    // Number of cells that fit without reallocating
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
+    // Resizes to height x width with every cell set to 0. Memory is only
+    // allocated when the new shape is larger than any seen before.
+    pub fn reset(&mut self, height: usize, width: usize) {
+        self.data.clear();
+        self.data.resize(height * width, 0);
+        self.height = height;
+        self.width = width;
+    }
+
+    pub fn to_arr2d(&self) -> Arr2D<i32> {
+        let mut matrix = Arr2D::full(0, self.height, self.width);
+        for i in 0..self.height {
+            matrix[i].copy_from_slice(&self[i]);
+        }
+        matrix
+    }
+}
+
+impl Index<usize> for ScratchMatrix {
+    type Output = [i32];
+
+    fn index(&self, row: usize) -> &Self::Output {
+        &self.data[row * self.width..(row + 1) * self.width]
+    }
+}
+
+impl IndexMut<usize> for ScratchMatrix {
+    fn index_mut(&mut self, row: usize) -> &mut Self::Output {
+        &mut self.data[row * self.width..(row + 1) * self.width]
+    }
+}
+
+impl fmt::Display for ScratchMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_arr2d())
+    }
+}
+
+// Buffers shared by every alignment an `Aligner` runs
+#[derive(Clone, Debug, Default)]
+pub struct Workspace {
+    query: Vec<char>,
+    subject: Vec<char>,
+    score_matrix: ScratchMatrix,
+    pointer_matrix: ScratchMatrix,
+    start_indices: Vec<(usize, usize)>,
+    max_score: i32,
+}
+
+impl Workspace {
+    fn load(&mut self, query: &str, subject: &str) {
+        self.query.clear();
+        self.query
+            .extend(query.chars().flat_map(char::to_uppercase));
+        self.subject.clear();
+        self.subject
+            .extend(subject.chars().flat_map(char::to_uppercase));
+
+        let height = self.query.len() + 1;
+        let width = self.subject.len() + 1;
+        self.score_matrix.reset(height, width);
+        self.pointer_matrix.reset(height, width);
+        self.start_indices.clear();
+        self.max_score = 0;
+    }
+
+    pub fn score_matrix(&self) -> &ScratchMatrix {
+        &self.score_matrix
+    }
+
+    pub fn pointer_matrix(&self) -> &ScratchMatrix {
+        &self.pointer_matrix
+    }
+}
+
+// Algorithms that can fill a `Workspace` instead of allocating new matrices
+pub trait WorkspaceKernel {
+    type View<'a>
+    where
+        Self: 'a;
+
+    fn fill(&self, workspace: &mut Workspace);
+    fn view<'a>(&'a self, workspace: &'a Workspace) -> Self::View<'a>;
+}
+
+// Owns an algorithm together with scratch buffers that grow to the largest
+// pair seen so far. Each result borrows the buffers until the next call, use
+// `to_model` on the result to keep it around.
+pub struct Aligner<K: WorkspaceKernel> {
+    pub kernel: K,
+    workspace: Workspace,
+}
+
+impl<K: WorkspaceKernel + Default> Default for Aligner<K> {
+    fn default() -> Self {
+        Self::new(K::default())
+    }
+}
+
+impl<K: WorkspaceKernel> Aligner<K> {
+    pub fn new(kernel: K) -> Self {
+        Self {
+            kernel,
+            workspace: Workspace::default(),
+        }
+    }
+
+    pub fn calculate_matrix(&mut self, query: &str, subject: &str) -> K::View<'_> {
+        self.workspace.load(query, subject);
+        self.kernel.fill(&mut self.workspace);
+        self.kernel.view(&self.workspace)
+    }
+
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+}
+
+impl<S: Scoring + Clone> WorkspaceKernel for NeedlemanWunsch<S> {
+    type View<'a>
+        = GlobalAlignmentView<'a>
+    where
+        Self: 'a;
+
+    fn fill(&self, workspace: &mut Workspace) {
+        self.fill_matrices(
+            &workspace.query,
+            &workspace.subject,
+            &mut workspace.score_matrix,
+            &mut workspace.pointer_matrix,
+        );
+    }
+
+    fn view<'a>(&'a self, workspace: &'a Workspace) -> GlobalAlignmentView<'a> {
+        GlobalAlignmentView {
+            workspace,
+            aligner: GlobalAlgorithm::NeedlemanWunsch,
+            metric: Metric::Similarity,
+            identity: self.scores.get_match_score(),
+            mismatch: self.scores.get_mismatch_score(),
+            gap: self.scores.get_gap_score(),
+            all_alignments: false,
+        }
+    }
+}
+
+impl<S: Scoring + Clone> WorkspaceKernel for WagnerFischer<S> {
+    type View<'a>
+        = GlobalAlignmentView<'a>
+    where
+        Self: 'a;
+
+    fn fill(&self, workspace: &mut Workspace) {
+        self.fill_matrices(
+            &workspace.query,
+            &workspace.subject,
+            &mut workspace.score_matrix,
+            &mut workspace.pointer_matrix,
+        );
+    }
+
+    fn view<'a>(&'a self, workspace: &'a Workspace) -> GlobalAlignmentView<'a> {
+        GlobalAlignmentView {
+            workspace,
+            aligner: GlobalAlgorithm::WagnerFischer,
+            metric: Metric::Distance,
+            identity: 0,
+            mismatch: self.scores.get_mismatch_score(),
+            gap: self.scores.get_gap_score(),
+            all_alignments: false,
+        }
+    }
+}
+
+impl<S: Scoring + Clone> WorkspaceKernel for SmithWaterman<S> {
+    type View<'a>
+        = LocalAlignmentView<'a>
+    where
+        Self: 'a;
+
+    fn fill(&self, workspace: &mut Workspace) {
+        workspace.max_score = self.fill_matrices(
+            &workspace.query,
+            &workspace.subject,
+            &mut workspace.score_matrix,
+            &mut workspace.pointer_matrix,
+            &mut workspace.start_indices,
+        );
+    }
+
+    fn view<'a>(&'a self, workspace: &'a Workspace) -> LocalAlignmentView<'a> {
+        LocalAlignmentView {
+            workspace,
+            aligner: LocalAlgorithm::SmithWaterman,
+            identity: self.scores.get_match_score(),
+            mismatch: self.scores.get_mismatch_score(),
+            gap: self.scores.get_gap_score(),
+            all_alignments: false,
+        }
+    }
+}
+
+// Borrowed counterpart of `GlobalAlignmentModel`
+pub struct GlobalAlignmentView<'a> {
+    workspace: &'a Workspace,
+    pub aligner: GlobalAlgorithm,
+    pub metric: Metric,
+    pub identity: usize,
+    pub mismatch: usize,
+    pub gap: usize,
+    pub all_alignments: bool,
+}
+
+impl<'a> GlobalAlignmentView<'a> {
+    pub fn all_alignments(&self, value: bool) -> Self {
+        Self {
+            workspace: self.workspace,
+            aligner: self.aligner.clone(),
+            metric: self.metric.clone(),
+            identity: self.identity,
+            mismatch: self.mismatch,
+            gap: self.gap,
+            all_alignments: value,
+        }
+    }
+
+    pub fn query(&self) -> &'a [char] {
+        &self.workspace.query
+    }
+
+    pub fn subject(&self) -> &'a [char] {
+        &self.workspace.subject
+    }
+
+    pub fn score_matrix(&self) -> &'a ScratchMatrix {
+        &self.workspace.score_matrix
+    }
+
+    pub fn pointer_matrix(&self) -> &'a ScratchMatrix {
+        &self.workspace.pointer_matrix
+    }
+
+    pub fn align(&self) -> Vec<String> {
+        let i = self.workspace.query.len();
+        let j = self.workspace.subject.len();
+        let global_aligner = GlobalAligner {
+            query_chars: &self.workspace.query,
+            subject_chars: &self.workspace.subject,
+            pointer_matrix: &self.workspace.pointer_matrix,
+            stack: vec![(Vec::new(), Vec::new(), i, j)],
+            all_alignments: self.all_alignments,
+            match_val: PointerValues::Match as i32,
+            up_val: PointerValues::Up as i32,
+            left_val: PointerValues::Left as i32,
+        };
+        global_aligner
+            .map(|(qs, ss)| format!("{qs}\n{ss}"))
+            .collect()
+    }
+
+    fn scores(&self) -> GlobalScores<'_> {
+        let i = self.workspace.query.len();
+        let j = self.workspace.subject.len();
+        GlobalScores {
+            query_len: i,
+            subject_len: j,
+            final_score: self.workspace.score_matrix[i][j],
+            metric: &self.metric,
+            identity: self.identity,
+            mismatch: self.mismatch,
+            gap: self.gap,
+        }
+    }
+
+    pub fn similarity(&self) -> i32 {
+        self.scores().similarity()
+    }
+
+    pub fn distance(&self) -> i32 {
+        self.scores().distance()
+    }
+
+    pub fn normalized_similarity(&self) -> f64 {
+        self.scores().normalized_similarity()
+    }
+
+    pub fn normalized_distance(&self) -> f64 {
+        self.scores().normalized_distance()
+    }
+
+    // Copies the borrowed buffers into an owned model
+    pub fn to_model(&self) -> GlobalAlignmentModel {
+        GlobalAlignmentModel {
+            data: owned_data(self.workspace),
+            aligner: self.aligner.clone(),
+            metric: self.metric.clone(),
+            identity: self.identity,
+            mismatch: self.mismatch,
+            gap: self.gap,
+            all_alignments: self.all_alignments,
+        }
+    }
+}
+
+// Borrowed counterpart of `LocalAlignmentModel`
+pub struct LocalAlignmentView<'a> {
+    workspace: &'a Workspace,
+    pub aligner: LocalAlgorithm,
+    pub identity: usize,
+    pub mismatch: usize,
+    pub gap: usize,
+    pub all_alignments: bool,
+}
+
+impl<'a> LocalAlignmentView<'a> {
+    pub fn all_alignments(&self, value: bool) -> Self {
+        Self {
+            workspace: self.workspace,
+            aligner: self.aligner.clone(),
+            identity: self.identity,
+            mismatch: self.mismatch,
+            gap: self.gap,
+            all_alignments: value,
+        }
+    }
+
+    pub fn query(&self) -> &'a [char] {
+        &self.workspace.query
+    }
+
+    pub fn subject(&self) -> &'a [char] {
+        &self.workspace.subject
+    }
+
+    pub fn score_matrix(&self) -> &'a ScratchMatrix {
+        &self.workspace.score_matrix
+    }
+
+    pub fn pointer_matrix(&self) -> &'a ScratchMatrix {
+        &self.workspace.pointer_matrix
+    }
+
+    pub fn start_indices(&self) -> &'a [(usize, usize)] {
+        &self.workspace.start_indices
+    }
+
+    pub fn align(&self) -> Vec<String> {
+        let local_aligner = LocalAligner {
+            query_chars: &self.workspace.query,
+            subject_chars: &self.workspace.subject,
+            pointer_matrix: &self.workspace.pointer_matrix,
+            score_matrix: &self.workspace.score_matrix,
+            stack: self
+                .workspace
+                .start_indices
+                .iter()
+                .map(|&(i, j)| (Vec::new(), Vec::new(), i, j))
+                .collect(),
+            all_alignments: self.all_alignments,
+        };
+        local_aligner
+            .map(|(qs, ss)| format!("{qs}\n{ss}"))
+            .collect()
+    }
+
+    pub fn similarity(&self) -> i32 {
+        self.workspace.max_score
+    }
+
+    // Copies the borrowed buffers into an owned model
+    pub fn to_model(&self) -> LocalAlignmentModel {
+        LocalAlignmentModel {
+            data: owned_data(self.workspace),
+            aligner: self.aligner.clone(),
+            metric: LocalMetric::Similarity,
+            identity: self.identity,
+            mismatch: self.mismatch,
+            gap: self.gap,
+            all_alignments: self.all_alignments,
+            max_score: self.workspace.max_score,
+            start_indices: self.workspace.start_indices.clone(),
+        }
+    }
+}
+
+fn owned_data(workspace: &Workspace) -> AlignmentData {
+    AlignmentData {
+        query: workspace.query.clone(),
+        subject: workspace.subject.clone(),
+        score_matrix: vec![workspace.score_matrix.to_arr2d()],
+        pointer_matrix: vec![workspace.pointer_matrix.to_arr2d()],
+    }
+}
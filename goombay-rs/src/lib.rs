@@ -7,6 +7,7 @@ pub mod scoring {
     pub use scoring::ExtendedGapScoring;
     pub use scoring::GeneralScoring;
     pub use scoring::LevenshteinScoring;
+    pub use scoring::NucleotideScoring;
     pub use scoring::TransposeScoring;
 }
 
@@ -14,7 +15,26 @@ pub mod align {
     // Re-exports everything from alignment folder as align
     pub use crate::alignment::*;
 
+    pub use batch::{
+        PairwiseMetric, condensed_distances, condensed_index, distance_matrix, squareform,
+    };
+    pub use circular::{CircularAligner, CircularAlignment, rotate};
     pub use edit::needleman_wunsch::NeedlemanWunsch;
     pub use edit::smith_waterman::SmithWaterman;
+    pub use edit::striped_smith_waterman::{StripedProfile, StripedSmithWaterman};
+    pub use edit::translated::{TranslatedAligner, TranslatedAlignmentModel};
     pub use edit::wagner_fischer::WagnerFischer;
+    pub use edit::x_drop::{XDrop, XDropExtension};
+    pub use fuzzy::{FuzzyMatch, FuzzySearch};
+    pub use genetic_code::GeneticCode;
+    pub use kmer_index::{Candidate, DiagonalSeed, KmerIndex};
+    pub use pair_hmm::{Emissions, MeaAlignment, PairHmm, PairHmmAlignment, PosteriorMatrix};
+    pub use search::{LocalSearch, SearchHit};
+    pub use shuffle::{SeededRng, Shuffle, shuffle};
+    pub use simd::SimdEngine;
+    pub use statistics::{EmpiricalSignificance, HitSignificance, KarlinAltschul, MonteCarlo};
+    pub use strand::{BothStrands, Strand, StrandedAlignment, complement, reverse_complement};
+    pub use workspace::{
+        Aligner, GlobalAlignmentView, LocalAlignmentView, ScratchMatrix, Workspace, WorkspaceKernel,
+    };
 }
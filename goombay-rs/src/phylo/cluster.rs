@@ -0,0 +1,108 @@
+use crate::align::batch::{PairwiseMetric, distance_matrix};
+use crate::align::{GlobalAlignmentMatrix, Scoring};
+use crate::phylo::tree::{Clade, Tree};
+use spindalis::utils::Arr2D;
+
+// How the distance from a merged cluster to the others is averaged.
+// `Upgma` weights each side by its number of leaves, so every leaf pair
+// counts equally. `Wpgma` gives both sides equal weight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Linkage {
+    Upgma,
+    Wpgma,
+}
+
+pub fn upgma<N: AsRef<str>>(distances: &Arr2D<f64>, names: &[N]) -> Tree {
+    cluster(distances, names, Linkage::Upgma)
+}
+
+pub fn wpgma<N: AsRef<str>>(distances: &Arr2D<f64>, names: &[N]) -> Tree {
+    cluster(distances, names, Linkage::Wpgma)
+}
+
+// Agglomerative clustering into a rooted ultrametric tree. The closest pair
+// of clusters is joined at half their distance, so every leaf ends up at the
+// same depth. Ties go to the pair that comes first in the matrix, and the
+// lower-indexed cluster is the first child.
+pub fn cluster<N: AsRef<str>>(distances: &Arr2D<f64>, names: &[N], linkage: Linkage) -> Tree {
+    let n = names.len();
+    assert!(n > 0, "clustering needs at least one sequence");
+    assert!(
+        distances.height == n && distances.width == n,
+        "distance matrix must be {n} x {n} to match the names"
+    );
+
+    let mut clades: Vec<Clade> = names
+        .iter()
+        .map(|name| Clade {
+            name: Some(name.as_ref().to_string()),
+            children: Vec::new(),
+        })
+        .collect();
+    // Per active slot: its clade, leaf count and height
+    let mut slots: Vec<Option<(usize, usize, f64)>> = (0..n).map(|i| Some((i, 1, 0.0))).collect();
+    let mut d: Vec<Vec<f64>> = (0..n).map(|i| distances[i].to_vec()).collect();
+
+    for _ in 1..n {
+        let mut closest: Option<(usize, usize)> = None;
+        for i in 0..n {
+            for j in i + 1..n {
+                if slots[i].is_some()
+                    && slots[j].is_some()
+                    && closest.is_none_or(|(a, b)| d[i][j] < d[a][b])
+                {
+                    closest = Some((i, j));
+                }
+            }
+        }
+        let (i, j) = closest.unwrap();
+        let (left, left_size, left_height) = slots[i].unwrap();
+        let (right, right_size, right_height) = slots[j].unwrap();
+        let height = d[i][j] / 2.0;
+
+        for k in 0..n {
+            if k == i || k == j || slots[k].is_none() {
+                continue;
+            }
+            let merged = match linkage {
+                Linkage::Upgma => {
+                    (left_size as f64 * d[i][k] + right_size as f64 * d[j][k])
+                        / (left_size + right_size) as f64
+                }
+                Linkage::Wpgma => (d[i][k] + d[j][k]) / 2.0,
+            };
+            d[i][k] = merged;
+            d[k][i] = merged;
+        }
+
+        clades.push(Clade {
+            name: None,
+            children: vec![
+                (left, (height - left_height).max(0.0)),
+                (right, (height - right_height).max(0.0)),
+            ],
+        });
+        slots[i] = Some((clades.len() - 1, left_size + right_size, height));
+        slots[j] = None;
+    }
+    Tree::from_clades(&clades, true)
+}
+
+// Aligns every pair of sequences, takes their normalised distances and
+// clusters them
+pub fn tree_from_sequences<A, S, N, Q>(
+    aligner: &A,
+    names: &[N],
+    sequences: &[Q],
+    linkage: Linkage,
+) -> Tree
+where
+    A: GlobalAlignmentMatrix<S> + Sync,
+    S: Scoring + Clone,
+    N: AsRef<str>,
+    Q: AsRef<str> + Sync,
+{
+    assert_eq!(names.len(), sequences.len(), "every sequence needs a name");
+    let distances = distance_matrix(aligner, sequences, PairwiseMetric::NormalizedDistance);
+    cluster(&distances, names, linkage)
+}
@@ -0,0 +1,501 @@
+use crate::align::batch::{condensed_map, squareform};
+use crate::align::global_base::GlobalAlignmentModel;
+use crate::align::{GlobalAlignmentMatrix, Scoring};
+use spindalis::utils::Arr2D;
+
+// Substitution model behind a corrected nucleotide distance. Each one
+// estimates the expected number of substitutions per site from the pairs of
+// bases in an alignment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NucleotideModel {
+    // Proportion of differing sites, without correction
+    PDistance,
+    // Jukes and Cantor (1969): equal base frequencies and rates
+    JukesCantor,
+    // Kimura (1980): separate transition and transversion rates
+    Kimura2P,
+    // Felsenstein (1981): unequal base frequencies, equal rates
+    Felsenstein81,
+    // Tamura and Nei (1993): unequal frequencies, separate purine and
+    // pyrimidine transition rates
+    TamuraNei,
+    // Lockhart et al. (1994), in Lake's paralinear form: consistent even when
+    // base composition differs between the sequences
+    LogDet,
+}
+
+// Correction applied to the proportion p of differing amino acids
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProteinModel {
+    // p itself, without correction
+    PDistance,
+    // -ln(1 - p): substitutions at each site follow a Poisson process
+    Poisson,
+    // Kimura (1983): -ln(1 - p - 0.2 p^2), an empirical fit to PAM
+    // distances
+    Kimura,
+    // Poisson with rates varying across sites as a gamma distribution of
+    // shape alpha: alpha ((1 - p)^(-1 / alpha) - 1). Small alpha means
+    // strong variation; as alpha grows it approaches `Poisson`.
+    Gamma(f64),
+}
+
+// Which sites are compared when there are gaps
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GapHandling {
+    // Drop the sites where either sequence of the pair has a gap
+    PairwiseDeletion,
+    // Drop the columns that have a gap in any sequence of the matrix. Same
+    // as `PairwiseDeletion` for a single pair.
+    CompleteDeletion,
+}
+
+// How IUPAC ambiguity codes such as R or N are counted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AmbiguityHandling {
+    // Drop the sites where either base is ambiguous
+    Skip,
+    // Spread the site evenly over every pair of bases the codes allow, so an
+    // R against an A counts as half an identity and half a transition
+    Average,
+}
+
+// Corrected distance between aligned nucleotide sequences. Gaps are '-' or
+// '.', U is read as T and '?' as N. Sites holding any other character, such
+// as a digit or an X, are skipped. Distances are None when the model cannot
+// be applied, because no sites are left to compare or because the sequences
+// are too divergent for the correction (its logarithm is undefined).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NucleotideDistance {
+    pub model: NucleotideModel,
+    pub gaps: GapHandling,
+    pub ambiguity: AmbiguityHandling,
+}
+
+impl Default for NucleotideDistance {
+    fn default() -> Self {
+        NucleotideDistance {
+            model: NucleotideModel::JukesCantor,
+            gaps: GapHandling::PairwiseDeletion,
+            ambiguity: AmbiguityHandling::Skip,
+        }
+    }
+}
+
+// Corrected distance between aligned protein sequences. Gaps are '-' or
+// '.'. Sites holding an ambiguous residue (B, Z, J, X, '?'), a stop ('*')
+// or a character that is not an amino acid code are skipped. Distances are
+// None when no sites are left to compare or the sequences are too divergent
+// for the correction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProteinDistance {
+    pub model: ProteinModel,
+    pub gaps: GapHandling,
+}
+
+impl Default for ProteinDistance {
+    fn default() -> Self {
+        ProteinDistance {
+            model: ProteinModel::Poisson,
+            gaps: GapHandling::PairwiseDeletion,
+        }
+    }
+}
+
+// Bases in the order A, C, G, T
+const A: usize = 0;
+const C: usize = 1;
+const G: usize = 2;
+const T: usize = 3;
+
+impl NucleotideDistance {
+    pub fn model(&self, value: NucleotideModel) -> Self {
+        Self {
+            model: value,
+            gaps: self.gaps,
+            ambiguity: self.ambiguity,
+        }
+    }
+
+    pub fn gaps(&self, value: GapHandling) -> Self {
+        Self {
+            model: self.model,
+            gaps: value,
+            ambiguity: self.ambiguity,
+        }
+    }
+
+    pub fn ambiguity(&self, value: AmbiguityHandling) -> Self {
+        Self {
+            model: self.model,
+            gaps: self.gaps,
+            ambiguity: value,
+        }
+    }
+
+    // Distance between two rows of an alignment, which must have the same
+    // length
+    pub fn between(&self, first: &str, second: &str) -> Option<f64> {
+        let first: Vec<char> = first.chars().collect();
+        let second: Vec<char> = second.chars().collect();
+        assert_eq!(
+            first.len(),
+            second.len(),
+            "aligned sequences must have the same length"
+        );
+        let sites = vec![true; first.len()];
+        self.estimate(&self.pairs(&first, &second, &sites))
+    }
+
+    // Distance from the first alignment a global aligner reports
+    pub fn alignment(&self, model: &GlobalAlignmentModel) -> Option<f64> {
+        let (first, second) = first_alignment(model);
+        self.between(&first, &second)
+    }
+
+    // Symmetric matrix of distances between the rows of a multiple
+    // alignment, in the form the `phylo` tree builders take. None if the
+    // distance is undefined for any pair.
+    pub fn matrix<Q: AsRef<str>>(&self, sequences: &[Q]) -> Option<Arr2D<f64>> {
+        let (rows, sites) = columns(sequences, self.gaps);
+
+        let n = rows.len();
+        let mut matrix = Arr2D::full(0.0, n, n);
+        for i in 0..n {
+            for j in i + 1..n {
+                let distance = self.estimate(&self.pairs(&rows[i], &rows[j], &sites))?;
+                matrix[i][j] = distance;
+                matrix[j][i] = distance;
+            }
+        }
+        Some(matrix)
+    }
+
+    // All-vs-all matrix for unaligned sequences: every pair is aligned
+    // with `aligner` and the first alignment of each pair is corrected, as
+    // in `ProteinDistance::distance_matrix`. None if the distance is
+    // undefined for any pair.
+    pub fn distance_matrix<A, S, Q>(&self, aligner: &A, sequences: &[Q]) -> Option<Arr2D<f64>>
+    where
+        A: GlobalAlignmentMatrix<S> + Sync,
+        S: Scoring + Clone,
+        Q: AsRef<str> + Sync,
+    {
+        let condensed: Option<Vec<f64>> =
+            condensed_map(aligner, sequences, |model| self.alignment(model))
+                .into_iter()
+                .collect();
+        Some(squareform(&condensed?, sequences.len()))
+    }
+
+    // Divergence matrix: how often base x in the first sequence is aligned
+    // with base y in the second, over the sites that are compared
+    fn pairs(&self, first: &[char], second: &[char], sites: &[bool]) -> [[f64; 4]; 4] {
+        let mut counts = [[0.0; 4]; 4];
+        for k in (0..first.len()).filter(|&k| sites[k]) {
+            let (a, b) = (first[k], second[k]);
+            if is_gap(a) || is_gap(b) {
+                continue;
+            }
+            let (Some(a), Some(b)) = (bases(a), bases(b)) else {
+                continue;
+            };
+            if self.ambiguity == AmbiguityHandling::Skip && (a.len() > 1 || b.len() > 1) {
+                continue;
+            }
+            let weight = 1.0 / (a.len() * b.len()) as f64;
+            for &x in a {
+                for &y in b {
+                    counts[x][y] += weight;
+                }
+            }
+        }
+        counts
+    }
+
+    fn estimate(&self, counts: &[[f64; 4]; 4]) -> Option<f64> {
+        let total: f64 = counts.iter().flatten().sum();
+        if total == 0.0 {
+            return None;
+        }
+        let frequency = |x: usize, y: usize| (counts[x][y] + counts[y][x]) / total;
+        let p = 1.0 - (0..4).map(|x| counts[x][x]).sum::<f64>() / total;
+        let purine = frequency(A, G);
+        let pyrimidine = frequency(C, T);
+        let transversions = p - purine - pyrimidine;
+        let base: Vec<f64> = (0..4)
+            .map(|x| (0..4).map(|y| counts[x][y] + counts[y][x]).sum::<f64>() / (2.0 * total))
+            .collect();
+
+        let distance = match self.model {
+            NucleotideModel::PDistance => Some(p),
+            NucleotideModel::JukesCantor => log_term(0.75, 1.0 - 4.0 * p / 3.0),
+            NucleotideModel::Kimura2P => {
+                let transitions = purine + pyrimidine;
+                Some(
+                    log_term(0.5, 1.0 - 2.0 * transitions - transversions)?
+                        + log_term(0.25, 1.0 - 2.0 * transversions)?,
+                )
+            }
+            NucleotideModel::Felsenstein81 => {
+                let b = 1.0 - base.iter().map(|f| f * f).sum::<f64>();
+                log_term(b, 1.0 - ratio(p, b))
+            }
+            NucleotideModel::TamuraNei => {
+                let (ag, ct) = (base[A] * base[G], base[C] * base[T]);
+                let purines = base[A] + base[G];
+                let pyrimidines = base[C] + base[T];
+                Some(
+                    log_term(
+                        2.0 * ratio(ag, purines),
+                        1.0 - ratio(purines * purine, 2.0 * ag)
+                            - ratio(transversions, 2.0 * purines),
+                    )? + log_term(
+                        2.0 * ratio(ct, pyrimidines),
+                        1.0 - ratio(pyrimidines * pyrimidine, 2.0 * ct)
+                            - ratio(transversions, 2.0 * pyrimidines),
+                    )? + log_term(
+                        2.0 * (purines * pyrimidines
+                            - ratio(ag * pyrimidines, purines)
+                            - ratio(ct * purines, pyrimidines)),
+                        1.0 - ratio(transversions, 2.0 * purines * pyrimidines),
+                    )?,
+                )
+            }
+            NucleotideModel::LogDet => log_det(counts, total),
+        };
+        // Rounding can leave identical sequences a hair below zero
+        distance.map(|d| d.max(0.0))
+    }
+}
+
+impl ProteinDistance {
+    pub fn model(&self, value: ProteinModel) -> Self {
+        Self {
+            model: value,
+            gaps: self.gaps,
+        }
+    }
+
+    pub fn gaps(&self, value: GapHandling) -> Self {
+        Self {
+            model: self.model,
+            gaps: value,
+        }
+    }
+
+    // Distance between two rows of an alignment, which must have the same
+    // length
+    pub fn between(&self, first: &str, second: &str) -> Option<f64> {
+        let first: Vec<char> = first.chars().collect();
+        let second: Vec<char> = second.chars().collect();
+        assert_eq!(
+            first.len(),
+            second.len(),
+            "aligned sequences must have the same length"
+        );
+        let sites = vec![true; first.len()];
+        self.estimate(&first, &second, &sites)
+    }
+
+    // Distance from the first alignment a global aligner reports
+    pub fn alignment(&self, model: &GlobalAlignmentModel) -> Option<f64> {
+        let (first, second) = first_alignment(model);
+        self.between(&first, &second)
+    }
+
+    // Symmetric matrix of distances between the rows of a multiple
+    // alignment. None if the distance is undefined for any pair.
+    pub fn matrix<Q: AsRef<str>>(&self, sequences: &[Q]) -> Option<Arr2D<f64>> {
+        let (rows, sites) = columns(sequences, self.gaps);
+
+        let n = rows.len();
+        let mut matrix = Arr2D::full(0.0, n, n);
+        for i in 0..n {
+            for j in i + 1..n {
+                let distance = self.estimate(&rows[i], &rows[j], &sites)?;
+                matrix[i][j] = distance;
+                matrix[j][i] = distance;
+            }
+        }
+        Some(matrix)
+    }
+
+    // All-vs-all matrix for unaligned sequences: every pair is aligned
+    // with `aligner`, like `distance_matrix`, and the first alignment of
+    // each pair is corrected. With the `parallel` feature the pairs are
+    // spread across the rayon thread pool. None if the distance is
+    // undefined for any pair.
+    pub fn distance_matrix<A, S, Q>(&self, aligner: &A, sequences: &[Q]) -> Option<Arr2D<f64>>
+    where
+        A: GlobalAlignmentMatrix<S> + Sync,
+        S: Scoring + Clone,
+        Q: AsRef<str> + Sync,
+    {
+        let condensed: Option<Vec<f64>> =
+            condensed_map(aligner, sequences, |model| self.alignment(model))
+                .into_iter()
+                .collect();
+        Some(squareform(&condensed?, sequences.len()))
+    }
+
+    fn estimate(&self, first: &[char], second: &[char], sites: &[bool]) -> Option<f64> {
+        let mut compared = 0;
+        let mut differences = 0;
+        for k in (0..first.len()).filter(|&k| sites[k]) {
+            let (a, b) = (first[k], second[k]);
+            if is_gap(a) || is_gap(b) || !is_residue(a) || !is_residue(b) {
+                continue;
+            }
+            compared += 1;
+            if !a.eq_ignore_ascii_case(&b) {
+                differences += 1;
+            }
+        }
+        if compared == 0 {
+            return None;
+        }
+        let p = differences as f64 / compared as f64;
+        let distance = match self.model {
+            ProteinModel::PDistance => Some(p),
+            ProteinModel::Poisson => log_term(1.0, 1.0 - p),
+            ProteinModel::Kimura => log_term(1.0, 1.0 - p - 0.2 * p * p),
+            ProteinModel::Gamma(alpha) => {
+                assert!(alpha > 0.0, "gamma shape alpha must be positive");
+                (p < 1.0).then(|| alpha * ((1.0 - p).powf(-1.0 / alpha) - 1.0))
+            }
+        };
+        distance.map(|d| d.max(0.0))
+    }
+}
+
+// Rows of a multiple alignment as characters, and which of its columns are
+// compared
+fn columns<Q: AsRef<str>>(sequences: &[Q], gaps: GapHandling) -> (Vec<Vec<char>>, Vec<bool>) {
+    let rows: Vec<Vec<char>> = sequences
+        .iter()
+        .map(|sequence| sequence.as_ref().chars().collect())
+        .collect();
+    let width = rows.first().map_or(0, Vec::len);
+    assert!(
+        rows.iter().all(|row| row.len() == width),
+        "aligned sequences must have the same length"
+    );
+    let sites = (0..width)
+        .map(|k| match gaps {
+            GapHandling::PairwiseDeletion => true,
+            GapHandling::CompleteDeletion => rows.iter().all(|row| !is_gap(row[k])),
+        })
+        .collect();
+    (rows, sites)
+}
+
+fn first_alignment(model: &GlobalAlignmentModel) -> (String, String) {
+    let aligned = model.align();
+    let (first, second) = aligned[0].split_once('\n').unwrap();
+    (first.to_string(), second.to_string())
+}
+
+// -weight * ln(argument), where a zero weight makes the term vanish
+fn log_term(weight: f64, argument: f64) -> Option<f64> {
+    if weight == 0.0 {
+        Some(0.0)
+    } else if argument > 0.0 {
+        Some(-weight * argument.ln())
+    } else {
+        None
+    }
+}
+
+// Quotient that is 0 when the divisor is, for base frequencies that are
+// absent along with every substitution that would divide by them
+fn ratio(numerator: f64, divisor: f64) -> f64 {
+    if divisor == 0.0 {
+        0.0
+    } else {
+        numerator / divisor
+    }
+}
+
+// -1/4 (ln det J - 1/2 ln(det Fx det Fy)), with J the divergence matrix as
+// proportions and Fx, Fy the base frequencies of each sequence on its
+// diagonal. Bases absent from both sequences are left out.
+fn log_det(counts: &[[f64; 4]; 4], total: f64) -> Option<f64> {
+    let present: Vec<usize> = (0..4)
+        .filter(|&x| (0..4).any(|y| counts[x][y] > 0.0 || counts[y][x] > 0.0))
+        .collect();
+    let mut joint: Vec<Vec<f64>> = present
+        .iter()
+        .map(|&x| present.iter().map(|&y| counts[x][y] / total).collect())
+        .collect();
+    let rows: f64 = joint.iter().map(|row| row.iter().sum::<f64>()).product();
+    let columns: f64 = (0..present.len())
+        .map(|y| joint.iter().map(|row| row[y]).sum::<f64>())
+        .product();
+    let det = determinant(&mut joint);
+    if det <= 0.0 || rows <= 0.0 || columns <= 0.0 {
+        return None;
+    }
+    Some(-0.25 * (det.ln() - 0.5 * (rows.ln() + columns.ln())))
+}
+
+// Gaussian elimination with partial pivoting
+fn determinant(matrix: &mut [Vec<f64>]) -> f64 {
+    let n = matrix.len();
+    let mut det = 1.0;
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))
+            .unwrap();
+        if matrix[pivot][col] == 0.0 {
+            return 0.0;
+        }
+        if pivot != col {
+            matrix.swap(pivot, col);
+            det = -det;
+        }
+        det *= matrix[col][col];
+        let (above, below) = matrix.split_at_mut(col + 1);
+        let pivot_row = &above[col];
+        for row in below {
+            let factor = row[col] / pivot_row[col];
+            for (value, &subtract) in row.iter_mut().zip(pivot_row).skip(col) {
+                *value -= factor * subtract;
+            }
+        }
+    }
+    det
+}
+
+pub(crate) fn is_gap(c: char) -> bool {
+    c == '-' || c == '.'
+}
+
+// Whether a protein character names one amino acid, rather than an
+// ambiguity code, a stop or something else
+fn is_residue(c: char) -> bool {
+    "ACDEFGHIKLMNOPQRSTUVWY".contains(c.to_ascii_uppercase())
+}
+
+// Bases an IUPAC code stands for, or None if it is not one
+pub(crate) fn bases(code: char) -> Option<&'static [usize]> {
+    let bases: &'static [usize] = match code.to_ascii_uppercase() {
+        'A' => &[A],
+        'C' => &[C],
+        'G' => &[G],
+        'T' | 'U' => &[T],
+        'R' => &[A, G],
+        'Y' => &[C, T],
+        'S' => &[C, G],
+        'W' => &[A, T],
+        'K' => &[G, T],
+        'M' => &[A, C],
+        'B' => &[C, G, T],
+        'D' => &[A, G, T],
+        'H' => &[A, C, T],
+        'V' => &[A, C, G],
+        'N' | '?' => &[A, C, G, T],
+        _ => return None,
+    };
+    Some(bases)
+}
@@ -0,0 +1,790 @@
+use crate::phylo::distance::bases;
+use crate::phylo::parsimony::leaf_rows;
+use crate::phylo::tree::{NodeId, Tree};
+use std::collections::HashMap;
+
+// Time-reversible nucleotide substitution model. Base frequencies are in
+// the order A, C, G, T and are normalised to sum to 1. GTR exchange rates
+// are in the order AC, AG, AT, CG, CT, GT. Every model is scaled so one unit
+// of branch length is one expected substitution per site.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubstitutionModel {
+    // Jukes and Cantor (1969): equal frequencies and rates
+    JukesCantor,
+    // Kimura (1980): transitions `kappa` times as fast as transversions
+    Kimura2P {
+        kappa: f64,
+    },
+    // Hasegawa, Kishino and Yano (1985): K80 with unequal frequencies
+    Hky85 {
+        kappa: f64,
+        frequencies: [f64; 4],
+    },
+    // General time-reversible model (Tavare 1986)
+    Gtr {
+        rates: [f64; 6],
+        frequencies: [f64; 4],
+    },
+}
+
+// Likelihood of an alignment on a tree with branch lengths, computed with
+// Felsenstein's pruning algorithm. With `gamma`, rates vary across sites
+// following a discrete gamma distribution (Yang 1994): every site is
+// averaged over equally likely categories, each scaled by its mean rate.
+//
+// Rows are matched to leaves by name. Gaps, '?', N and characters that are
+// not IUPAC codes fit any base, and other codes fit the bases they stand
+// for. Missing branch lengths count as 0. Reversible models make the
+// likelihood independent of where the tree is rooted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeLikelihood {
+    pub model: SubstitutionModel,
+    pub alpha: Option<f64>,
+    pub categories: usize,
+}
+
+// Tree with optimised branch lengths, its log-likelihood and how many
+// passes over the branches it took
+#[derive(Clone, Debug, PartialEq)]
+pub struct FittedTree {
+    pub tree: Tree,
+    pub log_likelihood: f64,
+    pub rounds: usize,
+}
+
+// Search range for a branch length, and when to stop optimising
+const MIN_LENGTH: f64 = 1e-8;
+const MAX_LENGTH: f64 = 10.0;
+const LENGTH_TOLERANCE: f64 = 1e-7;
+const LIKELIHOOD_TOLERANCE: f64 = 1e-6;
+const MAX_ROUNDS: usize = 100;
+// Starting length for branches that have none
+const DEFAULT_LENGTH: f64 = 0.1;
+
+impl Default for TreeLikelihood {
+    fn default() -> Self {
+        TreeLikelihood {
+            model: SubstitutionModel::JukesCantor,
+            alpha: None,
+            categories: 4,
+        }
+    }
+}
+
+impl TreeLikelihood {
+    pub fn model(&self, value: SubstitutionModel) -> Self {
+        Self {
+            model: value,
+            alpha: self.alpha,
+            categories: self.categories,
+        }
+    }
+
+    // +G rate variation with gamma shape `alpha` over `categories` rates
+    pub fn gamma(&self, alpha: f64, categories: usize) -> Self {
+        assert!(alpha > 0.0, "gamma shape alpha must be positive");
+        assert!(categories > 0, "gamma needs at least one rate category");
+        Self {
+            model: self.model.clone(),
+            alpha: Some(alpha),
+            categories,
+        }
+    }
+
+    // No rate variation across sites
+    pub fn uniform_rates(&self) -> Self {
+        Self {
+            model: self.model.clone(),
+            alpha: None,
+            categories: self.categories,
+        }
+    }
+
+    // Natural log-likelihood of each column of the alignment
+    pub fn site_log_likelihoods<N: AsRef<str>, Q: AsRef<str>>(
+        &self,
+        tree: &Tree,
+        names: &[N],
+        sequences: &[Q],
+    ) -> Vec<f64> {
+        let patterns = Patterns::new(tree, names, sequences);
+        let values = self.process().pattern_log_likelihoods(tree, &patterns);
+        patterns.sites.iter().map(|&k| values[k]).collect()
+    }
+
+    pub fn log_likelihood<N: AsRef<str>, Q: AsRef<str>>(
+        &self,
+        tree: &Tree,
+        names: &[N],
+        sequences: &[Q],
+    ) -> f64 {
+        let patterns = Patterns::new(tree, names, sequences);
+        let values = self.process().pattern_log_likelihoods(tree, &patterns);
+        weighted(&values, &patterns.weights)
+    }
+
+    // Maximises the likelihood over branch lengths, one branch at a time
+    // with Brent's method, until a pass over every branch gains less than
+    // 1e-6 log units. Topology and model parameters stay as they are.
+    pub fn optimize<N: AsRef<str>, Q: AsRef<str>>(
+        &self,
+        tree: &Tree,
+        names: &[N],
+        sequences: &[Q],
+    ) -> FittedTree {
+        let patterns = Patterns::new(tree, names, sequences);
+        let process = self.process();
+        let mut tree = tree.clone();
+        let branches: Vec<NodeId> = tree
+            .preorder()
+            .into_iter()
+            .filter(|&id| id != tree.root())
+            .collect();
+        for &id in &branches {
+            let length = tree.node(id).branch_length.unwrap_or(DEFAULT_LENGTH);
+            tree.node_mut(id).branch_length = Some(length.clamp(MIN_LENGTH, MAX_LENGTH));
+        }
+
+        let mut best = weighted(
+            &process.pattern_log_likelihoods(&tree, &patterns),
+            &patterns.weights,
+        );
+        let mut rounds = 0;
+        while rounds < MAX_ROUNDS {
+            rounds += 1;
+            let before = best;
+            for &id in &branches {
+                // The rest of the tree stays fixed while the branch's length
+                // varies, so its partials on either side are computed once
+                let ends = process.branch_ends(&tree, &patterns, id);
+                let (length, negative) = brent(
+                    |length| {
+                        let values = process.branch_log_likelihoods(&ends, length);
+                        -weighted(&values, &patterns.weights)
+                    },
+                    MIN_LENGTH,
+                    MAX_LENGTH,
+                    LENGTH_TOLERANCE,
+                );
+                if -negative > best {
+                    best = -negative;
+                    tree.node_mut(id).branch_length = Some(length);
+                }
+            }
+            if best - before < LIKELIHOOD_TOLERANCE {
+                break;
+            }
+        }
+        FittedTree {
+            tree,
+            log_likelihood: best,
+            rounds,
+        }
+    }
+
+    fn process(&self) -> Process {
+        Process {
+            eigen: Eigen::new(&self.model),
+            rates: match self.alpha {
+                Some(alpha) => discrete_gamma_rates(alpha, self.categories),
+                None => vec![1.0],
+            },
+        }
+    }
+}
+
+// The model's eigen decomposition and the rate of each category, which stay
+// the same for every tree one call evaluates
+struct Process {
+    eigen: Eigen,
+    rates: Vec<f64>,
+}
+
+impl Process {
+    fn pattern_log_likelihoods(&self, tree: &Tree, patterns: &Patterns) -> Vec<f64> {
+        let by_category: Vec<Vec<f64>> = self
+            .rates
+            .iter()
+            .map(|&rate| {
+                let below = self.below(tree, patterns, rate);
+                let root = &below[tree.root()];
+                root.values
+                    .iter()
+                    .zip(&root.scale)
+                    .map(|(values, scale)| {
+                        let sum: f64 = (0..4).map(|a| self.eigen.frequencies[a] * values[a]).sum();
+                        sum.ln() + scale
+                    })
+                    .collect()
+            })
+            .collect();
+        average(&by_category)
+    }
+
+    // Partials above and below the branch leading to `id`, in each category
+    fn branch_ends(
+        &self,
+        tree: &Tree,
+        patterns: &Patterns,
+        id: NodeId,
+    ) -> Vec<(Partials, Partials)> {
+        self.rates
+            .iter()
+            .map(|&rate| {
+                let mut below = self.below(tree, patterns, rate);
+                let mut above = self.above(tree, &below, rate);
+                (
+                    std::mem::take(&mut above[id]),
+                    std::mem::take(&mut below[id]),
+                )
+            })
+            .collect()
+    }
+
+    // Log-likelihood of every pattern with the branch between `ends` set to
+    // `length`
+    fn branch_log_likelihoods(&self, ends: &[(Partials, Partials)], length: f64) -> Vec<f64> {
+        let by_category: Vec<Vec<f64>> = ends
+            .iter()
+            .zip(&self.rates)
+            .map(|((above, below), &rate)| {
+                let p = self.eigen.transition(length * rate);
+                (0..above.values.len())
+                    .map(|k| {
+                        let (up, down) = (&above.values[k], &below.values[k]);
+                        let sum: f64 = (0..4)
+                            .map(|a| up[a] * (0..4).map(|b| p[a][b] * down[b]).sum::<f64>())
+                            .sum();
+                        sum.ln() + above.scale[k] + below.scale[k]
+                    })
+                    .collect()
+            })
+            .collect();
+        average(&by_category)
+    }
+
+    // Felsenstein's pruning: the likelihood of each node's subtree given
+    // its state
+    fn below(&self, tree: &Tree, patterns: &Patterns, rate: f64) -> Vec<Partials> {
+        let count = patterns.weights.len();
+        let mut partials = vec![Partials::default(); tree.len()];
+        for id in tree.postorder() {
+            let node = tree.node(id);
+            if node.is_leaf() {
+                partials[id] = Partials {
+                    values: patterns.tips[&id].clone(),
+                    scale: vec![0.0; count],
+                };
+                continue;
+            }
+            let mut partial = Partials::ones(count);
+            for &child in node.children() {
+                let p = self.transition(tree, child, rate);
+                partial.absorb(&p, &partials[child]);
+            }
+            partial.rescale();
+            partials[id] = partial;
+        }
+        partials
+    }
+
+    // The likelihood of everything outside each node's subtree given the
+    // state of its parent, from the root down
+    fn above(&self, tree: &Tree, below: &[Partials], rate: f64) -> Vec<Partials> {
+        let count = below[tree.root()].values.len();
+        let mut partials = vec![Partials::default(); tree.len()];
+        for id in tree.preorder() {
+            let node = tree.node(id);
+            // Everything outside the node's subtree, given its own state
+            let outside = match node.parent() {
+                None => Partials {
+                    values: vec![self.eigen.frequencies; count],
+                    scale: vec![0.0; count],
+                },
+                Some(_) => partials[id].spread(&self.transition(tree, id, rate)),
+            };
+            for &child in node.children() {
+                let mut partial = outside.clone();
+                for &sibling in node.children().iter().filter(|&&other| other != child) {
+                    let p = self.transition(tree, sibling, rate);
+                    partial.absorb(&p, &below[sibling]);
+                }
+                partial.rescale();
+                partials[child] = partial;
+            }
+        }
+        partials
+    }
+
+    // Transition probabilities along the branch leading to `id`
+    fn transition(&self, tree: &Tree, id: NodeId, rate: f64) -> [[f64; 4]; 4] {
+        let length = tree.node(id).branch_length.unwrap_or(0.0);
+        self.eigen.transition(length * rate)
+    }
+}
+
+// Conditional likelihoods of the four states for every pattern, divided by
+// exp(scale) to keep deep trees from underflowing
+#[derive(Clone, Debug, Default)]
+struct Partials {
+    values: Vec<[f64; 4]>,
+    scale: Vec<f64>,
+}
+
+impl Partials {
+    fn ones(count: usize) -> Self {
+        Partials {
+            values: vec![[1.0; 4]; count],
+            scale: vec![0.0; count],
+        }
+    }
+
+    // Multiplies in a child's partials carried up its branch
+    fn absorb(&mut self, p: &[[f64; 4]; 4], child: &Partials) {
+        for (k, values) in self.values.iter_mut().enumerate() {
+            let below = &child.values[k];
+            for (a, value) in values.iter_mut().enumerate() {
+                *value *= (0..4).map(|b| p[a][b] * below[b]).sum::<f64>();
+            }
+            self.scale[k] += child.scale[k];
+        }
+    }
+
+    // Carries partials given the parent's state down a branch, to partials
+    // given the child's
+    fn spread(&self, p: &[[f64; 4]; 4]) -> Self {
+        let values = self
+            .values
+            .iter()
+            .map(|above| std::array::from_fn(|b| (0..4).map(|a| above[a] * p[a][b]).sum()))
+            .collect();
+        Partials {
+            values,
+            scale: self.scale.clone(),
+        }
+    }
+
+    fn rescale(&mut self) {
+        for (values, scale) in self.values.iter_mut().zip(&mut self.scale) {
+            let largest = values.iter().copied().fold(0.0, f64::max);
+            if largest > 0.0 {
+                values.iter_mut().for_each(|value| *value /= largest);
+                *scale += largest.ln();
+            }
+        }
+    }
+}
+
+// Log-likelihood of each pattern averaged over equally likely categories,
+// in log space
+fn average(by_category: &[Vec<f64>]) -> Vec<f64> {
+    let categories = by_category.len() as f64;
+    let count = by_category.first().map_or(0, Vec::len);
+    (0..count)
+        .map(|k| {
+            let highest = by_category
+                .iter()
+                .map(|values| values[k])
+                .fold(f64::NEG_INFINITY, f64::max);
+            if highest == f64::NEG_INFINITY {
+                return highest;
+            }
+            let sum: f64 = by_category
+                .iter()
+                .map(|values| (values[k] - highest).exp())
+                .sum();
+            highest + (sum / categories).ln()
+        })
+        .collect()
+}
+
+// Sum of the pattern log-likelihoods, each counted once per column
+fn weighted(values: &[f64], weights: &[usize]) -> f64 {
+    values
+        .iter()
+        .zip(weights)
+        .map(|(value, &weight)| value * weight as f64)
+        .sum()
+}
+
+// Mean rates of `categories` equally likely slices of a gamma distribution
+// with shape `alpha` and mean 1, from slowest to fastest (Yang 1994)
+pub fn discrete_gamma_rates(alpha: f64, categories: usize) -> Vec<f64> {
+    assert!(alpha > 0.0, "gamma shape alpha must be positive");
+    assert!(categories > 0, "gamma needs at least one rate category");
+    let k = categories as f64;
+    // With shape alpha and rate alpha, the mass below x is P(alpha, alpha x)
+    // and the mean below x is P(alpha + 1, alpha x)
+    let mut below = 0.0;
+    let mut rates = Vec::with_capacity(categories);
+    for i in 1..=categories {
+        let mean_below = if i == categories {
+            1.0
+        } else {
+            let cut = gamma_quantile(alpha, i as f64 / k);
+            regularized_gamma(alpha + 1.0, alpha * cut)
+        };
+        rates.push(k * (mean_below - below));
+        below = mean_below;
+    }
+    rates
+}
+
+// Patterns of characters across the leaves, each with how many columns
+// share it. Identical columns only need their likelihood computed once.
+struct Patterns {
+    // Partial likelihoods of each leaf, one per pattern
+    tips: HashMap<NodeId, Vec<[f64; 4]>>,
+    weights: Vec<usize>,
+    // Pattern of each column
+    sites: Vec<usize>,
+}
+
+impl Patterns {
+    fn new<N: AsRef<str>, Q: AsRef<str>>(tree: &Tree, names: &[N], sequences: &[Q]) -> Self {
+        let rows = leaf_rows(tree, names, sequences);
+        let leaves = tree.leaves();
+        let width = rows.values().next().map_or(0, Vec::len);
+        let columns: Vec<Vec<char>> = (0..width)
+            .map(|site| leaves.iter().map(|leaf| rows[leaf][site]).collect())
+            .collect();
+
+        let mut index: HashMap<Vec<char>, usize> = HashMap::new();
+        let mut tips: HashMap<NodeId, Vec<[f64; 4]>> =
+            leaves.iter().map(|&leaf| (leaf, Vec::new())).collect();
+        let mut weights = Vec::new();
+        let mut sites = Vec::with_capacity(width);
+        for column in columns {
+            let next = weights.len();
+            let k = *index.entry(column.clone()).or_insert(next);
+            if k == next {
+                weights.push(0);
+                for (leaf, &c) in leaves.iter().zip(&column) {
+                    let mut partial = [0.0; 4];
+                    match bases(c) {
+                        Some(codes) => {
+                            for &base in codes {
+                                partial[base] = 1.0;
+                            }
+                        }
+                        None => partial = [1.0; 4],
+                    }
+                    tips.get_mut(leaf).unwrap().push(partial);
+                }
+            }
+            weights[k] += 1;
+            sites.push(k);
+        }
+        Patterns {
+            tips,
+            weights,
+            sites,
+        }
+    }
+}
+
+// Eigen decomposition of a reversible rate matrix, through the symmetric
+// matrix S = diag(sqrt(pi)) Q diag(1 / sqrt(pi)), which shares its
+// eigenvalues
+struct Eigen {
+    frequencies: [f64; 4],
+    values: [f64; 4],
+    vectors: [[f64; 4]; 4],
+}
+
+impl Eigen {
+    fn new(model: &SubstitutionModel) -> Self {
+        let (rates, frequencies) = match model {
+            SubstitutionModel::JukesCantor => ([1.0; 6], [0.25; 4]),
+            SubstitutionModel::Kimura2P { kappa } => {
+                ([1.0, *kappa, 1.0, 1.0, *kappa, 1.0], [0.25; 4])
+            }
+            SubstitutionModel::Hky85 { kappa, frequencies } => {
+                ([1.0, *kappa, 1.0, 1.0, *kappa, 1.0], *frequencies)
+            }
+            SubstitutionModel::Gtr { rates, frequencies } => (*rates, *frequencies),
+        };
+        assert!(
+            rates.iter().all(|&rate| rate > 0.0),
+            "substitution rates must be positive"
+        );
+        assert!(
+            frequencies.iter().all(|&f| f > 0.0),
+            "base frequencies must be positive"
+        );
+        let sum: f64 = frequencies.iter().sum();
+        let frequencies = frequencies.map(|f| f / sum);
+
+        let mut exchange = [[0.0; 4]; 4];
+        let pairs = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+        for (&(i, j), &rate) in pairs.iter().zip(&rates) {
+            exchange[i][j] = rate;
+            exchange[j][i] = rate;
+        }
+        // Scale to one expected substitution per unit of time
+        let mean: f64 = (0..4)
+            .map(|i| {
+                (0..4)
+                    .map(|j| frequencies[i] * exchange[i][j] * frequencies[j])
+                    .sum::<f64>()
+            })
+            .sum();
+
+        let mut symmetric = [[0.0; 4]; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                if i != j {
+                    symmetric[i][j] =
+                        exchange[i][j] * (frequencies[i] * frequencies[j]).sqrt() / mean;
+                }
+            }
+            symmetric[i][i] = -(0..4).map(|j| exchange[i][j] * frequencies[j]).sum::<f64>() / mean;
+        }
+        let (values, vectors) = jacobi(symmetric);
+        Eigen {
+            frequencies,
+            values,
+            vectors,
+        }
+    }
+
+    // P(t)[a][b], the probability of b after time t starting from a
+    fn transition(&self, time: f64) -> [[f64; 4]; 4] {
+        let decay = self.values.map(|value| (value * time).exp());
+        let mut p = [[0.0; 4]; 4];
+        for (a, row) in p.iter_mut().enumerate() {
+            for (b, value) in row.iter_mut().enumerate() {
+                let sum: f64 = (0..4)
+                    .map(|k| self.vectors[a][k] * self.vectors[b][k] * decay[k])
+                    .sum();
+                *value = ((self.frequencies[b] / self.frequencies[a]).sqrt() * sum).max(0.0);
+            }
+        }
+        p
+    }
+}
+
+// Eigenvalues and eigenvectors (as columns) of a symmetric matrix by cyclic
+// Jacobi rotations
+fn jacobi(mut matrix: [[f64; 4]; 4]) -> ([f64; 4], [[f64; 4]; 4]) {
+    let mut vectors = [[0.0; 4]; 4];
+    for (i, row) in vectors.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+    for _ in 0..100 {
+        let off: f64 = (0..4)
+            .flat_map(|i| (0..4).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| matrix[i][j] * matrix[i][j])
+            .sum();
+        if off < 1e-30 {
+            break;
+        }
+        for p in 0..4 {
+            for q in p + 1..4 {
+                if matrix[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = (matrix[q][q] - matrix[p][p]) / (2.0 * matrix[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in matrix.iter_mut() {
+                    let (kp, kq) = (row[p], row[q]);
+                    row[p] = c * kp - s * kq;
+                    row[q] = s * kp + c * kq;
+                }
+                let (rp, rq) = (matrix[p], matrix[q]);
+                for k in 0..4 {
+                    matrix[p][k] = c * rp[k] - s * rq[k];
+                    matrix[q][k] = s * rp[k] + c * rq[k];
+                }
+                for row in vectors.iter_mut() {
+                    let (kp, kq) = (row[p], row[q]);
+                    row[p] = c * kp - s * kq;
+                    row[q] = s * kp + c * kq;
+                }
+            }
+        }
+    }
+    (
+        [matrix[0][0], matrix[1][1], matrix[2][2], matrix[3][3]],
+        vectors,
+    )
+}
+
+// Brent's (1973) minimisation of `f` on [lower, upper] by golden section
+// and parabolic steps, stopping once the minimum is pinned to `tolerance`.
+// Returns the minimum and the value there.
+fn brent(mut f: impl FnMut(f64) -> f64, lower: f64, upper: f64, tolerance: f64) -> (f64, f64) {
+    let golden = (3.0 - 5f64.sqrt()) / 2.0;
+    let epsilon = f64::EPSILON.sqrt();
+    let (mut a, mut b) = (lower, upper);
+    let mut x = a + golden * (b - a);
+    let (mut v, mut w) = (x, x);
+    let mut fx = f(x);
+    let (mut fv, mut fw) = (fx, fx);
+    let (mut d, mut e): (f64, f64) = (0.0, 0.0);
+
+    for _ in 0..200 {
+        let middle = (a + b) / 2.0;
+        let tol1 = epsilon * x.abs() + tolerance / 3.0;
+        let tol2 = 2.0 * tol1;
+        if (x - middle).abs() <= tol2 - (b - a) / 2.0 {
+            break;
+        }
+
+        // Try a parabola through x, v and w
+        let (mut p, mut q) = (0.0, 0.0);
+        if e.abs() > tol1 {
+            let r = (x - w) * (fx - fv);
+            q = (x - v) * (fx - fw);
+            p = (x - v) * q - (x - w) * r;
+            q = 2.0 * (q - r);
+            if q > 0.0 {
+                p = -p;
+            } else {
+                q = -q;
+            }
+        }
+        let previous = e;
+        if p.abs() >= (0.5 * q * previous).abs() || p <= q * (a - x) || p >= q * (b - x) {
+            e = if x < middle { b - x } else { a - x };
+            d = golden * e;
+        } else {
+            e = d;
+            d = p / q;
+            let u = x + d;
+            if u - a < tol2 || b - u < tol2 {
+                d = if x < middle { tol1 } else { -tol1 };
+            }
+        }
+
+        let u = if d.abs() >= tol1 {
+            x + d
+        } else if d > 0.0 {
+            x + tol1
+        } else {
+            x - tol1
+        };
+        let fu = f(u);
+        if fu <= fx {
+            if u < x {
+                b = x;
+            } else {
+                a = x;
+            }
+            (v, fv, w, fw, x, fx) = (w, fw, x, fx, u, fu);
+        } else {
+            if u < x {
+                a = u;
+            } else {
+                b = u;
+            }
+            if fu <= fw || w == x {
+                (v, fv, w, fw) = (w, fw, u, fu);
+            } else if fu <= fv || v == x || v == w {
+                (v, fv) = (u, fu);
+            }
+        }
+    }
+    (x, fx)
+}
+
+// Regularised lower incomplete gamma function P(a, x), by its series for
+// small x and its continued fraction otherwise. Both take on the order of
+// sqrt(a) terms near x = a.
+fn regularized_gamma(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let prefix = a * x.ln() - x - ln_gamma(a);
+    if x < a + 1.0 {
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..100_000 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-16 {
+                break;
+            }
+        }
+        (sum.ln() + prefix).exp()
+    } else {
+        // Modified Lentz evaluation of the continued fraction for Q(a, x)
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..100_000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-16 {
+                break;
+            }
+        }
+        1.0 - (h.ln() + prefix).exp()
+    }
+}
+
+// x with P(alpha, alpha x) = probability, i.e. a quantile of the gamma
+// distribution with shape alpha and mean 1, by bisection
+fn gamma_quantile(alpha: f64, probability: f64) -> f64 {
+    let mut high = 1.0;
+    while regularized_gamma(alpha, alpha * high) < probability {
+        high *= 2.0;
+    }
+    let mut low = 0.0;
+    for _ in 0..200 {
+        let middle = (low + high) / 2.0;
+        if regularized_gamma(alpha, alpha * middle) < probability {
+            low = middle;
+        } else {
+            high = middle;
+        }
+        if high - low <= 1e-15 * high {
+            break;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// Lanczos approximation of ln(Gamma(x)) for x > 0
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| {
+            sum + c / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
@@ -1 +1,20 @@
+pub mod cluster;
+pub mod distance;
+pub mod likelihood;
+pub mod neighbor_joining;
+pub mod newick;
+pub mod nexus;
+pub mod parsimony;
+pub mod tree;
 
+pub use cluster::{Linkage, cluster, tree_from_sequences, upgma, wpgma};
+pub use distance::{
+    AmbiguityHandling, GapHandling, NucleotideDistance, NucleotideModel, ProteinDistance,
+    ProteinModel,
+};
+pub use likelihood::{FittedTree, SubstitutionModel, TreeLikelihood, discrete_gamma_rates};
+pub use neighbor_joining::{bionj, neighbor_joining};
+pub use newick::{NewickError, parse_newick, parse_newick_trees, to_newick};
+pub use nexus::{Nexus, NexusError, parse_nexus, to_nexus};
+pub use parsimony::{ParsimonyScore, StepMatrix, fitch, sankoff};
+pub use tree::{Node, NodeId, Tree};
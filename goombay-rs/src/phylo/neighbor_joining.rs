@@ -0,0 +1,142 @@
+use crate::phylo::tree::{Clade, Tree};
+use spindalis::utils::Arr2D;
+
+// How the distances to a newly joined node are estimated
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Reduction {
+    // Saitou and Nei (1987): the plain average of the two paths
+    Classic,
+    // Gascuel (1997): a weighted average chosen to minimise the variance of
+    // the new distances, tracked in a matrix alongside them
+    Bionj,
+}
+
+// Saitou-Nei neighbor joining. Builds an unrooted tree with branch lengths
+// and does not assume a molecular clock. Negative branch lengths are
+// clamped to zero.
+pub fn neighbor_joining<N: AsRef<str>>(distances: &Arr2D<f64>, names: &[N]) -> Tree {
+    join(distances, names, Reduction::Classic)
+}
+
+// BIONJ, neighbor joining with variance-weighted distance updates. Same
+// joining criterion and output as `neighbor_joining`, but usually more
+// accurate on noisy distances.
+pub fn bionj<N: AsRef<str>>(distances: &Arr2D<f64>, names: &[N]) -> Tree {
+    join(distances, names, Reduction::Bionj)
+}
+
+fn join<N: AsRef<str>>(distances: &Arr2D<f64>, names: &[N], reduction: Reduction) -> Tree {
+    let n = names.len();
+    assert!(n > 0, "neighbor joining needs at least one sequence");
+    assert!(
+        distances.height == n && distances.width == n,
+        "distance matrix must be {n} x {n} to match the names"
+    );
+
+    let mut clades: Vec<Clade> = names
+        .iter()
+        .map(|name| Clade {
+            name: Some(name.as_ref().to_string()),
+            children: Vec::new(),
+        })
+        .collect();
+    if n == 1 {
+        return Tree::from_clades(&clades, false);
+    }
+
+    // Clade held by each active slot
+    let mut slots: Vec<Option<usize>> = (0..n).map(Some).collect();
+    let mut d: Vec<Vec<f64>> = (0..n).map(|i| distances[i].to_vec()).collect();
+    let mut variance = d.clone();
+    let mut active = n;
+
+    while active > 3 {
+        let live: Vec<usize> = (0..n).filter(|&i| slots[i].is_some()).collect();
+        let totals: Vec<f64> = (0..n)
+            .map(|i| live.iter().map(|&k| d[i][k]).sum())
+            .collect();
+        let r = active as f64;
+
+        // Pair minimising Q(i, j) = (r - 2) d(i, j) - R(i) - R(j); ties go to
+        // the pair that comes first in the matrix
+        let mut best: Option<(f64, usize, usize)> = None;
+        for (a, &i) in live.iter().enumerate() {
+            for &j in &live[a + 1..] {
+                let q = (r - 2.0) * d[i][j] - totals[i] - totals[j];
+                if best.is_none_or(|(lowest, _, _)| q < lowest) {
+                    best = Some((q, i, j));
+                }
+            }
+        }
+        let (_, i, j) = best.unwrap();
+        let to_i = d[i][j] / 2.0 + (totals[i] - totals[j]) / (2.0 * (r - 2.0));
+        let to_j = d[i][j] - to_i;
+
+        let lambda = match reduction {
+            Reduction::Classic => 0.5,
+            Reduction::Bionj if variance[i][j] == 0.0 => 0.5,
+            Reduction::Bionj => {
+                let spread: f64 = live
+                    .iter()
+                    .filter(|&&k| k != i && k != j)
+                    .map(|&k| variance[j][k] - variance[i][k])
+                    .sum();
+                (0.5 + spread / (2.0 * (r - 2.0) * variance[i][j])).clamp(0.0, 1.0)
+            }
+        };
+        for &k in &live {
+            if k == i || k == j {
+                continue;
+            }
+            let joined = match reduction {
+                Reduction::Classic => (d[i][k] + d[j][k] - d[i][j]) / 2.0,
+                Reduction::Bionj => lambda * (d[i][k] - to_i) + (1.0 - lambda) * (d[j][k] - to_j),
+            };
+            let joined_variance = lambda * variance[i][k] + (1.0 - lambda) * variance[j][k]
+                - lambda * (1.0 - lambda) * variance[i][j];
+            d[i][k] = joined;
+            d[k][i] = joined;
+            variance[i][k] = joined_variance;
+            variance[k][i] = joined_variance;
+        }
+
+        clades.push(Clade {
+            name: None,
+            children: vec![
+                (slots[i].unwrap(), to_i.max(0.0)),
+                (slots[j].unwrap(), to_j.max(0.0)),
+            ],
+        });
+        slots[i] = Some(clades.len() - 1);
+        slots[j] = None;
+        active -= 1;
+    }
+
+    // The last two or three nodes meet at the centre of the tree
+    let live: Vec<usize> = (0..n).filter(|&i| slots[i].is_some()).collect();
+    let children = if let [a, b, c] = live[..] {
+        vec![
+            (
+                slots[a].unwrap(),
+                ((d[a][b] + d[a][c] - d[b][c]) / 2.0).max(0.0),
+            ),
+            (
+                slots[b].unwrap(),
+                ((d[a][b] + d[b][c] - d[a][c]) / 2.0).max(0.0),
+            ),
+            (
+                slots[c].unwrap(),
+                ((d[a][c] + d[b][c] - d[a][b]) / 2.0).max(0.0),
+            ),
+        ]
+    } else {
+        let (a, b) = (live[0], live[1]);
+        let half = (d[a][b] / 2.0).max(0.0);
+        vec![(slots[a].unwrap(), half), (slots[b].unwrap(), half)]
+    };
+    clades.push(Clade {
+        name: None,
+        children,
+    });
+    Tree::from_clades(&clades, false)
+}
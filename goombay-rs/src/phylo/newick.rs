@@ -0,0 +1,355 @@
+use crate::phylo::tree::{NodeId, Tree};
+use std::fmt;
+
+// Where and why a Newick string could not be read. Lines and columns start
+// at 1 and columns count characters, not bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewickError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for NewickError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for NewickError {}
+
+// Reads a single tree, which must end with ';'. Anything after it other than
+// whitespace and comments is an error.
+//
+// Labels may be quoted with single quotes, where '' stands for a quote, and
+// underscores are kept as they are. Comments in square brackets are skipped,
+// except for:
+// - a leading [&R] or [&U], which marks the tree as rooted or unrooted
+// - a number in brackets inside a node's label or length, e.g. A:0.1[95],
+//   which is read as that node's support
+// Unquoted numeric labels on internal nodes other than the root are
+// bootstrap values and are stored as support rather than as a name. Without
+// a rooting comment, a tree whose root has three or more children is
+// unrooted and any other tree is rooted.
+pub fn parse_newick(text: &str) -> Result<Tree, NewickError> {
+    let mut parser = Parser::new(text);
+    let tree = parser.tree()?;
+    parser.skip_blank()?;
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error(parser.pos, "unexpected text after ';'"));
+    }
+    Ok(tree)
+}
+
+// Reads every tree in the text, e.g. a file of bootstrap replicates
+pub fn parse_newick_trees(text: &str) -> Result<Vec<Tree>, NewickError> {
+    let mut parser = Parser::new(text);
+    let mut trees = Vec::new();
+    parser.skip_blank()?;
+    while parser.pos < parser.chars.len() {
+        trees.push(parser.tree()?);
+        parser.skip_blank()?;
+    }
+    Ok(trees)
+}
+
+// Writes the tree as a single line ending with ';'. Reading the output
+// gives back an equal tree as long as its ids are in pre-order, which holds
+// for every tree built by this crate.
+//
+// Support on an unnamed internal node is written as its label, which is how
+// most viewers expect bootstrap values. Anywhere else it follows the branch
+// length in brackets. Lengths that are very small or very large use
+// scientific notation. The rooting comment is only written when the shape of
+// the root would suggest the wrong one.
+pub fn to_newick(tree: &Tree) -> String {
+    enum Step {
+        Enter(NodeId),
+        Exit(NodeId),
+        Comma,
+    }
+
+    let root = tree.root();
+    let mut text = String::new();
+    if tree.is_rooted() != looks_rooted(tree) {
+        text.push_str(if tree.is_rooted() { "[&R] " } else { "[&U] " });
+    }
+    let mut steps = vec![Step::Enter(root)];
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Enter(id) if tree.node(id).is_leaf() => write_tail(tree, id, &mut text),
+            Step::Enter(id) => {
+                text.push('(');
+                steps.push(Step::Exit(id));
+                for (k, &child) in tree.node(id).children().iter().enumerate().rev() {
+                    steps.push(Step::Enter(child));
+                    if k > 0 {
+                        steps.push(Step::Comma);
+                    }
+                }
+            }
+            Step::Exit(id) => {
+                text.push(')');
+                write_tail(tree, id, &mut text);
+            }
+            Step::Comma => text.push(','),
+        }
+    }
+    text.push(';');
+    text
+}
+
+fn looks_rooted(tree: &Tree) -> bool {
+    tree.node(tree.root()).children().len() < 3
+}
+
+// Label, branch length and support of one node
+fn write_tail(tree: &Tree, id: NodeId, text: &mut String) {
+    let node = tree.node(id);
+    let bootstrap = !node.is_leaf() && id != tree.root();
+    let mut support = node.support;
+    match (&node.name, support) {
+        (Some(name), _) => write_label(name, bootstrap, text),
+        (None, Some(value)) if bootstrap => {
+            text.push_str(&number(value));
+            support = None;
+        }
+        (None, _) => {}
+    }
+    if let Some(length) = node.branch_length {
+        text.push(':');
+        text.push_str(&number(length));
+    }
+    if let Some(value) = support {
+        text.push_str(&format!("[{}]", number(value)));
+    }
+}
+
+// Quotes labels that would otherwise be read differently, including numeric
+// names on internal nodes, which would come back as support values
+fn write_label(name: &str, bootstrap: bool, text: &mut String) {
+    let quote = name.is_empty()
+        || name.chars().any(|c| c.is_whitespace() || is_delimiter(c))
+        || (bootstrap && name.parse::<f64>().is_ok());
+    if quote {
+        text.push('\'');
+        text.push_str(&name.replace('\'', "''"));
+        text.push('\'');
+    } else {
+        text.push_str(name);
+    }
+}
+
+// Shortest text that reads back as the same value
+fn number(value: f64) -> String {
+    if value != 0.0 && !(1e-4..1e15).contains(&value.abs()) {
+        format!("{value:e}")
+    } else {
+        format!("{value}")
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    matches!(c, '(' | ')' | '[' | ']' | '\'' | ':' | ';' | ',')
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    // Last numeric comment seen in the current node's label and length
+    bracketed: Option<f64>,
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+            bracketed: None,
+        }
+    }
+
+    fn error(&self, at: usize, message: &str) -> NewickError {
+        let before = &self.chars[..at.min(self.chars.len())];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+        NewickError {
+            line,
+            column,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    // Skips whitespace and comments, remembering any numeric comment
+    fn skip_blank(&mut self) -> Result<Vec<String>, NewickError> {
+        let mut comments = Vec::new();
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => self.pos += 1,
+                Some('[') => {
+                    let start = self.pos;
+                    let Some(length) = self.chars[start..].iter().position(|&c| c == ']') else {
+                        return Err(self.error(start, "unterminated comment"));
+                    };
+                    let comment: String = self.chars[start + 1..start + length].iter().collect();
+                    if let Ok(value) = comment.trim().parse::<f64>() {
+                        self.bracketed = Some(value);
+                    }
+                    comments.push(comment);
+                    self.pos = start + length + 1;
+                }
+                _ => return Ok(comments),
+            }
+        }
+    }
+
+    fn tree(&mut self) -> Result<Tree, NewickError> {
+        let mut rooting = None;
+        for comment in self.skip_blank()? {
+            match comment.trim().to_ascii_uppercase().as_str() {
+                "&R" => rooting = Some(true),
+                "&U" => rooting = Some(false),
+                _ => {}
+            }
+        }
+        self.bracketed = None;
+
+        let mut tree = Tree::new(true);
+        let mut open: Vec<NodeId> = Vec::new();
+        let mut current = tree.root();
+        loop {
+            // Start of a node: descend through any opening brackets
+            self.skip_blank()?;
+            while self.peek() == Some('(') {
+                self.pos += 1;
+                open.push(current);
+                current = tree.add_child(current, None, None);
+                self.skip_blank()?;
+            }
+            self.tail(&mut tree, current)?;
+
+            // Close finished clades, then move to the next sibling or stop
+            loop {
+                self.skip_blank()?;
+                match self.peek() {
+                    Some(')') => {
+                        let Some(parent) = open.pop() else {
+                            return Err(self.error(self.pos, "unmatched ')'"));
+                        };
+                        self.pos += 1;
+                        current = parent;
+                        self.tail(&mut tree, current)?;
+                    }
+                    Some(',') => {
+                        let Some(&parent) = open.last() else {
+                            return Err(self.error(self.pos, "',' outside of parentheses"));
+                        };
+                        self.pos += 1;
+                        current = tree.add_child(parent, None, None);
+                        break;
+                    }
+                    Some(';') if open.is_empty() => {
+                        self.pos += 1;
+                        let rooted = rooting.unwrap_or_else(|| looks_rooted(&tree));
+                        tree.set_rooted(rooted);
+                        return Ok(tree);
+                    }
+                    Some(';') => return Err(self.error(self.pos, "missing ')' before ';'")),
+                    Some(c) => {
+                        return Err(self.error(self.pos, &format!("unexpected '{c}'")));
+                    }
+                    None => return Err(self.error(self.pos, "missing ';' at end of tree")),
+                }
+            }
+        }
+    }
+
+    // Optional label, branch length and support comment after a node
+    fn tail(&mut self, tree: &mut Tree, id: NodeId) -> Result<(), NewickError> {
+        self.bracketed = None;
+        self.skip_blank()?;
+        let (label, quoted) = self.label()?;
+        self.skip_blank()?;
+        let mut length = None;
+        if self.peek() == Some(':') {
+            self.pos += 1;
+            self.skip_blank()?;
+            let start = self.pos;
+            let token = self.token();
+            let value = token.parse::<f64>().ok().filter(|value| value.is_finite());
+            let Some(value) = value else {
+                let message = if token.is_empty() {
+                    "missing branch length after ':'".to_string()
+                } else {
+                    format!("invalid branch length '{token}'")
+                };
+                return Err(self.error(start, &message));
+            };
+            length = Some(value);
+            self.skip_blank()?;
+        }
+
+        let bootstrap = !tree.node(id).is_leaf() && id != tree.root();
+        let node = tree.node_mut(id);
+        node.branch_length = length;
+        node.support = self.bracketed.take();
+        match label {
+            Some(text) if bootstrap && !quoted && node.support.is_none() => {
+                match text.parse::<f64>() {
+                    Ok(value) => node.support = Some(value),
+                    Err(_) => node.name = Some(text),
+                }
+            }
+            label => node.name = label,
+        }
+        Ok(())
+    }
+
+    // Quoted or bare label, if there is one
+    fn label(&mut self) -> Result<(Option<String>, bool), NewickError> {
+        if self.peek() != Some('\'') {
+            let token = self.token();
+            return Ok(((!token.is_empty()).then_some(token), false));
+        }
+        let start = self.pos;
+        self.pos += 1;
+        let mut label = String::new();
+        loop {
+            match self.peek() {
+                Some('\'') if self.chars.get(self.pos + 1) == Some(&'\'') => {
+                    label.push('\'');
+                    self.pos += 2;
+                }
+                Some('\'') => {
+                    self.pos += 1;
+                    return Ok((Some(label), true));
+                }
+                Some(c) => {
+                    label.push(c);
+                    self.pos += 1;
+                }
+                None => return Err(self.error(start, "unterminated quoted label")),
+            }
+        }
+    }
+
+    // Run of characters up to the next delimiter or whitespace
+    fn token(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !is_delimiter(c))
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
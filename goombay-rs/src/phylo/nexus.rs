@@ -0,0 +1,754 @@
+use crate::phylo::newick::{parse_newick, to_newick};
+use crate::phylo::tree::Tree;
+use std::collections::HashMap;
+use std::fmt;
+
+// Where and why a NEXUS file could not be read. Lines and columns start at
+// 1 and columns count characters, not bytes. Errors inside a tree point into
+// the file, not into the tree string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NexusError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for NexusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for NexusError {}
+
+// Contents of the TAXA, CHARACTERS (or DATA) and TREES blocks of a NEXUS
+// file. `sequences` holds the character matrix row by row, gaps included,
+// in the order of the file. Tree leaves carry taxon names, with any
+// TRANSLATE table already applied.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Nexus {
+    pub taxa: Vec<String>,
+    pub data_type: String,
+    pub gap: char,
+    pub missing: char,
+    pub sequences: Vec<(String, String)>,
+    pub trees: Vec<(String, Tree)>,
+}
+
+impl Default for Nexus {
+    fn default() -> Self {
+        Nexus {
+            taxa: Vec::new(),
+            data_type: "STANDARD".to_string(),
+            gap: '-',
+            missing: '?',
+            sequences: Vec::new(),
+            trees: Vec::new(),
+        }
+    }
+}
+
+impl Nexus {
+    // Matrix row of a taxon, gaps included
+    pub fn sequence(&self, name: &str) -> Option<&str> {
+        self.sequences
+            .iter()
+            .find(|(taxon, _)| taxon == name)
+            .map(|(_, sequence)| sequence.as_str())
+    }
+
+    // Matrix rows with the gap character removed, ready to be aligned again
+    pub fn unaligned(&self) -> Vec<(String, String)> {
+        self.sequences
+            .iter()
+            .map(|(name, sequence)| {
+                let residues = sequence.chars().filter(|&c| c != self.gap).collect();
+                (name.clone(), residues)
+            })
+            .collect()
+    }
+
+    pub fn tree(&self, name: &str) -> Option<&Tree> {
+        self.trees
+            .iter()
+            .find(|(title, _)| title == name)
+            .map(|(_, tree)| tree)
+    }
+}
+
+// Reads a NEXUS file. Blocks other than TAXA, CHARACTERS, DATA and TREES are
+// skipped, as are commands these blocks may hold that are not needed here,
+// such as CHARSTATELABELS. Matrices may be sequential or interleaved, and
+// MATCHCHAR is expanded against the first row. Polymorphic or uncertain
+// states such as {AG}, {A G} or (A,G) are read as the missing character. Without a
+// TRANSLATE table, numeric leaf labels refer to taxa by their position.
+pub fn parse_nexus(text: &str) -> Result<Nexus, NexusError> {
+    let mut lexer = Lexer::new(text);
+    match lexer.next()? {
+        Some(token) if !token.quoted && token.text.eq_ignore_ascii_case("#NEXUS") => {}
+        _ => return Err(lexer.error(0, "missing #NEXUS header")),
+    }
+
+    let mut nexus = Nexus::default();
+    while let Some(token) = lexer.next()? {
+        if !token.is("BEGIN") {
+            return Err(lexer.error(token.at, &format!("expected BEGIN, found '{}'", token.text)));
+        }
+        let Some(name) = lexer.next()? else {
+            return Err(lexer.error(token.at, "missing block name after BEGIN"));
+        };
+        lexer.expect_semicolon(&name)?;
+        let mut block = Block {
+            lexer: &mut lexer,
+            name: name.text.to_ascii_uppercase(),
+            begin: token.at,
+        };
+        match block.name.as_str() {
+            "TAXA" => block.taxa(&mut nexus)?,
+            "CHARACTERS" | "DATA" => block.characters(&mut nexus)?,
+            "TREES" => block.trees(&mut nexus)?,
+            _ => block.skip()?,
+        }
+    }
+    Ok(nexus)
+}
+
+// Writes the TAXA, CHARACTERS and TREES blocks that have content. Trees are
+// written through a TRANSLATE table when there are taxa, and always carry
+// a rooting comment.
+pub fn to_nexus(nexus: &Nexus) -> String {
+    let mut text = String::from("#NEXUS\n");
+
+    if !nexus.taxa.is_empty() {
+        let labels: Vec<String> = nexus.taxa.iter().map(|name| quote(name)).collect();
+        text.push_str("\nBEGIN TAXA;\n");
+        text.push_str(&format!("    DIMENSIONS NTAX={};\n", labels.len()));
+        text.push_str(&format!("    TAXLABELS {};\n", labels.join(" ")));
+        text.push_str("END;\n");
+    }
+
+    if !nexus.sequences.is_empty() {
+        let width = nexus.sequences[0].1.chars().count();
+        assert!(
+            nexus
+                .sequences
+                .iter()
+                .all(|(_, sequence)| sequence.chars().count() == width),
+            "every row of a NEXUS matrix must have the same length"
+        );
+        let labels: Vec<String> = nexus
+            .sequences
+            .iter()
+            .map(|(name, _)| quote(name))
+            .collect();
+        let padding = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap();
+        text.push_str("\nBEGIN CHARACTERS;\n");
+        text.push_str(&format!("    DIMENSIONS NCHAR={width};\n"));
+        text.push_str(&format!(
+            "    FORMAT DATATYPE={} GAP={} MISSING={};\n",
+            nexus.data_type, nexus.gap, nexus.missing
+        ));
+        text.push_str("    MATRIX\n");
+        for (label, (_, sequence)) in labels.iter().zip(&nexus.sequences) {
+            text.push_str(&format!("        {label:<padding$} {sequence}\n"));
+        }
+        text.push_str("    ;\nEND;\n");
+    }
+
+    if !nexus.trees.is_empty() {
+        text.push_str("\nBEGIN TREES;\n");
+        if !nexus.taxa.is_empty() {
+            let entries: Vec<String> = nexus
+                .taxa
+                .iter()
+                .enumerate()
+                .map(|(k, name)| format!("        {} {}", k + 1, quote(name)))
+                .collect();
+            text.push_str("    TRANSLATE\n");
+            text.push_str(&entries.join(",\n"));
+            text.push_str("\n    ;\n");
+        }
+        let numbers: HashMap<&str, String> = nexus
+            .taxa
+            .iter()
+            .enumerate()
+            .map(|(k, name)| (name.as_str(), (k + 1).to_string()))
+            .collect();
+        for (name, tree) in &nexus.trees {
+            let mut numbered = tree.clone();
+            for leaf in tree.leaves() {
+                let taxon = tree.node(leaf).name.as_deref();
+                if let Some(number) = taxon.and_then(|taxon| numbers.get(taxon)) {
+                    numbered.node_mut(leaf).name = Some(number.clone());
+                }
+            }
+            let newick = to_newick(&numbered);
+            let rooting = match (newick.starts_with('['), tree.is_rooted()) {
+                (true, _) => "",
+                (false, true) => "[&R] ",
+                (false, false) => "[&U] ",
+            };
+            text.push_str(&format!("    TREE {} = {rooting}{newick}\n", quote(name)));
+        }
+        text.push_str("END;\n");
+    }
+    text
+}
+
+// Quotes names holding whitespace or NEXUS punctuation
+fn quote(name: &str) -> String {
+    let plain = !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || "()[]{}/\\,;:=*'\"".contains(c));
+    if plain {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\'', "''"))
+    }
+}
+
+struct Token {
+    text: String,
+    quoted: bool,
+    // Character offset and line of the first character
+    at: usize,
+    line: usize,
+}
+
+impl Token {
+    fn is(&self, word: &str) -> bool {
+        !self.quoted && self.text.eq_ignore_ascii_case(word)
+    }
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Lexer {
+    fn new(text: &str) -> Self {
+        Lexer {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn error(&self, at: usize, message: &str) -> NexusError {
+        let before = &self.chars[..at.min(self.chars.len())];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+        NexusError {
+            line,
+            column,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn advance(&mut self) {
+        if self.peek() == Some('\n') {
+            self.line += 1;
+        }
+        self.pos += 1;
+    }
+
+    // Skips whitespace and comments, which may be nested
+    fn skip_blank(&mut self) -> Result<(), NexusError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => self.advance(),
+                Some('[') => {
+                    let start = self.pos;
+                    let mut depth = 0;
+                    loop {
+                        match self.peek() {
+                            Some('[') => depth += 1,
+                            Some(']') => depth -= 1,
+                            Some(_) => {}
+                            None => return Err(self.error(start, "unterminated comment")),
+                        }
+                        self.advance();
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<Token>, NexusError> {
+        self.skip_blank()?;
+        let (at, line) = (self.pos, self.line);
+        let Some(first) = self.peek() else {
+            return Ok(None);
+        };
+        let mut text = String::new();
+        let quoted = first == '\'' || first == '"';
+        if quoted {
+            self.advance();
+            loop {
+                match self.peek() {
+                    Some(c) if c == first && self.chars.get(self.pos + 1) == Some(&first) => {
+                        text.push(c);
+                        self.pos += 2;
+                    }
+                    Some(c) if c == first => {
+                        self.advance();
+                        break;
+                    }
+                    Some(c) => {
+                        text.push(c);
+                        self.advance();
+                    }
+                    None => return Err(self.error(at, "unterminated quoted token")),
+                }
+            }
+        } else if matches!(first, ';' | '=' | ',') {
+            text.push(first);
+            self.advance();
+        } else {
+            while let Some(c) = self.peek() {
+                if c.is_whitespace() || matches!(c, ';' | '=' | ',' | '[' | '\'' | '"') {
+                    break;
+                }
+                text.push(c);
+                self.advance();
+            }
+        }
+        Ok(Some(Token {
+            text,
+            quoted,
+            at,
+            line,
+        }))
+    }
+
+    fn expect_semicolon(&mut self, after: &Token) -> Result<(), NexusError> {
+        match self.next()? {
+            Some(token) if token.is(";") => Ok(()),
+            Some(token) => Err(self.error(
+                token.at,
+                &format!(
+                    "expected ';' after '{}', found '{}'",
+                    after.text, token.text
+                ),
+            )),
+            None => Err(self.error(self.pos, &format!("expected ';' after '{}'", after.text))),
+        }
+    }
+
+    // Like `next`, but a state set such as {A G} or (A,G) inside a word is
+    // kept whole, blanks and commas included. Used for matrix rows.
+    fn matrix_token(&mut self) -> Result<Option<Token>, NexusError> {
+        self.skip_blank()?;
+        let (at, line) = (self.pos, self.line);
+        match self.peek() {
+            None => return Ok(None),
+            Some(';' | '=' | ',' | '\'' | '"') => return self.next(),
+            _ => {}
+        }
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || matches!(c, ';' | '=' | ',' | '[' | '\'' | '"') {
+                break;
+            }
+            text.push(c);
+            self.advance();
+            let close = match c {
+                '{' => '}',
+                '(' => ')',
+                _ => continue,
+            };
+            let start = self.pos - 1;
+            loop {
+                match self.peek() {
+                    Some(inner) if inner == close => break,
+                    Some(';') | None => return Err(self.error(start, "unterminated state set")),
+                    Some(inner) => {
+                        text.push(inner);
+                        self.advance();
+                    }
+                }
+            }
+        }
+        Ok(Some(Token {
+            text,
+            quoted: false,
+            at,
+            line,
+        }))
+    }
+
+    // Everything up to and including the next ';' outside quotes and
+    // comments, with its starting offset. Used for tree strings, so leading
+    // comments such as [&U] are kept for the Newick reader.
+    fn raw_command(&mut self) -> Result<(usize, String), NexusError> {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.advance();
+        }
+        let start = self.pos;
+        let mut quoted = false;
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            self.advance();
+            match c {
+                '\'' => quoted = !quoted,
+                '[' if !quoted => depth += 1,
+                ']' if !quoted && depth > 0 => depth -= 1,
+                ';' if !quoted && depth == 0 => {
+                    return Ok((start, self.chars[start..self.pos].iter().collect()));
+                }
+                _ => {}
+            }
+        }
+        Err(self.error(start, "missing ';' at end of command"))
+    }
+}
+
+// Commands of one block, read until END or ENDBLOCK
+struct Block<'a> {
+    lexer: &'a mut Lexer,
+    name: String,
+    begin: usize,
+}
+
+impl Block<'_> {
+    // Name of the next command, or None once the block has ended
+    fn command(&mut self) -> Result<Option<Token>, NexusError> {
+        let Some(token) = self.lexer.next()? else {
+            let message = format!("{} block has no END", self.name);
+            return Err(self.lexer.error(self.begin, &message));
+        };
+        if token.is("END") || token.is("ENDBLOCK") {
+            self.lexer.expect_semicolon(&token)?;
+            return Ok(None);
+        }
+        Ok(Some(token))
+    }
+
+    // Tokens of the current command up to its ';'
+    fn arguments(&mut self, command: &Token) -> Result<Vec<Token>, NexusError> {
+        self.read_arguments(command, Lexer::next)
+    }
+
+    fn read_arguments(
+        &mut self,
+        command: &Token,
+        read: fn(&mut Lexer) -> Result<Option<Token>, NexusError>,
+    ) -> Result<Vec<Token>, NexusError> {
+        let mut tokens = Vec::new();
+        loop {
+            match read(self.lexer)? {
+                Some(token) if token.is(";") => return Ok(tokens),
+                Some(token) => tokens.push(token),
+                None => {
+                    let message = format!("missing ';' at end of {}", command.text);
+                    return Err(self.lexer.error(command.at, &message));
+                }
+            }
+        }
+    }
+
+    fn skip(&mut self) -> Result<(), NexusError> {
+        while let Some(command) = self.command()? {
+            self.arguments(&command)?;
+        }
+        Ok(())
+    }
+
+    fn taxa(&mut self, nexus: &mut Nexus) -> Result<(), NexusError> {
+        let mut count = None;
+        let mut labels = None;
+        while let Some(command) = self.command()? {
+            let arguments = self.arguments(&command)?;
+            if command.is("DIMENSIONS") {
+                count = self.number(&arguments, "NTAX")?;
+            } else if command.is("TAXLABELS") {
+                labels = Some((command.at, arguments));
+            }
+        }
+        let Some((at, labels)) = labels else {
+            return Err(self.lexer.error(self.begin, "TAXA block has no TAXLABELS"));
+        };
+        if let Some(count) = count.filter(|&count| count != labels.len()) {
+            let message = format!("expected {count} taxa, found {}", labels.len());
+            return Err(self.lexer.error(at, &message));
+        }
+        nexus.taxa = labels.into_iter().map(|token| token.text).collect();
+        Ok(())
+    }
+
+    fn characters(&mut self, nexus: &mut Nexus) -> Result<(), NexusError> {
+        let mut taxa = None;
+        let mut width = None;
+        let mut interleave = false;
+        let mut match_char = None;
+        while let Some(command) = self.command()? {
+            let arguments = if command.is("MATRIX") {
+                self.read_arguments(&command, Lexer::matrix_token)?
+            } else {
+                self.arguments(&command)?
+            };
+            if command.is("DIMENSIONS") {
+                taxa = self.number(&arguments, "NTAX")?;
+                width = self.number(&arguments, "NCHAR")?;
+            } else if command.is("FORMAT") {
+                for (key, value) in options(&arguments) {
+                    let symbol = value.and_then(|value| value.text.chars().next());
+                    match key.to_ascii_uppercase().as_str() {
+                        "DATATYPE" => {
+                            if let Some(value) = value {
+                                nexus.data_type = value.text.to_ascii_uppercase();
+                            }
+                        }
+                        "GAP" => nexus.gap = symbol.unwrap_or(nexus.gap),
+                        "MISSING" => nexus.missing = symbol.unwrap_or(nexus.missing),
+                        "MATCHCHAR" => match_char = symbol,
+                        "INTERLEAVE" => {
+                            interleave = value.is_none_or(|value| !value.is("NO"));
+                        }
+                        "TRANSPOSE" => {
+                            let message = "transposed matrices are not supported";
+                            return Err(self.lexer.error(command.at, message));
+                        }
+                        _ => {}
+                    }
+                }
+            } else if command.is("MATRIX") {
+                let Some(width) = width else {
+                    let message = "MATRIX needs NCHAR in DIMENSIONS first";
+                    return Err(self.lexer.error(command.at, message));
+                };
+                let rows = self.matrix(&arguments, width, interleave, nexus.missing)?;
+                if let Some(count) = taxa.filter(|&count| count != rows.len()) {
+                    let message = format!("expected {count} rows, found {}", rows.len());
+                    return Err(self.lexer.error(command.at, &message));
+                }
+                for (at, name, _) in &rows {
+                    if !nexus.taxa.is_empty() && !nexus.taxa.contains(name) {
+                        let message = format!("unknown taxon '{name}'");
+                        return Err(self.lexer.error(*at, &message));
+                    }
+                }
+                let mut sequences: Vec<(String, String)> = rows
+                    .into_iter()
+                    .map(|(_, name, states)| (name, states.into_iter().collect()))
+                    .collect();
+                if let Some(symbol) = match_char
+                    && !sequences.is_empty()
+                {
+                    let first: Vec<char> = sequences[0].1.chars().collect();
+                    for (_, sequence) in sequences.iter_mut().skip(1) {
+                        *sequence = sequence
+                            .chars()
+                            .zip(&first)
+                            .map(|(c, &above)| if c == symbol { above } else { c })
+                            .collect();
+                    }
+                }
+                if nexus.taxa.is_empty() {
+                    nexus.taxa = sequences.iter().map(|(name, _)| name.clone()).collect();
+                }
+                nexus.sequences = sequences;
+            }
+        }
+        Ok(())
+    }
+
+    // Rows of (offset of the name, name, states). Sequential rows run until
+    // they hold `width` states, interleaved rows until the end of the line.
+    fn matrix(
+        &self,
+        tokens: &[Token],
+        width: usize,
+        interleave: bool,
+        missing: char,
+    ) -> Result<Vec<(usize, String, Vec<char>)>, NexusError> {
+        let mut rows: Vec<(usize, String, Vec<char>)> = Vec::new();
+        let mut current = 0;
+        let mut line = None;
+        for token in tokens {
+            let starts_row = if interleave {
+                line != Some(token.line)
+            } else {
+                rows.last().is_none_or(|row| row.2.len() >= width)
+            };
+            line = Some(token.line);
+            if !starts_row {
+                states(&token.text, missing, &mut rows[current].2);
+                continue;
+            }
+            current = match rows.iter().position(|row| row.1 == token.text) {
+                Some(row) if interleave => row,
+                Some(_) => {
+                    let message = format!("taxon '{}' appears twice", token.text);
+                    return Err(self.lexer.error(token.at, &message));
+                }
+                None => {
+                    rows.push((token.at, token.text.clone(), Vec::new()));
+                    rows.len() - 1
+                }
+            };
+        }
+        for (at, name, states) in &rows {
+            if states.len() != width {
+                let message = format!(
+                    "row '{name}' has {} characters, expected {width}",
+                    states.len()
+                );
+                return Err(self.lexer.error(*at, &message));
+            }
+        }
+        Ok(rows)
+    }
+
+    fn trees(&mut self, nexus: &mut Nexus) -> Result<(), NexusError> {
+        let mut translate: HashMap<String, String> = HashMap::new();
+        while let Some(command) = self.command()? {
+            if command.is("TRANSLATE") {
+                let arguments = self.arguments(&command)?;
+                for entry in arguments.split(|token| token.is(",")) {
+                    let [key, name] = entry else {
+                        let at = entry.first().map_or(command.at, |token| token.at);
+                        let message = "TRANSLATE entries must be a label and a taxon name";
+                        return Err(self.lexer.error(at, message));
+                    };
+                    translate.insert(key.text.clone(), name.text.clone());
+                }
+            } else if command.is("TREE") || command.is("UTREE") {
+                let name = self.tree_name(&command)?;
+                let (start, newick) = self.lexer.raw_command()?;
+                let mut tree = parse_newick(&newick).map_err(|error| {
+                    let base = self.lexer.error(start, "");
+                    NexusError {
+                        line: base.line + error.line - 1,
+                        column: if error.line == 1 {
+                            base.column + error.column - 1
+                        } else {
+                            error.column
+                        },
+                        message: error.message,
+                    }
+                })?;
+                if command.is("UTREE") {
+                    tree.set_rooted(false);
+                }
+                for leaf in tree.leaves() {
+                    let Some(label) = tree.node(leaf).name.as_deref() else {
+                        continue;
+                    };
+                    let taxon = if translate.is_empty() {
+                        label
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|_| !nexus.taxa.iter().any(|taxon| taxon == label))
+                            .and_then(|k| nexus.taxa.get(k.wrapping_sub(1)))
+                            .cloned()
+                    } else {
+                        translate.get(label).cloned()
+                    };
+                    if let Some(taxon) = taxon {
+                        tree.node_mut(leaf).name = Some(taxon);
+                    }
+                }
+                nexus.trees.push((name, tree));
+            } else {
+                self.arguments(&command)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Title of a TREE command, skipping the '*' that marks the default tree,
+    // and the '=' after it
+    fn tree_name(&mut self, command: &Token) -> Result<String, NexusError> {
+        let mut name = self.lexer.next()?;
+        if name.as_ref().is_some_and(|token| token.is("*")) {
+            name = self.lexer.next()?;
+        }
+        let Some(name) = name.filter(|token| !token.is("=") && !token.is(";")) else {
+            return Err(self.lexer.error(command.at, "TREE needs a name"));
+        };
+        match self.lexer.next()? {
+            Some(token) if token.is("=") => Ok(name.text),
+            _ => Err(self
+                .lexer
+                .error(name.at, &format!("expected '=' after tree '{}'", name.text))),
+        }
+    }
+
+    // Value of a numeric option such as NTAX=5
+    fn number(&self, arguments: &[Token], key: &str) -> Result<Option<usize>, NexusError> {
+        for (option, value) in options(arguments) {
+            if !option.eq_ignore_ascii_case(key) {
+                continue;
+            }
+            return match value.map(|value| (value, value.text.parse::<usize>())) {
+                Some((_, Ok(number))) => Ok(Some(number)),
+                Some((value, Err(_))) => {
+                    let message = format!("{key} must be a whole number, found '{}'", value.text);
+                    Err(self.lexer.error(value.at, &message))
+                }
+                None => Err(self
+                    .lexer
+                    .error(self.begin, &format!("{key} needs a value"))),
+            };
+        }
+        Ok(None)
+    }
+}
+
+// KEY=VALUE pairs and bare KEY flags of a command
+fn options(arguments: &[Token]) -> Vec<(&str, Option<&Token>)> {
+    let mut pairs = Vec::new();
+    let mut k = 0;
+    while k < arguments.len() {
+        if arguments.get(k + 1).is_some_and(|token| token.is("=")) {
+            pairs.push((arguments[k].text.as_str(), arguments.get(k + 2)));
+            k += 3;
+        } else {
+            pairs.push((arguments[k].text.as_str(), None));
+            k += 1;
+        }
+    }
+    pairs
+}
+
+// Appends the states of one matrix token, reading a set such as {AG} or
+// (A,G) as a single missing state
+fn states(token: &str, missing: char, row: &mut Vec<char>) {
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        let close = match c {
+            '{' => '}',
+            '(' => ')',
+            _ => {
+                row.push(c);
+                continue;
+            }
+        };
+        for inner in chars.by_ref() {
+            if inner == close {
+                break;
+            }
+        }
+        row.push(missing);
+    }
+}
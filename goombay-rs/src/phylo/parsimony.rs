@@ -0,0 +1,303 @@
+use crate::phylo::distance::is_gap;
+use crate::phylo::tree::{NodeId, Tree};
+use spindalis::utils::Arr2D;
+use std::collections::HashMap;
+
+// Parsimony score of an alignment on a tree. `sites` holds the score of each
+// column and `total` their sum. `states` is indexed by node id: leaves keep
+// their rows, and every internal node holds one most parsimonious
+// reconstruction, one character per column.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsimonyScore<T> {
+    pub total: T,
+    pub sites: Vec<T>,
+    pub states: Vec<String>,
+}
+
+// Cost of changing from one state to another along a branch, from parent
+// to child. Costs need not be symmetric.
+#[derive(Clone, Debug)]
+pub struct StepMatrix {
+    states: Vec<char>,
+    costs: Arr2D<f64>,
+}
+
+impl StepMatrix {
+    // `costs[i][j]` is the cost of a change from the i-th to the j-th state
+    pub fn new(states: &str, costs: Arr2D<f64>) -> Self {
+        let states: Vec<char> = states.chars().map(|c| c.to_ascii_uppercase()).collect();
+        let n = states.len();
+        assert!(
+            costs.height == n && costs.width == n,
+            "step matrix must be {n} x {n} to match the states"
+        );
+        StepMatrix { states, costs }
+    }
+
+    // Every change costs one step, which gives the Fitch score
+    pub fn unit(states: &str) -> Self {
+        let n = states.chars().count();
+        let mut costs = Arr2D::full(1.0, n, n);
+        for i in 0..n {
+            costs[i][i] = 0.0;
+        }
+        StepMatrix::new(states, costs)
+    }
+
+    pub fn states(&self) -> &[char] {
+        &self.states
+    }
+
+    pub fn cost(&self, from: char, to: char) -> f64 {
+        self.costs[self.index(from)][self.index(to)]
+    }
+
+    fn index(&self, state: char) -> usize {
+        let state = state.to_ascii_uppercase();
+        self.states
+            .iter()
+            .position(|&s| s == state)
+            .unwrap_or_else(|| panic!("'{state}' is not a state of the step matrix"))
+    }
+}
+
+// Unweighted parsimony by Fitch's algorithm, with Hartigan's extension for
+// nodes with more than two children. States are compared ignoring case, and
+// '-', '.' and '?' are missing data that fit any state at no cost. `names` and
+// `sequences` give the aligned row of each leaf.
+pub fn fitch<N: AsRef<str>, Q: AsRef<str>>(
+    tree: &Tree,
+    names: &[N],
+    sequences: &[Q],
+) -> ParsimonyScore<usize> {
+    let rows = by_node(tree, leaf_rows(tree, names, sequences));
+    let width = tree.leaves().first().map_or(0, |&leaf| rows[leaf].len());
+    let order = tree.postorder();
+    let mut states = initial_states(tree, &rows, width);
+    let mut sites = Vec::with_capacity(width);
+
+    let leaves = tree.leaves();
+    for (site, column) in columns(&rows, width).iter().enumerate() {
+        // Alphabet of this column in sorted order, as bits of a set
+        let mut alphabet: Vec<char> = leaves
+            .iter()
+            .map(|&leaf| column[leaf])
+            .filter(|&c| !is_missing(c))
+            .collect();
+        alphabet.sort_unstable();
+        alphabet.dedup();
+        if alphabet.is_empty() {
+            // Nothing but missing data
+            sites.push(0);
+            for &id in &order {
+                if !tree.node(id).is_leaf() {
+                    states[id].push('?');
+                }
+            }
+            continue;
+        }
+        assert!(alphabet.len() <= 64, "too many states in column {site}");
+        let everything = if alphabet.len() == 64 {
+            u64::MAX
+        } else {
+            (1u64 << alphabet.len()) - 1
+        };
+
+        // Bottom-up: the states shared by the most children, and one step for
+        // every child that cannot take them
+        let mut sets = vec![0u64; tree.len()];
+        let mut steps = 0;
+        for &id in &order {
+            let node = tree.node(id);
+            if node.is_leaf() {
+                let c = column[id];
+                sets[id] = match alphabet.binary_search(&c) {
+                    Ok(k) => 1 << k,
+                    Err(_) => everything,
+                };
+                continue;
+            }
+            let children = node.children();
+            let counts: Vec<usize> = (0..alphabet.len())
+                .map(|k| {
+                    children
+                        .iter()
+                        .filter(|&&child| sets[child] & (1 << k) != 0)
+                        .count()
+                })
+                .collect();
+            let most = counts.iter().copied().max().unwrap();
+            sets[id] = (0..alphabet.len())
+                .filter(|&k| counts[k] == most)
+                .fold(0, |set, k| set | (1 << k));
+            steps += children.len() - most;
+        }
+        sites.push(steps);
+
+        // Top-down: keep the parent's state where the node allows it
+        let mut chosen = vec![0usize; tree.len()];
+        for &id in order.iter().rev() {
+            let set = sets[id];
+            let inherited = tree.node(id).parent().map(|parent| chosen[parent]);
+            chosen[id] = match inherited {
+                Some(k) if set & (1 << k) != 0 => k,
+                _ => set.trailing_zeros() as usize,
+            };
+            if !tree.node(id).is_leaf() {
+                states[id].push(alphabet[chosen[id]]);
+            }
+        }
+    }
+
+    ParsimonyScore {
+        total: sites.iter().sum(),
+        sites,
+        states,
+    }
+}
+
+// Weighted parsimony by Sankoff's algorithm with the costs of `steps`.
+// Every character of the alignment other than the missing data '-', '.' and
+// '?' must be one of its states.
+pub fn sankoff<N: AsRef<str>, Q: AsRef<str>>(
+    tree: &Tree,
+    names: &[N],
+    sequences: &[Q],
+    steps: &StepMatrix,
+) -> ParsimonyScore<f64> {
+    let rows = by_node(tree, leaf_rows(tree, names, sequences));
+    let width = tree.leaves().first().map_or(0, |&leaf| rows[leaf].len());
+    let order = tree.postorder();
+    let n = steps.states.len();
+    let mut states = initial_states(tree, &rows, width);
+    let mut sites = Vec::with_capacity(width);
+
+    for column in &columns(&rows, width) {
+        // Bottom-up: least cost of each node's subtree given its state
+        let mut costs = vec![vec![0.0; n]; tree.len()];
+        for &id in &order {
+            let node = tree.node(id);
+            if node.is_leaf() {
+                let c = column[id];
+                if !is_missing(c) {
+                    let k = steps.index(c);
+                    costs[id] = (0..n)
+                        .map(|s| if s == k { 0.0 } else { f64::INFINITY })
+                        .collect();
+                }
+                continue;
+            }
+            for s in 0..n {
+                costs[id][s] = node
+                    .children()
+                    .iter()
+                    .map(|&child| cheapest(steps, &costs[child], s).1)
+                    .sum();
+            }
+        }
+        let root = tree.root();
+        let (best, score) =
+            (0..n)
+                .map(|s| (s, costs[root][s]))
+                .fold((0, f64::INFINITY), |low, (s, cost)| {
+                    if cost < low.1 { (s, cost) } else { low }
+                });
+        sites.push(score);
+
+        // Top-down: the cheapest child state given the parent's
+        let mut chosen = vec![0usize; tree.len()];
+        chosen[root] = best;
+        for &id in order.iter().rev() {
+            if let Some(parent) = tree.node(id).parent() {
+                chosen[id] = cheapest(steps, &costs[id], chosen[parent]).0;
+            }
+            if !tree.node(id).is_leaf() {
+                states[id].push(steps.states[chosen[id]]);
+            }
+        }
+    }
+
+    ParsimonyScore {
+        total: sites.iter().sum(),
+        sites,
+        states,
+    }
+}
+
+// Child state that minimises the step from `parent` plus the child's
+// subtree cost, with ties going to the first state
+fn cheapest(steps: &StepMatrix, child: &[f64], parent: usize) -> (usize, f64) {
+    (0..child.len())
+        .map(|t| (t, steps.costs[parent][t] + child[t]))
+        .fold((0, f64::INFINITY), |low, (t, cost)| {
+            if cost < low.1 { (t, cost) } else { low }
+        })
+}
+
+fn is_missing(c: char) -> bool {
+    is_gap(c) || c == '?'
+}
+
+// Upper-cased row of each leaf, checking every leaf has one and the rows
+// line up
+pub(crate) fn leaf_rows<N: AsRef<str>, Q: AsRef<str>>(
+    tree: &Tree,
+    names: &[N],
+    sequences: &[Q],
+) -> HashMap<NodeId, Vec<char>> {
+    assert_eq!(names.len(), sequences.len(), "every sequence needs a name");
+    let by_name: HashMap<&str, &str> = names
+        .iter()
+        .map(AsRef::as_ref)
+        .zip(sequences.iter().map(AsRef::as_ref))
+        .collect();
+    let rows: HashMap<NodeId, Vec<char>> = tree
+        .leaves()
+        .into_iter()
+        .map(|leaf| {
+            let name = tree.node(leaf).name.as_deref().unwrap_or("");
+            let Some(row) = by_name.get(name) else {
+                panic!("leaf '{name}' has no sequence");
+            };
+            (leaf, row.chars().map(|c| c.to_ascii_uppercase()).collect())
+        })
+        .collect();
+    let width = rows.values().next().map_or(0, Vec::len);
+    assert!(
+        rows.values().all(|row| row.len() == width),
+        "aligned sequences must have the same length"
+    );
+    rows
+}
+
+// Rows indexed by node id, empty for internal nodes
+fn by_node(tree: &Tree, mut rows: HashMap<NodeId, Vec<char>>) -> Vec<Vec<char>> {
+    (0..tree.len())
+        .map(|id| rows.remove(&id).unwrap_or_default())
+        .collect()
+}
+
+// Character of each node in each column, indexed by node id. Internal
+// nodes hold '?'.
+fn columns(rows: &[Vec<char>], width: usize) -> Vec<Vec<char>> {
+    (0..width)
+        .map(|site| {
+            rows.iter()
+                .map(|row| row.get(site).copied().unwrap_or('?'))
+                .collect()
+        })
+        .collect()
+}
+
+// Leaves start with their rows, internal nodes empty
+fn initial_states(tree: &Tree, rows: &[Vec<char>], width: usize) -> Vec<String> {
+    (0..tree.len())
+        .map(|id| {
+            if tree.node(id).is_leaf() {
+                rows[id].iter().collect()
+            } else {
+                String::with_capacity(width)
+            }
+        })
+        .collect()
+}
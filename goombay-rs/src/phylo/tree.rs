@@ -0,0 +1,411 @@
+use std::collections::VecDeque;
+
+pub type NodeId = usize;
+
+// One node of a tree. `name` is the leaf name or the internal node label.
+// `branch_length` and `support` describe the branch to the parent, so both
+// are None on the root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub name: Option<String>,
+    pub branch_length: Option<f64>,
+    pub support: Option<f64>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl Node {
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+// Phylogenetic tree stored as an arena of nodes addressed by `NodeId`.
+// Unrooted trees are stored the way Newick writes them, hanging from an
+// arbitrary internal node (usually with three children), and only differ
+// from rooted trees in the `rooted` flag. Operations that reshape the tree
+// return a new tree with fresh ids, numbered in pre-order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tree {
+    nodes: Vec<Node>,
+    root: NodeId,
+    rooted: bool,
+}
+
+// Node of a tree built bottom-up, e.g. by clustering. Leaves have a name and
+// no children; internal clades list (child clade, branch length) pairs.
+pub(crate) struct Clade {
+    pub name: Option<String>,
+    pub children: Vec<(usize, f64)>,
+}
+
+impl Default for Tree {
+    fn default() -> Self {
+        Tree::new(true)
+    }
+}
+
+impl Tree {
+    // Tree holding a single unnamed root
+    pub fn new(rooted: bool) -> Self {
+        Tree {
+            nodes: vec![Node {
+                name: None,
+                branch_length: None,
+                support: None,
+                parent: None,
+                children: Vec::new(),
+            }],
+            root: 0,
+            rooted,
+        }
+    }
+
+    pub fn add_child(
+        &mut self,
+        parent: NodeId,
+        name: Option<&str>,
+        branch_length: Option<f64>,
+    ) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.map(str::to_string),
+            branch_length,
+            support: None,
+            parent: Some(parent),
+            children: Vec::new(),
+        });
+        self.nodes[parent].children.push(id);
+        id
+    }
+
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    pub fn is_rooted(&self) -> bool {
+        self.rooted
+    }
+
+    pub fn set_rooted(&mut self, rooted: bool) {
+        self.rooted = rooted;
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id]
+    }
+
+    // Number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // Leaves in pre-order, i.e. left to right
+    pub fn leaves(&self) -> Vec<NodeId> {
+        self.preorder()
+            .into_iter()
+            .filter(|&id| self.nodes[id].is_leaf())
+            .collect()
+    }
+
+    pub fn leaf_names(&self) -> Vec<&str> {
+        self.leaves()
+            .into_iter()
+            .filter_map(|id| self.nodes[id].name.as_deref())
+            .collect()
+    }
+
+    // First node in pre-order with this name
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.preorder()
+            .into_iter()
+            .find(|&id| self.nodes[id].name.as_deref() == Some(name))
+    }
+
+    pub fn preorder(&self) -> Vec<NodeId> {
+        self.preorder_from(self.root)
+    }
+
+    // Node, then each child's subtree in order
+    pub fn preorder_from(&self, node: NodeId) -> Vec<NodeId> {
+        let mut order = Vec::new();
+        let mut stack = vec![node];
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            stack.extend(self.nodes[id].children.iter().rev());
+        }
+        order
+    }
+
+    pub fn postorder(&self) -> Vec<NodeId> {
+        self.postorder_from(self.root)
+    }
+
+    // Each child's subtree in order, then the node
+    pub fn postorder_from(&self, node: NodeId) -> Vec<NodeId> {
+        let mut order = Vec::new();
+        let mut stack = vec![node];
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            stack.extend(self.nodes[id].children.iter());
+        }
+        order.reverse();
+        order
+    }
+
+    // Breadth first from the root, children left to right
+    pub fn levelorder(&self) -> Vec<NodeId> {
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([self.root]);
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            queue.extend(self.nodes[id].children.iter());
+        }
+        order
+    }
+
+    // Nodes from `node` up to the root, both included
+    pub fn ancestors(&self, node: NodeId) -> Vec<NodeId> {
+        let mut path = vec![node];
+        let mut current = node;
+        while let Some(parent) = self.nodes[current].parent {
+            path.push(parent);
+            current = parent;
+        }
+        path
+    }
+
+    // Lowest common ancestor
+    pub fn lca(&self, a: NodeId, b: NodeId) -> NodeId {
+        let above_a = self.ancestors(a);
+        self.ancestors(b)
+            .into_iter()
+            .find(|id| above_a.contains(id))
+            .unwrap()
+    }
+
+    // Lowest common ancestor of several nodes, or None for an empty slice
+    pub fn lca_of(&self, nodes: &[NodeId]) -> Option<NodeId> {
+        let (&first, rest) = nodes.split_first()?;
+        Some(
+            rest.iter()
+                .fold(first, |ancestor, &id| self.lca(ancestor, id)),
+        )
+    }
+
+    // Sum of branch lengths from the root; missing lengths count as 0
+    pub fn depth(&self, node: NodeId) -> f64 {
+        self.ancestors(node)
+            .iter()
+            .map(|&id| self.nodes[id].branch_length.unwrap_or(0.0))
+            .sum()
+    }
+
+    // Patristic distance, the sum of branch lengths on the path between nodes
+    pub fn distance(&self, a: NodeId, b: NodeId) -> f64 {
+        let ancestor = self.lca(a, b);
+        self.depth(a) + self.depth(b) - 2.0 * self.depth(ancestor)
+    }
+
+    // Copy of the clade below `node`, as a rooted tree
+    pub fn subtree(&self, node: NodeId) -> Tree {
+        let mut keep = vec![false; self.nodes.len()];
+        for id in self.preorder_from(node) {
+            keep[id] = true;
+        }
+        self.grow(node, &keep, true)
+    }
+
+    // Copy without the named leaves. Internal nodes left without leaves are
+    // removed and nodes left with a single child are merged into it, adding
+    // up branch lengths. At least one leaf has to remain.
+    pub fn prune(&self, names: &[&str]) -> Tree {
+        let mut keep = vec![false; self.nodes.len()];
+        for id in self.postorder() {
+            let node = &self.nodes[id];
+            keep[id] = if node.is_leaf() {
+                !node
+                    .name
+                    .as_deref()
+                    .is_some_and(|name| names.contains(&name))
+            } else {
+                node.children.iter().any(|&child| keep[child])
+            };
+        }
+        assert!(keep[self.root], "pruning would remove every leaf");
+
+        // A root left with one child hands the root over to it. An unrooted
+        // tree is never rooted at a node of degree 2, so it moves down too.
+        let mut start = self.root;
+        loop {
+            let kept: Vec<NodeId> = self.nodes[start]
+                .children
+                .iter()
+                .copied()
+                .filter(|&child| keep[child])
+                .collect();
+            let internal = kept.iter().copied().find(|&c| !self.nodes[c].is_leaf());
+            match (kept.len(), internal) {
+                (1, _) => {
+                    keep[start] = false;
+                    start = kept[0];
+                }
+                (2, Some(child)) if !self.rooted => break start = child,
+                _ => break,
+            }
+        }
+        self.grow(start, &keep, self.rooted)
+    }
+
+    // Copy hanging from `node`. Branch lengths and supports stay with their
+    // branches, and an old bifurcating root is merged away.
+    pub fn root_at(&self, node: NodeId) -> Tree {
+        self.grow(node, &vec![true; self.nodes.len()], self.rooted)
+    }
+
+    // Rooted copy with the root placed halfway along the branch above
+    // `outgroup`
+    pub fn reroot(&self, outgroup: NodeId) -> Tree {
+        let parent = self.nodes[outgroup]
+            .parent
+            .expect("the root has no branch to place a new root on");
+        let half = self.nodes[outgroup]
+            .branch_length
+            .map(|length| length / 2.0);
+        let mut tree = self.clone();
+        let midpoint = tree.nodes.len();
+        tree.nodes.push(Node {
+            name: None,
+            branch_length: half,
+            support: self.nodes[outgroup].support,
+            parent: Some(parent),
+            children: vec![outgroup],
+        });
+        for child in tree.nodes[parent].children.iter_mut() {
+            if *child == outgroup {
+                *child = midpoint;
+            }
+        }
+        tree.nodes[outgroup].parent = Some(midpoint);
+        tree.nodes[outgroup].branch_length = half;
+        let mut rerooted = tree.root_at(midpoint);
+        rerooted.rooted = true;
+        rerooted
+    }
+
+    // Unrooted copy. A bifurcating root is merged into one of its children,
+    // since an unrooted tree has no node of degree 2.
+    pub fn unroot(&self) -> Tree {
+        let children = &self.nodes[self.root].children;
+        let start = match children.iter().find(|&&c| !self.nodes[c].is_leaf()) {
+            Some(&child) if children.len() == 2 => child,
+            _ => self.root,
+        };
+        let mut unrooted = self.grow(start, &vec![true; self.nodes.len()], false);
+        unrooted.rooted = false;
+        unrooted
+    }
+
+    // Neighbours of a node in the undirected tree: children, then the parent
+    fn neighbours(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes[id]
+            .children
+            .iter()
+            .copied()
+            .chain(self.nodes[id].parent)
+    }
+
+    // Length and support of the branch between two adjacent nodes
+    fn branch(&self, a: NodeId, b: NodeId) -> (Option<f64>, Option<f64>) {
+        let lower = if self.nodes[b].parent == Some(a) {
+            b
+        } else {
+            a
+        };
+        (self.nodes[lower].branch_length, self.nodes[lower].support)
+    }
+
+    // Walks the undirected tree from `start` over the kept nodes and copies
+    // it with `start` as the root. Nodes other than the root that are left
+    // with a single child are merged into the child.
+    fn grow(&self, start: NodeId, keep: &[bool], rooted: bool) -> Tree {
+        let mut tree = Tree::new(rooted);
+        tree.nodes[0].name = self.nodes[start].name.clone();
+        // (old node, node it was reached from, new parent), pushed in reverse
+        // so new ids come out in pre-order
+        let onward = |node: NodeId, from: NodeId| -> Vec<NodeId> {
+            self.neighbours(node)
+                .filter(|&n| n != from && keep[n])
+                .collect()
+        };
+        let mut stack: Vec<(NodeId, NodeId, NodeId)> = self
+            .neighbours(start)
+            .filter(|&n| keep[n])
+            .map(|n| (n, start, 0))
+            .collect();
+        stack.reverse();
+        while let Some((neighbour, from, parent)) = stack.pop() {
+            let (mut length, mut support) = self.branch(from, neighbour);
+            let (mut previous, mut current) = (from, neighbour);
+            let mut next = onward(current, previous);
+            while next.len() == 1 {
+                let (extra, extra_support) = self.branch(current, next[0]);
+                length = match (length, extra) {
+                    (Some(a), Some(b)) => Some(a + b),
+                    (a, b) => a.or(b),
+                };
+                support = support.or(extra_support);
+                (previous, current) = (current, next[0]);
+                next = onward(current, previous);
+            }
+            let child = tree.add_child(parent, self.nodes[current].name.as_deref(), length);
+            tree.nodes[child].support = support;
+            stack.extend(next.into_iter().rev().map(|n| (n, current, child)));
+        }
+        tree
+    }
+
+    // Tree from clades listed so that children come before their parents,
+    // with the last clade as the root
+    pub(crate) fn from_clades(clades: &[Clade], rooted: bool) -> Tree {
+        let mut tree = Tree::new(rooted);
+        let Some(root) = clades.last() else {
+            return tree;
+        };
+        tree.nodes[0].name = root.name.clone();
+        let mut stack: Vec<(usize, f64, NodeId)> = root
+            .children
+            .iter()
+            .rev()
+            .map(|&(clade, length)| (clade, length, 0))
+            .collect();
+        while let Some((clade, length, parent)) = stack.pop() {
+            let clade_ref = &clades[clade];
+            let child = tree.add_child(parent, clade_ref.name.as_deref(), Some(length));
+            stack.extend(
+                clade_ref
+                    .children
+                    .iter()
+                    .rev()
+                    .map(|&(grandchild, length)| (grandchild, length, child)),
+            );
+        }
+        tree
+    }
+}
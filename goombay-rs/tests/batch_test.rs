@@ -0,0 +1,70 @@
+use goombay_rs::align::{
+    NeedlemanWunsch, PairwiseMetric, WagnerFischer, condensed_distances, condensed_index,
+    distance_matrix, squareform,
+};
+
+const SEQUENCES: [&str; 4] = ["ACTG", "ACTT", "AGTT", "TTTT"];
+
+#[test]
+fn test_condensed_order() {
+    let wf = WagnerFischer::default();
+    let condensed = condensed_distances(&wf, &SEQUENCES, PairwiseMetric::Distance);
+
+    assert_eq!(condensed.len(), 6);
+    for i in 0..SEQUENCES.len() {
+        for j in i + 1..SEQUENCES.len() {
+            let expected = WagnerFischer::compute(SEQUENCES[i], SEQUENCES[j]).distance() as f64;
+            assert_eq!(condensed[condensed_index(SEQUENCES.len(), i, j)], expected);
+        }
+    }
+    assert_eq!(condensed, vec![1.0, 2.0, 3.0, 1.0, 2.0, 2.0]);
+}
+
+#[test]
+fn test_dense_matrix_is_symmetric() {
+    let nw = NeedlemanWunsch::default();
+    let matrix = distance_matrix(&nw, &SEQUENCES, PairwiseMetric::NormalizedDistance);
+
+    assert_eq!(matrix.shape(), (4, 4));
+    for i in 0..4 {
+        assert_eq!(matrix[i][i], 0.0);
+        for j in 0..4 {
+            assert_eq!(matrix[i][j], matrix[j][i]);
+        }
+    }
+    let expected = NeedlemanWunsch::compute("ACTG", "TTTT").normalized_distance();
+    assert_eq!(matrix[0][3], expected);
+}
+
+#[test]
+fn test_owned_sequences() {
+    let sequences: Vec<String> = SEQUENCES.iter().map(|s| s.to_string()).collect();
+    let wf = WagnerFischer::default();
+    let matrix = distance_matrix(&wf, &sequences, PairwiseMetric::Distance);
+    assert_eq!(matrix[1][3], 2.0);
+}
+
+#[test]
+fn test_squareform_roundtrip() {
+    let matrix = squareform(&[1.0, 2.0, 3.0], 3);
+    assert_eq!(
+        matrix,
+        vec![
+            vec![0.0, 1.0, 2.0],
+            vec![1.0, 0.0, 3.0],
+            vec![2.0, 3.0, 0.0],
+        ]
+    );
+    assert_eq!(condensed_index(3, 2, 1), 2);
+}
+
+#[test]
+fn test_small_inputs() {
+    let wf = WagnerFischer::default();
+    let empty: [&str; 0] = [];
+    assert!(condensed_distances(&wf, &empty, PairwiseMetric::Distance).is_empty());
+    assert_eq!(
+        distance_matrix(&wf, &["ACTG"], PairwiseMetric::Distance).shape(),
+        (1, 1)
+    );
+}
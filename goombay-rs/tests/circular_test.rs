@@ -0,0 +1,112 @@
+use goombay_rs::align::{CircularAligner, NeedlemanWunsch, SeededRng, rotate};
+use goombay_rs::scoring::GeneralScoring;
+
+mod common;
+use common::{DNA, random_sequence};
+
+const PLASMID: &str = "GATTACACCGGTTAACTGCA";
+
+#[test]
+fn test_rotate() {
+    assert_eq!(rotate("ABCDE", 0), "ABCDE");
+    assert_eq!(rotate("ABCDE", 2), "CDEAB");
+    assert_eq!(rotate("ABCDE", 5), "ABCDE");
+    assert_eq!(rotate("ABCDE", 7), "CDEAB");
+    assert_eq!(rotate("", 3), "");
+}
+
+#[test]
+fn test_circular_exact_rotation() {
+    let query = rotate(PLASMID, 7);
+    let result = CircularAligner::default().calculate_matrix(&query, PLASMID);
+
+    assert_eq!(result.offset, 7);
+    assert_eq!(result.score, 40);
+    assert_eq!(result.model.similarity(), 40);
+    assert_eq!(result.model.align(), vec![format!("{query}\n{query}")]);
+
+    // Without the rotation the linear alignment pays for the shifted origin
+    let linear = NeedlemanWunsch::compute(&query, PLASMID);
+    assert!(linear.similarity() < result.score);
+}
+
+#[test]
+fn test_circular_same_origin() {
+    let result = CircularAligner::default().calculate_matrix(PLASMID, PLASMID);
+    assert_eq!(result.offset, 0);
+    assert_eq!(result.score, 40);
+}
+
+#[test]
+fn test_circular_with_edits() {
+    // One substitution and one deleted base in a rotated copy
+    let mut query: Vec<char> = rotate(PLASMID, 13).chars().collect();
+    query[3] = 'G';
+    query.remove(10);
+    let query: String = query.into_iter().collect();
+
+    let result = CircularAligner::default().calculate_matrix(&query, PLASMID);
+    assert_eq!(result.offset, 13);
+    // 18 matches, one mismatch and one gap
+    assert_eq!(result.score, 18 * 2 - 1 - 2);
+    let linear = NeedlemanWunsch::compute(&query, PLASMID);
+    assert!(linear.similarity() < result.score);
+}
+
+#[test]
+fn test_circular_lowercase_and_scores() {
+    let scores = GeneralScoring {
+        identity: 1,
+        mismatch: 1,
+        gap: 1,
+    };
+    let query = rotate(PLASMID, 3).to_lowercase();
+    let result = CircularAligner::set_scores(&scores).calculate_matrix(&query, PLASMID);
+    assert_eq!(result.offset, 3);
+    assert_eq!(result.score, 20);
+}
+
+#[test]
+fn test_circular_empty_sequences() {
+    let aligner = CircularAligner::default();
+
+    let result = aligner.calculate_matrix("ACGT", "");
+    assert_eq!(result.offset, 0);
+    assert_eq!(result.score, -8);
+
+    let result = aligner.calculate_matrix("", PLASMID);
+    assert_eq!(result.offset, 0);
+    assert_eq!(result.score, -40);
+}
+
+fn random_dna(rng: &mut SeededRng, max_len: usize) -> String {
+    let len = 1 + rng.below(max_len);
+    random_sequence(rng, len, &DNA)
+}
+
+#[test]
+fn test_circular_matches_every_rotation() {
+    // The semi-global placement alone misses the best rotation here
+    let result = CircularAligner::default().calculate_matrix("TGCCT", "TTC");
+    assert_eq!(result.score, 2);
+
+    let mut rng = SeededRng::new(7);
+    for _ in 0..2000 {
+        let query = random_dna(&mut rng, 12);
+        let subject = random_dna(&mut rng, 12);
+        let scores: Vec<i32> = (0..subject.len())
+            .map(|offset| NeedlemanWunsch::compute(&query, &rotate(&subject, offset)).similarity())
+            .collect();
+        let best = *scores.iter().max().unwrap();
+
+        let result = CircularAligner::default().calculate_matrix(&query, &subject);
+        assert_eq!(result.score, best, "{query} {subject}");
+        // Ties go to the smallest offset
+        assert_eq!(
+            Some(result.offset),
+            scores.iter().position(|&score| score == best),
+            "{query} {subject}"
+        );
+        assert_eq!(result.model.similarity(), best);
+    }
+}
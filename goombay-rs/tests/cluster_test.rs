@@ -0,0 +1,99 @@
+use goombay_rs::align::NeedlemanWunsch;
+use goombay_rs::phylo::{Linkage, Tree, cluster, tree_from_sequences, upgma, wpgma};
+use spindalis::utils::Arr2D;
+
+mod common;
+use common::{matrix, render};
+
+fn assert_ultrametric(tree: &Tree) {
+    let depths: Vec<f64> = tree.leaves().iter().map(|&leaf| tree.depth(leaf)).collect();
+    for depth in &depths {
+        assert!((depth - depths[0]).abs() < 1e-9, "{depths:?}");
+    }
+}
+
+// 5S ribosomal RNA distances from the Wikipedia UPGMA and WPGMA articles
+fn five_s() -> Arr2D<f64> {
+    matrix(&[
+        &[0.0, 17.0, 21.0, 31.0, 23.0],
+        &[17.0, 0.0, 30.0, 34.0, 21.0],
+        &[21.0, 30.0, 0.0, 28.0, 39.0],
+        &[31.0, 34.0, 28.0, 0.0, 43.0],
+        &[23.0, 21.0, 39.0, 43.0, 0.0],
+    ])
+}
+
+#[test]
+fn test_upgma_textbook() {
+    let tree = upgma(&five_s(), &["a", "b", "c", "d", "e"]);
+    assert!(tree.is_rooted());
+    assert_eq!(
+        render(&tree, tree.root()),
+        "(((a:8.5,b:8.5):2.5,e:11):5.5,(c:14,d:14):2.5)"
+    );
+    assert_ultrametric(&tree);
+    assert_eq!(tree.depth(tree.find("a").unwrap()), 16.5);
+}
+
+#[test]
+fn test_wpgma_textbook() {
+    let tree = wpgma(&five_s(), &["a", "b", "c", "d", "e"]);
+    assert_eq!(
+        render(&tree, tree.root()),
+        "(((a:8.5,b:8.5):2.5,e:11):6.5,(c:14,d:14):3.5)"
+    );
+    assert_ultrametric(&tree);
+    assert_eq!(
+        tree,
+        cluster(&five_s(), &["a", "b", "c", "d", "e"], Linkage::Wpgma)
+    );
+}
+
+#[test]
+fn test_small_inputs() {
+    let single = upgma(&matrix(&[&[0.0]]), &["only"]);
+    assert_eq!(render(&single, single.root()), "only");
+    assert_eq!(single.len(), 1);
+
+    let pair = upgma(&matrix(&[&[0.0, 3.0], &[3.0, 0.0]]), &["x", "y"]);
+    assert_eq!(render(&pair, pair.root()), "(x:1.5,y:1.5)");
+}
+
+#[test]
+fn test_ties_join_first_pair() {
+    let distances = matrix(&[&[0.0, 2.0, 2.0], &[2.0, 0.0, 2.0], &[2.0, 2.0, 0.0]]);
+    let tree = upgma(&distances, &["p", "q", "r"]);
+    assert_eq!(render(&tree, tree.root()), "((p:1,q:1):0,r:1)");
+}
+
+#[test]
+#[should_panic(expected = "must be 3 x 3")]
+fn test_mismatched_names() {
+    upgma(&five_s(), &["a", "b", "c"]);
+}
+
+#[test]
+fn test_tree_from_sequences() {
+    let names = ["human", "chimp", "mouse", "rat"];
+    let sequences = [
+        "ACGTACGTTAGCCGATAGCT",
+        "ACGTACGTTAGCCGTTAGCT",
+        "TCGAACGTCAGGCGAAAGGT",
+        "TCGAACGACAGGCGAAAGCT",
+    ];
+    let aligner = NeedlemanWunsch::default();
+    for linkage in [Linkage::Upgma, Linkage::Wpgma] {
+        let tree = tree_from_sequences(&aligner, &names, &sequences, linkage);
+        assert_ultrametric(&tree);
+        assert_eq!(tree.leaf_names(), vec!["human", "chimp", "mouse", "rat"]);
+
+        let id = |name| tree.find(name).unwrap();
+        let primates = tree.lca(id("human"), id("chimp"));
+        let rodents = tree.lca(id("mouse"), id("rat"));
+        assert_ne!(primates, tree.root());
+        assert_ne!(rodents, tree.root());
+        assert_eq!(tree.node(primates).parent(), Some(tree.root()));
+        // One substitution in 20 columns
+        assert!((tree.distance(id("human"), id("chimp")) - 0.05).abs() < 1e-9);
+    }
+}
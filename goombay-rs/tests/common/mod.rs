@@ -0,0 +1,45 @@
+// Helpers shared by the integration tests. Each test crate uses only some
+// of them.
+#![allow(dead_code)]
+
+use goombay_rs::align::SeededRng;
+use goombay_rs::phylo::{NodeId, Tree};
+use spindalis::utils::Arr2D;
+
+pub const DNA: [char; 4] = ['A', 'C', 'G', 'T'];
+
+// Sequence of `len` residues drawn uniformly from `alphabet`
+pub fn random_sequence(rng: &mut SeededRng, len: usize, alphabet: &[char]) -> String {
+    (0..len)
+        .map(|_| alphabet[rng.below(alphabet.len())])
+        .collect()
+}
+
+// Compact Newick-like rendering used to compare shapes
+pub fn render(tree: &Tree, id: NodeId) -> String {
+    let node = tree.node(id);
+    let mut text = String::new();
+    if !node.is_leaf() {
+        let children: Vec<String> = node.children().iter().map(|&c| render(tree, c)).collect();
+        text.push_str(&format!("({})", children.join(",")));
+    }
+    text.push_str(node.name.as_deref().unwrap_or(""));
+    if let Some(support) = node.support {
+        text.push_str(&format!("[{support}]"));
+    }
+    if let Some(length) = node.branch_length {
+        text.push_str(&format!(":{length}"));
+    }
+    text
+}
+
+// Square matrix from its rows
+pub fn matrix(rows: &[&[f64]]) -> Arr2D<f64> {
+    let mut matrix = Arr2D::full(0.0, rows.len(), rows.len());
+    for (i, row) in rows.iter().enumerate() {
+        for (j, &value) in row.iter().enumerate() {
+            matrix[i][j] = value;
+        }
+    }
+    matrix
+}
@@ -0,0 +1,302 @@
+use goombay_rs::align::NeedlemanWunsch;
+use goombay_rs::phylo::{
+    AmbiguityHandling, GapHandling, NucleotideDistance, NucleotideModel, ProteinDistance,
+    ProteinModel, bionj, neighbor_joining,
+};
+
+const MODELS: [NucleotideModel; 6] = [
+    NucleotideModel::PDistance,
+    NucleotideModel::JukesCantor,
+    NucleotideModel::Kimura2P,
+    NucleotideModel::Felsenstein81,
+    NucleotideModel::TamuraNei,
+    NucleotideModel::LogDet,
+];
+
+fn close(actual: Option<f64>, expected: f64) {
+    let actual = actual.unwrap();
+    assert!((actual - expected).abs() < 1e-12, "{actual} != {expected}");
+}
+
+// 200 sites with equal base frequencies: 12 transitions and 8 transversions
+fn balanced() -> (String, String) {
+    let pairs = [
+        ("AA", 45),
+        ("CC", 45),
+        ("GG", 45),
+        ("TT", 45),
+        ("AG", 3),
+        ("GA", 3),
+        ("CT", 3),
+        ("TC", 3),
+        ("AC", 2),
+        ("GT", 2),
+        ("AT", 2),
+        ("CG", 2),
+    ];
+    let (mut first, mut second) = (String::new(), String::new());
+    for (pair, count) in pairs {
+        let pair: Vec<char> = pair.chars().collect();
+        for _ in 0..count {
+            first.push(pair[0]);
+            second.push(pair[1]);
+        }
+    }
+    (first, second)
+}
+
+#[test]
+fn test_equal_frequencies() {
+    let (first, second) = balanced();
+    let distance = |model| {
+        NucleotideDistance::default()
+            .model(model)
+            .between(&first, &second)
+    };
+    close(distance(NucleotideModel::PDistance), 0.1);
+    close(distance(NucleotideModel::JukesCantor), 0.10732563273050497);
+    close(distance(NucleotideModel::Kimura2P), 0.10802209580715165);
+    close(distance(NucleotideModel::LogDet), 0.1071231603416738);
+    // With equal base frequencies F81 reduces to JC69 and TN93 to K80
+    close(
+        distance(NucleotideModel::Felsenstein81),
+        0.10732563273050497,
+    );
+    close(distance(NucleotideModel::TamuraNei), 0.10802209580715165);
+}
+
+#[test]
+fn test_unequal_frequencies() {
+    let first = "AAAAAAAAAACCCCCGGGGGGGTTTAAGGCTA";
+    let second = "AAAAAAAGGACCCTCGGGGAGGTTCAAGGCTT";
+    let expected = [
+        0.1875,
+        0.21576155433883568,
+        0.22674136282254456,
+        0.21738857772719772,
+        0.22979241089538754,
+        0.2562966499988215,
+    ];
+    for (model, expected) in MODELS.into_iter().zip(expected) {
+        let distance = NucleotideDistance::default().model(model);
+        close(distance.between(first, second), expected);
+        // Lower case and U are read like upper case and T
+        close(
+            distance.between(&first.to_lowercase(), &second.replace('T', "U")),
+            expected,
+        );
+    }
+}
+
+#[test]
+fn test_identical_and_saturated() {
+    for model in MODELS {
+        let distance = NucleotideDistance::default().model(model);
+        close(distance.between("ACGTTGCA", "ACGTTGCA"), 0.0);
+        assert_eq!(distance.between("----", "ACGT"), None);
+    }
+    // Three in four sites differ, beyond what JC69 can correct
+    let jc = NucleotideDistance::default();
+    assert_eq!(jc.between("AAAA", "CGTA"), None);
+    close(
+        jc.model(NucleotideModel::PDistance).between("AAAA", "CGTA"),
+        0.75,
+    );
+}
+
+#[test]
+fn test_gaps_and_ambiguity() {
+    let p = NucleotideDistance::default().model(NucleotideModel::PDistance);
+    // The gap and the N are dropped, leaving one difference in four sites
+    close(p.between("AC-GTA", "ACTGNT"), 0.25);
+
+    // Averaging counts R against A as half a difference and N against A as
+    // three quarters
+    let average = p.ambiguity(AmbiguityHandling::Average);
+    close(average.between("ACGTR", "ACGTA"), 0.1);
+    close(average.between("ACGTN", "ACGTA"), 0.15);
+    close(average.between("AC-GTA", "ACTGNT"), 0.35);
+    close(p.between("ACGTR", "ACGTA"), 0.0);
+}
+
+#[test]
+fn test_skips_unknown_characters() {
+    let p = NucleotideDistance::default().model(NucleotideModel::PDistance);
+    // The X, the digit and the space are dropped like gaps
+    close(p.between("ACGX1 TA", "ACGTAAAT"), 0.4);
+    close(
+        p.ambiguity(AmbiguityHandling::Average)
+            .between("AJGT", "ACCT"),
+        1.0 / 3.0,
+    );
+    assert_eq!(p.between("MKJ", "MKV"), None);
+    let matrix = p.matrix(&["ACGT", "AC9T", "XCGA"]).unwrap();
+    close(Some(matrix[0][1]), 0.0);
+    close(Some(matrix[0][2]), 1.0 / 3.0);
+
+    let protein = ProteinDistance::default().model(ProteinModel::PDistance);
+    close(protein.between("MK1VL W", "MKVVLAW"), 0.0);
+    close(protein.between("MKV#L", "MRVLL"), 0.25);
+}
+
+#[test]
+fn test_matrix_and_complete_deletion() {
+    let rows = ["ACGTACGTAC", "ACGTACGTTC", "ACG-ACGAAC", "TCGTAGGTAC"];
+    let p = NucleotideDistance::default().model(NucleotideModel::PDistance);
+
+    let pairwise = p.matrix(&rows).unwrap();
+    close(Some(pairwise[0][1]), 0.1);
+    assert_eq!(pairwise[1][0], pairwise[0][1]);
+    assert_eq!(pairwise[0][0], 0.0);
+    close(Some(pairwise[0][2]), 1.0 / 9.0);
+
+    // Column 4 holds a gap, so it is dropped for every pair
+    let complete = p.gaps(GapHandling::CompleteDeletion).matrix(&rows).unwrap();
+    close(Some(complete[0][1]), 1.0 / 9.0);
+    close(Some(complete[0][3]), 2.0 / 9.0);
+
+    // Every entry matches the pairwise estimate
+    let k80 = NucleotideDistance::default().model(NucleotideModel::Kimura2P);
+    let matrix = k80.matrix(&rows).unwrap();
+    for i in 0..rows.len() {
+        for j in 0..rows.len() {
+            if i != j {
+                assert_eq!(Some(matrix[i][j]), k80.between(rows[i], rows[j]));
+            }
+        }
+    }
+    assert!(k80.matrix(&["AAAA", "ACGT"]).is_none());
+}
+
+#[test]
+fn test_alignment_and_tree() {
+    let model = NeedlemanWunsch::compute("ACGTACGTAC", "ACGACGTTC");
+    let distance = NucleotideDistance::default().model(NucleotideModel::PDistance);
+    let aligned = model.align();
+    let (first, second) = aligned[0].split_once('\n').unwrap();
+    assert_eq!(distance.alignment(&model), distance.between(first, second));
+
+    let rows = [
+        "ACGTACGTACGTACGTACGT",
+        "ACGTACGTACGTACGTACGA",
+        "TCGAACGTACGAACGTTCGT",
+        "TCGAACGTACGAACGTTCGG",
+    ];
+    let matrix = NucleotideDistance::default().matrix(&rows).unwrap();
+    let tree = neighbor_joining(&matrix, &["a", "b", "c", "d"]);
+    let id = |name| tree.find(name).unwrap();
+    assert_eq!(tree.node(id("a")).parent(), tree.node(id("b")).parent());
+    assert!((tree.distance(id("a"), id("b")) - matrix[0][1]).abs() < 1e-12);
+}
+
+// Ten comparable sites with two differences; the gap, the X and the stop
+// are skipped
+const PROTEIN_PAIR: (&str, &str) = ("MKV-LAAGLLAX*", "MKVQLGAGLIAWW");
+
+#[test]
+fn test_protein_models() {
+    let (first, second) = PROTEIN_PAIR;
+    let distance = |model| {
+        ProteinDistance::default()
+            .model(model)
+            .between(first, second)
+    };
+    close(distance(ProteinModel::PDistance), 0.2);
+    close(distance(ProteinModel::Poisson), 0.2231435513142097);
+    close(distance(ProteinModel::Kimura), 0.23319388716771114);
+    close(distance(ProteinModel::Gamma(2.0)), 0.2360679774997898);
+    // Rate variation fades as alpha grows
+    let flat = distance(ProteinModel::Gamma(1e9)).unwrap();
+    assert!((flat - 0.2231435513142097).abs() < 1e-6);
+    assert_eq!(ProteinDistance::default().model, ProteinModel::Poisson);
+}
+
+#[test]
+fn test_protein_saturation() {
+    let poisson = ProteinDistance::default();
+    close(poisson.between("mkvl", "MKVL"), 0.0);
+    assert_eq!(poisson.between("MKVL", "WWWW"), None);
+    assert_eq!(poisson.between("XX--", "MKVL"), None);
+    // Kimura's correction fails before p reaches 1
+    let kimura = poisson.model(ProteinModel::Kimura);
+    assert_eq!(kimura.between("MKVLAAGLLA", "WWWWWWWWWA"), None);
+    close(poisson.between("MKVLAAGLLA", "WWWWWWWWWA"), -(0.1f64.ln()));
+}
+
+#[test]
+#[should_panic(expected = "alpha must be positive")]
+fn test_gamma_needs_positive_alpha() {
+    ProteinDistance::default()
+        .model(ProteinModel::Gamma(0.0))
+        .between("MKV", "MKV");
+}
+
+#[test]
+fn test_protein_from_aligner() {
+    let sequences = [
+        "MKVLAAGLLALLAQG",
+        "MKVLAAGLIALLAQG",
+        "MKTLAGSLLVLLSQG",
+        "MKTLAGSLLVLASQ",
+    ];
+    let aligner = NeedlemanWunsch::default();
+    let gamma = ProteinDistance::default().model(ProteinModel::Gamma(0.5));
+    let matrix = gamma.distance_matrix(&aligner, &sequences).unwrap();
+
+    for i in 0..sequences.len() {
+        assert_eq!(matrix[i][i], 0.0);
+        for j in i + 1..sequences.len() {
+            let model = NeedlemanWunsch::compute(sequences[i], sequences[j]);
+            assert_eq!(Some(matrix[i][j]), gamma.alignment(&model));
+            assert_eq!(matrix[j][i], matrix[i][j]);
+        }
+    }
+
+    let tree = bionj(&matrix, &["a", "b", "c", "d"]);
+    let id = |name| tree.find(name).unwrap();
+    assert_eq!(tree.node(id("a")).parent(), tree.node(id("b")).parent());
+
+    assert!(
+        ProteinDistance::default()
+            .distance_matrix(&aligner, &["MKVL", "WWWW"])
+            .is_none()
+    );
+}
+
+#[test]
+fn test_nucleotide_from_aligner() {
+    let sequences = [
+        "ACGTACGTTAGC",
+        "ACGTACGATAGC",
+        "ACGAACTTAGC",
+        "TTGCACGGTAGCA",
+    ];
+    let aligner = NeedlemanWunsch::default();
+    let k2p = NucleotideDistance::default().model(NucleotideModel::Kimura2P);
+    let matrix = k2p.distance_matrix(&aligner, &sequences).unwrap();
+
+    for i in 0..sequences.len() {
+        assert_eq!(matrix[i][i], 0.0);
+        for j in i + 1..sequences.len() {
+            let model = NeedlemanWunsch::compute(sequences[i], sequences[j]);
+            assert_eq!(Some(matrix[i][j]), k2p.alignment(&model));
+            assert_eq!(matrix[j][i], matrix[i][j]);
+        }
+    }
+
+    assert!(
+        NucleotideDistance::default()
+            .distance_matrix(&aligner, &["AAAA", "CGTC"])
+            .is_none()
+    );
+}
+
+#[test]
+fn test_protein_matrix_complete_deletion() {
+    let rows = ["MKVLAG", "MKV-AS", "MRVLAS"];
+    let p = ProteinDistance::default().model(ProteinModel::PDistance);
+    close(Some(p.matrix(&rows).unwrap()[0][2]), 2.0 / 6.0);
+    let complete = p.gaps(GapHandling::CompleteDeletion).matrix(&rows).unwrap();
+    close(Some(complete[0][2]), 2.0 / 5.0);
+    close(Some(complete[0][1]), 1.0 / 5.0);
+}
@@ -0,0 +1,80 @@
+use goombay_rs::align::{FuzzyMatch, FuzzySearch, WagnerFischer};
+
+#[test]
+fn test_exact_and_edited_matches() {
+    let search = FuzzySearch::default();
+    let matches = search.find("ACGTAC", "TTACGTACTTTTACGAACTTTTACTAC");
+
+    let spans: Vec<(usize, usize, i32)> = matches
+        .iter()
+        .map(|m| (m.start, m.end, m.distance))
+        .collect();
+    assert_eq!(spans, vec![(2, 8, 0), (12, 18, 1), (22, 27, 1)]);
+    assert!(matches.iter().all(|m| m.alignment.is_none()));
+}
+
+#[test]
+fn test_max_distance() {
+    let text = "GGGGACGTACGGGGACTTACGGGG";
+    assert_eq!(
+        FuzzySearch::default()
+            .max_distance(0)
+            .find("ACGTAC", text)
+            .len(),
+        1
+    );
+    let two = FuzzySearch::default().max_distance(2).find("ACGTAC", text);
+    assert_eq!(two.len(), 2);
+    assert_eq!((two[1].start, two[1].distance), (14, 1));
+}
+
+#[test]
+fn test_matches_do_not_overlap() {
+    let matches = FuzzySearch::default()
+        .max_distance(2)
+        .find("ABAB", "ABABABABXXABAB");
+    assert!(matches.windows(2).all(|pair| pair[0].end <= pair[1].start));
+    assert_eq!(
+        matches[0],
+        FuzzyMatch {
+            start: 0,
+            end: 4,
+            distance: 0,
+            alignment: None
+        }
+    );
+    assert!(matches.iter().any(|m| (m.start, m.end) == (4, 8)));
+    assert!(matches.iter().any(|m| (m.start, m.end) == (10, 14)));
+}
+
+#[test]
+fn test_distance_matches_wagner_fischer() {
+    let pattern = "kitten";
+    let text = "the sitting cat sat by the mitten and the kitchen";
+    let matches = FuzzySearch::default().max_distance(2).find(pattern, text);
+    assert!(!matches.is_empty());
+
+    let chars: Vec<char> = text.chars().collect();
+    for m in matches {
+        let window: String = chars[m.start..m.end].iter().collect();
+        assert_eq!(
+            WagnerFischer::compute(pattern, &window).distance(),
+            m.distance
+        );
+    }
+}
+
+#[test]
+fn test_alignment_output() {
+    let matches = FuzzySearch::default()
+        .with_alignment(true)
+        .find("ACGTAC", "TTTACGAACTTT");
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].alignment.as_deref(), Some("ACGTAC\nACGAAC"));
+}
+
+#[test]
+fn test_empty_inputs() {
+    assert!(FuzzySearch::default().find("", "ACGT").is_empty());
+    assert!(FuzzySearch::default().find("ACGT", "").is_empty());
+}
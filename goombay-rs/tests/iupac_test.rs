@@ -0,0 +1,154 @@
+use goombay_rs::align::{
+    Aligner, BothStrands, FuzzySearch, GlobalAlignmentMatrix, LocalAlignmentMatrix, LocalSearch,
+    NeedlemanWunsch, PairwiseMetric, Scoring, SeededRng, SimdEngine, SmithWaterman, Strand,
+    StripedSmithWaterman, WagnerFischer, XDrop, distance_matrix,
+};
+use goombay_rs::scoring::{GeneralScoring, NucleotideScoring};
+
+mod common;
+use common::random_sequence;
+
+// Plain bases weighted twice as often as the ambiguity codes
+const CODES: [char; 14] = [
+    'A', 'C', 'G', 'T', 'A', 'C', 'G', 'T', 'R', 'Y', 'N', 'S', 'K', 'B',
+];
+
+#[test]
+fn test_pair_scores() {
+    let scores = NucleotideScoring::default();
+    assert_eq!(scores.pair_score('A', 'A'), 2);
+    assert_eq!(scores.pair_score('A', 'C'), -1);
+    assert_eq!(scores.pair_score('U', 'T'), 2);
+    // Half the resolutions of R match A
+    assert_eq!(scores.pair_score('A', 'R'), 1);
+    assert_eq!(scores.pair_score('R', 'A'), 1);
+    assert_eq!(scores.pair_score('R', 'R'), 1);
+    // Disjoint sets are a plain mismatch
+    assert_eq!(scores.pair_score('R', 'Y'), -1);
+    assert_eq!(scores.pair_score('B', 'A'), -1);
+    // One in three: 2/3 - 2/3
+    assert_eq!(scores.pair_score('B', 'C'), 0);
+    assert_eq!(scores.pair_score('N', 'A'), 0);
+    assert_eq!(scores.pair_score('N', 'N'), 0);
+    // Non-nucleotide chars fall back to equality
+    assert_eq!(scores.pair_score('*', '*'), 2);
+    assert_eq!(scores.pair_score('*', 'A'), -1);
+
+    let custom = NucleotideScoring {
+        identity: 5,
+        mismatch: 4,
+        gap: 8,
+        n_score: -2,
+    };
+    assert_eq!(custom.pair_score('A', 'N'), -2);
+    assert_eq!(custom.pair_score('A', 'M'), 1);
+    assert_eq!(custom.pair_cost('A', 'N'), 7);
+
+    // Plain scorings keep comparing chars directly
+    let general = GeneralScoring {
+        identity: 2,
+        mismatch: 1,
+        gap: 2,
+    };
+    assert_eq!(general.pair_score('A', 'R'), -1);
+}
+
+#[test]
+fn test_global_aligners() {
+    let nw = NeedlemanWunsch::set_scores(&NucleotideScoring::default());
+    let model = nw.calculate_matrix("ACGTNACGT", "ACGTRACGT");
+    assert_eq!(model.similarity(), 16);
+    assert_eq!(model.align()[0], "ACGTNACGT\nACGTRACGT");
+    assert!(NeedlemanWunsch::compute("ACGTNACGT", "ACGTRACGT").similarity() < 16);
+
+    let iupac = NeedlemanWunsch::<NucleotideScoring>::compute("ACRT", "ACGT");
+    assert_eq!(iupac.similarity(), 7);
+
+    // N is free for the distance aligner
+    let wf = WagnerFischer::<NucleotideScoring>::compute("ACNNT", "ACGTT");
+    assert_eq!(wf.distance(), 0);
+    assert_eq!(WagnerFischer::compute("ACNNT", "ACGTT").distance(), 2);
+}
+
+#[test]
+fn test_local_aligners_agree() {
+    let scores = NucleotideScoring::default();
+    let sw = SmithWaterman::set_scores(&scores);
+    let model = sw.calculate_matrix("TTTTACGNRCGTTTT", "GGACGTACGGG");
+    // ACG scores 6, N/T 0, R/A 1 and CG 4
+    assert_eq!(model.similarity(), 11);
+
+    let mut rng = SeededRng::new(0);
+    for _ in 0..20 {
+        let query = random_sequence(&mut rng, 40, &CODES);
+        let subject = random_sequence(&mut rng, 70, &CODES);
+        let expected = sw.calculate_matrix(&query, &subject).similarity();
+        for engine in [SimdEngine::Avx2, SimdEngine::Sse2, SimdEngine::Portable] {
+            let ssw = StripedSmithWaterman::set_scores(&scores).engine(engine);
+            assert_eq!(ssw.score(&query, &subject), expected);
+        }
+        let parallel = sw.calculate_matrix_parallel(&query, &subject, 8);
+        assert_eq!(parallel.similarity(), expected);
+    }
+}
+
+#[test]
+fn test_striped_scores_outside_match_range() {
+    // A very negative N score no longer fits the byte bias
+    let scores = NucleotideScoring {
+        identity: 2,
+        mismatch: 1,
+        gap: 2,
+        n_score: -300,
+    };
+    let query = "ACGTNACGTACGT";
+    let subject = "ACGTAACGTACGT";
+    let expected = SmithWaterman::set_scores(&scores)
+        .calculate_matrix(query, subject)
+        .similarity();
+    let ssw = StripedSmithWaterman::set_scores(&scores);
+    assert_eq!(ssw.score(query, subject), expected);
+    // Gapping around the N beats aligning it
+    assert_eq!(expected, 20);
+}
+
+#[test]
+fn test_helpers_accept_nucleotide_scoring() {
+    let scores = NucleotideScoring::default();
+
+    let hits = LocalSearch::set_scores(&scores).search(
+        "ACGTACGT",
+        vec![("plain", "ACGAACGA"), ("ambiguous", "ACGNACGN")],
+    );
+    assert_eq!(hits[0].id, "ambiguous");
+
+    let ext = XDrop::set_scores(&scores).extend("ACGTNNACGT", "ACGTACACGT", (0, 0));
+    assert_eq!((ext.query_end, ext.subject_end), (10, 10));
+    assert_eq!(ext.similarity(), 16);
+
+    let edits = NucleotideScoring {
+        identity: 0,
+        mismatch: 1,
+        gap: 1,
+        n_score: 0,
+    };
+    let matches = FuzzySearch::set_scores(&edits)
+        .max_distance(0)
+        .find("ACNT", "GGACGTGGACTTGG");
+    assert_eq!(matches.len(), 2);
+
+    let hit = BothStrands::set_scores(&scores).local("RCGTT", "AACGYAA");
+    assert_eq!(hit.strand, Strand::Reverse);
+
+    let wf = WagnerFischer::set_scores(&edits);
+    let matrix = distance_matrix(&wf, &["ACGT", "ACGN", "TTTT"], PairwiseMetric::Distance);
+    assert_eq!(matrix[0][1], 0.0);
+
+    let mut aligner = Aligner::new(NeedlemanWunsch::set_scores(&scores));
+    assert_eq!(
+        aligner
+            .calculate_matrix("ACGTNACGT", "ACGTRACGT")
+            .similarity(),
+        16
+    );
+}
@@ -0,0 +1,129 @@
+use goombay_rs::align::{DiagonalSeed, KmerIndex, SeededRng, XDrop};
+
+mod common;
+use common::DNA;
+
+fn random_sequence(seed: u64, len: usize) -> String {
+    common::random_sequence(&mut SeededRng::new(seed), len, &DNA)
+}
+
+fn database() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("none", "CCCCCCCCCCCC"),
+        ("shifted", "TTTTTACGTACGGA"),
+        ("exact", "ACGTACGGA"),
+        ("scattered", "ACGTTTTTTTACGG"),
+    ]
+}
+
+#[test]
+fn test_exact_kmer_seeds() {
+    let index = KmerIndex::new(4, database());
+    assert_eq!(index.len(), 4);
+
+    let seeds: Vec<(usize, DiagonalSeed)> = index
+        .seeds("ACGTACGGA")
+        .into_iter()
+        .filter(|(target, _)| *target == 1)
+        .collect();
+    // Every query k-mer lands on diagonal 5 of "shifted", TACG also hits the
+    // leading run at diagonal 1
+    assert_eq!(seeds.len(), 7);
+    assert_eq!(
+        seeds
+            .iter()
+            .filter(|(_, seed)| seed.diagonal() == 5)
+            .count(),
+        6
+    );
+    assert_eq!(
+        seeds[0].1,
+        DiagonalSeed {
+            query_pos: 0,
+            target_pos: 5
+        }
+    );
+}
+
+#[test]
+fn test_candidate_ranking() {
+    let index = KmerIndex::new(4, database());
+    let candidates = index.candidates("acgtacgga", 1);
+
+    let ids: Vec<&str> = candidates.iter().map(|c| c.id).collect();
+    // Equal diagonal support keeps index order; "none" shares nothing
+    assert_eq!(ids, vec!["shifted", "exact", "scattered"]);
+    assert_eq!(candidates[0].best_diagonal, 5);
+    assert_eq!(candidates[1].best_diagonal, 0);
+    assert_eq!(candidates[1].diagonal_hits, 6);
+    // "scattered" shares ACGT at its start and TACG, ACGG further along
+    assert_eq!(candidates[2].hits, 3);
+    assert_eq!(candidates[2].diagonal_hits, 2);
+    assert_eq!(candidates[2].best_diagonal, 6);
+
+    let filtered = index.candidates("ACGTACGGA", 3);
+    assert_eq!(filtered.len(), 2);
+}
+
+#[test]
+fn test_short_sequences() {
+    let index = KmerIndex::new(5, vec![(0, "ACG"), (1, "ACGTACGT")]);
+    assert!(index.seeds("ACG").is_empty());
+    assert!(index.candidates("ACGTA", 1).iter().all(|c| c.id == 1));
+}
+
+#[test]
+fn test_minimizers_find_shared_windows() {
+    let k = 11;
+    let window = 10;
+    let targets: Vec<(usize, String)> = (0..8)
+        .map(|i| (i, random_sequence(i as u64, 400)))
+        .collect();
+    let full = KmerIndex::new(k, targets.clone());
+    let sampled = KmerIndex::with_minimizers(k, window, targets.clone());
+    assert!(sampled.kmer_count() * 3 < full.kmer_count());
+
+    // A query sharing a stretch longer than window + k - 1 always gets seeds
+    let query = format!(
+        "{}{}{}",
+        random_sequence(100, 50),
+        &targets[5].1[200..260],
+        random_sequence(101, 50)
+    );
+    let candidates = sampled.candidates(&query, 1);
+    assert_eq!(candidates[0].id, 5);
+    assert_eq!(candidates[0].best_diagonal, 150);
+}
+
+#[test]
+fn test_deterministic() {
+    let targets: Vec<(usize, String)> = (0..5)
+        .map(|i| (i, random_sequence(i as u64, 200)))
+        .collect();
+    let query = targets[2].1[50..150].to_string();
+    let first = KmerIndex::with_minimizers(8, 5, targets.clone()).candidates(&query, 1);
+    let second = KmerIndex::with_minimizers(8, 5, targets).candidates(&query, 1);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_seeds_feed_xdrop() {
+    let target = format!(
+        "{}ACGTTGCAACGTTGCA{}",
+        random_sequence(7, 30),
+        random_sequence(8, 30)
+    );
+    let index = KmerIndex::new(6, vec![("target", target.as_str())]);
+    let query = "ACGTTGCAACGTTGCA";
+
+    let candidate = &index.candidates(query, 1)[0];
+    // The repeated half of the query also seeds off the main diagonal
+    let seed = *candidate
+        .seeds
+        .iter()
+        .find(|seed| seed.diagonal() == candidate.best_diagonal)
+        .unwrap();
+    let ext = XDrop::default().extend(query, &target, (seed.query_pos, seed.target_pos));
+    assert_eq!((ext.query_start, ext.query_end), (0, 16));
+    assert_eq!(ext.subject_start as isize, candidate.best_diagonal);
+}
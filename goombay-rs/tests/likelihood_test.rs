@@ -0,0 +1,213 @@
+use goombay_rs::phylo::{
+    NucleotideDistance, SubstitutionModel, Tree, TreeLikelihood, discrete_gamma_rates, parse_newick,
+};
+
+const NAMES: [&str; 4] = ["a", "b", "c", "d"];
+const ROWS: [&str; 4] = [
+    "ACGTACGTACGTTGCAACGTAGCT",
+    "ACGTACGAACGTTGCAACTTAGCT",
+    "ACGAACTTACGATGCTACGTAGCA",
+    "ACGAACTTGCGATGCTACGTCGCA",
+];
+
+fn tree(newick: &str) -> Tree {
+    parse_newick(newick).unwrap()
+}
+
+fn close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() < tolerance,
+        "{actual} != {expected}"
+    );
+}
+
+#[test]
+fn test_two_taxa_jukes_cantor() {
+    // Only the path between the leaves matters: 0.3 substitutions per site
+    let tree = tree("(a:0.1,b:0.2);");
+    let decay = (-4.0 * 0.3 / 3.0f64).exp();
+    let same = (0.25 * (0.25 + 0.75 * decay)).ln();
+    let different = (0.25 * (0.25 - 0.25 * decay)).ln();
+
+    let jc = TreeLikelihood::default();
+    let sites = jc.site_log_likelihoods(&tree, &["a", "b"], &["ACGT", "ACTT"]);
+    for (actual, expected) in sites.iter().zip([same, same, different, same]) {
+        close(*actual, expected, 1e-12);
+    }
+    close(
+        jc.log_likelihood(&tree, &["a", "b"], &["ACGT", "ACTT"]),
+        3.0 * same + different,
+        1e-12,
+    );
+
+    // Gaps, '?' and N fit any base, leaving only the other leaf's base
+    let sites = jc.site_log_likelihoods(&tree, &["a", "b"], &["A-?N", "ACGT"]);
+    close(sites[0], same, 1e-12);
+    for site in &sites[1..] {
+        close(*site, 0.25f64.ln(), 1e-12);
+    }
+}
+
+#[test]
+fn test_nested_models() {
+    let tree = tree("((a:0.1,b:0.05):0.02,(c:0.2,d:0.12):0.03);");
+    let jc = TreeLikelihood::default().log_likelihood(&tree, &NAMES, &ROWS);
+    let equal = [0.25; 4];
+    let reductions = [
+        SubstitutionModel::Kimura2P { kappa: 1.0 },
+        SubstitutionModel::Hky85 {
+            kappa: 1.0,
+            frequencies: equal,
+        },
+        SubstitutionModel::Gtr {
+            rates: [2.0; 6],
+            frequencies: equal,
+        },
+    ];
+    for model in reductions {
+        let value = TreeLikelihood::default()
+            .model(model)
+            .log_likelihood(&tree, &NAMES, &ROWS);
+        close(value, jc, 1e-10);
+    }
+
+    // GTR with transitions kappa times as fast as transversions is HKY85
+    let frequencies = [0.4, 0.1, 0.2, 0.3];
+    let hky = TreeLikelihood::default().model(SubstitutionModel::Hky85 {
+        kappa: 4.0,
+        frequencies,
+    });
+    let gtr = hky.model(SubstitutionModel::Gtr {
+        rates: [1.0, 4.0, 1.0, 1.0, 4.0, 1.0],
+        frequencies,
+    });
+    let k80 = hky.model(SubstitutionModel::Kimura2P { kappa: 4.0 });
+    let value = hky.log_likelihood(&tree, &NAMES, &ROWS);
+    close(gtr.log_likelihood(&tree, &NAMES, &ROWS), value, 1e-10);
+    assert!((k80.log_likelihood(&tree, &NAMES, &ROWS) - value).abs() > 1e-3);
+}
+
+#[test]
+fn test_root_position() {
+    // Reversible models give the same likelihood wherever the root sits
+    let model = TreeLikelihood::default().model(SubstitutionModel::Gtr {
+        rates: [1.0, 3.0, 0.5, 0.8, 4.0, 1.0],
+        frequencies: [0.3, 0.2, 0.2, 0.3],
+    });
+    let rooted = tree("((a:0.1,b:0.05):0.02,(c:0.2,d:0.12):0.03);");
+    let unrooted = tree("(a:0.1,b:0.05,(c:0.2,d:0.12):0.05);");
+    let elsewhere = tree("(a:0.04,(b:0.05,(c:0.2,d:0.12):0.05):0.06);");
+    let value = model.log_likelihood(&rooted, &NAMES, &ROWS);
+    close(model.log_likelihood(&unrooted, &NAMES, &ROWS), value, 1e-10);
+    close(
+        model.log_likelihood(&elsewhere, &NAMES, &ROWS),
+        value,
+        1e-10,
+    );
+
+    let sites = model.site_log_likelihoods(&rooted, &NAMES, &ROWS);
+    assert_eq!(sites.len(), ROWS[0].len());
+    close(sites.iter().sum(), value, 1e-10);
+}
+
+#[test]
+fn test_discrete_gamma() {
+    // Yang (1994), table 1
+    let rates = discrete_gamma_rates(0.5, 4);
+    for (rate, expected) in rates.iter().zip([0.0334, 0.2519, 0.8203, 2.8944]) {
+        close(*rate, expected, 1e-4);
+    }
+    for (alpha, k) in [(0.2, 4), (1.0, 8), (5.0, 3)] {
+        let rates = discrete_gamma_rates(alpha, k);
+        close(rates.iter().sum::<f64>() / k as f64, 1.0, 1e-10);
+        assert!(rates.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+    assert_eq!(discrete_gamma_rates(0.5, 1), vec![1.0]);
+
+    // Rate variation fades as alpha grows
+    let tree = tree("((a:0.1,b:0.05):0.02,(c:0.2,d:0.12):0.03);");
+    let uniform = TreeLikelihood::default();
+    let flat = uniform.gamma(1e6, 4);
+    let value = uniform.log_likelihood(&tree, &NAMES, &ROWS);
+    close(flat.log_likelihood(&tree, &NAMES, &ROWS), value, 1e-4);
+    let varied = uniform.gamma(0.3, 4);
+    assert!((varied.log_likelihood(&tree, &NAMES, &ROWS) - value).abs() > 1e-3);
+    assert_eq!(varied.uniform_rates(), uniform);
+}
+
+#[test]
+fn test_optimize_two_taxa() {
+    // The maximum likelihood path length is the JC69 distance
+    let (first, second) = ("ACGTACGTACGTACGTACGT", "ACGTTCGTACGAACGTACCT");
+    let names = ["a", "b"];
+    let fitted = TreeLikelihood::default().optimize(&tree("(a,b);"), &names, &[first, second]);
+    let id = |name| fitted.tree.find(name).unwrap();
+    let expected = NucleotideDistance::default()
+        .between(first, second)
+        .unwrap();
+    close(fitted.tree.distance(id("a"), id("b")), expected, 1e-5);
+    close(
+        TreeLikelihood::default().log_likelihood(&fitted.tree, &names, &[first, second]),
+        fitted.log_likelihood,
+        1e-10,
+    );
+}
+
+#[test]
+fn test_optimize_improves() {
+    let start = tree("((a:0.5,b:0.5):0.5,(c:0.5,d:0.5):0.5);");
+    let model = TreeLikelihood::default()
+        .model(SubstitutionModel::Hky85 {
+            kappa: 2.0,
+            frequencies: [0.3, 0.2, 0.2, 0.3],
+        })
+        .gamma(0.8, 4);
+    let before = model.log_likelihood(&start, &NAMES, &ROWS);
+    let fitted = model.optimize(&start, &NAMES, &ROWS);
+    assert!(fitted.log_likelihood > before + 1.0);
+    assert!(fitted.rounds >= 1);
+    assert_eq!(fitted.tree.leaf_names(), start.leaf_names());
+
+    // No single branch can be nudged to a better likelihood
+    for id in fitted.tree.preorder().into_iter().skip(1) {
+        let length = fitted.tree.node(id).branch_length.unwrap();
+        for step in [-1e-3, 1e-3] {
+            let mut nudged = fitted.tree.clone();
+            nudged.node_mut(id).branch_length = Some((length + step).max(0.0));
+            let value = model.log_likelihood(&nudged, &NAMES, &ROWS);
+            assert!(value <= fitted.log_likelihood + 1e-6);
+        }
+    }
+}
+
+#[test]
+fn test_optimize_multifurcating() {
+    // Branches below a polytomy and a unary node are fitted against the
+    // rest of the tree, which must agree with a full evaluation
+    let start = tree("((a,b,c),(d));");
+    let model = TreeLikelihood::default().gamma(0.5, 3);
+    let fitted = model.optimize(&start, &NAMES, &ROWS);
+    close(
+        model.log_likelihood(&fitted.tree, &NAMES, &ROWS),
+        fitted.log_likelihood,
+        1e-9,
+    );
+    assert!(fitted.log_likelihood > model.log_likelihood(&start, &NAMES, &ROWS));
+}
+
+#[test]
+fn test_unknown_characters_are_missing() {
+    let tree = tree("(a:0.1,b:0.2);");
+    let jc = TreeLikelihood::default();
+    let missing = jc.log_likelihood(&tree, &["a", "b"], &["AC-T", "ACGT"]);
+    close(
+        jc.log_likelihood(&tree, &["a", "b"], &["AC9T", "ACGT"]),
+        missing,
+        1e-12,
+    );
+    close(
+        jc.log_likelihood(&tree, &["a", "b"], &["ACJT", "ACGT"]),
+        missing,
+        1e-12,
+    );
+}
@@ -0,0 +1,94 @@
+use goombay_rs::phylo::{Tree, bionj, neighbor_joining};
+use spindalis::utils::Arr2D;
+
+mod common;
+use common::{matrix, render};
+
+const NAMES: [&str; 5] = ["a", "b", "c", "d", "e"];
+
+// Additive example from the Wikipedia neighbor joining article, which
+// neighbor joining reconstructs exactly
+fn textbook() -> Arr2D<f64> {
+    matrix(&[
+        &[0.0, 5.0, 9.0, 9.0, 8.0],
+        &[5.0, 0.0, 10.0, 10.0, 9.0],
+        &[9.0, 10.0, 0.0, 8.0, 7.0],
+        &[9.0, 10.0, 8.0, 0.0, 3.0],
+        &[8.0, 9.0, 7.0, 3.0, 0.0],
+    ])
+}
+
+fn assert_reproduces(tree: &Tree, distances: &Arr2D<f64>) {
+    for (i, a) in NAMES.iter().enumerate() {
+        for (j, b) in NAMES.iter().enumerate() {
+            let path = tree.distance(tree.find(a).unwrap(), tree.find(b).unwrap());
+            assert!((path - distances[i][j]).abs() < 1e-9, "{a}-{b}: {path}");
+        }
+    }
+}
+
+#[test]
+fn test_textbook_example() {
+    let tree = neighbor_joining(&textbook(), &NAMES);
+    assert!(!tree.is_rooted());
+    assert_eq!(render(&tree, tree.root()), "(((a:2,b:3):3,c:4):2,d:2,e:1)");
+    assert_reproduces(&tree, &textbook());
+}
+
+#[test]
+fn test_bionj_on_additive_distances() {
+    // On additive distances the variance weighting changes nothing
+    let tree = bionj(&textbook(), &NAMES);
+    assert_eq!(render(&tree, tree.root()), "(((a:2,b:3):3,c:4):2,d:2,e:1)");
+    assert_reproduces(&tree, &textbook());
+}
+
+#[test]
+fn test_bionj_differs_on_noisy_distances() {
+    let noisy = matrix(&[
+        &[0.0, 5.5, 9.0, 8.0, 8.5],
+        &[5.5, 0.0, 10.5, 10.0, 8.0],
+        &[9.0, 10.5, 0.0, 8.5, 7.0],
+        &[8.0, 10.0, 8.5, 0.0, 3.5],
+        &[8.5, 8.0, 7.0, 3.5, 0.0],
+    ]);
+    let nj = neighbor_joining(&noisy, &NAMES);
+    let weighted = bionj(&noisy, &NAMES);
+    // Same joins, different branch lengths
+    assert_eq!(nj.leaf_names(), weighted.leaf_names());
+    assert_ne!(nj, weighted);
+    for tree in [&nj, &weighted] {
+        assert!(
+            tree.preorder()
+                .iter()
+                .all(|&id| tree.node(id).branch_length.unwrap_or(0.0) >= 0.0)
+        );
+    }
+}
+
+#[test]
+fn test_negative_lengths_are_clamped() {
+    // The centre of a, b and c would sit -4 away from c
+    let distances = matrix(&[&[0.0, 10.0, 1.0], &[10.0, 0.0, 1.0], &[1.0, 1.0, 0.0]]);
+    for tree in [
+        neighbor_joining(&distances, &["a", "b", "c"]),
+        bionj(&distances, &["a", "b", "c"]),
+    ] {
+        assert_eq!(render(&tree, tree.root()), "(a:5,b:5,c:0)");
+    }
+}
+
+#[test]
+fn test_small_inputs() {
+    let single = neighbor_joining(&matrix(&[&[0.0]]), &["x"]);
+    assert_eq!(render(&single, single.root()), "x");
+
+    let pair = bionj(&matrix(&[&[0.0, 4.0], &[4.0, 0.0]]), &["x", "y"]);
+    assert_eq!(render(&pair, pair.root()), "(x:2,y:2)");
+}
+
+#[test]
+#[should_panic(expected = "must be 6 x 6")]
+fn test_mismatched_names() {
+    neighbor_joining(&textbook(), &["a", "b", "c", "d", "e", "f"]);
+}
@@ -0,0 +1,211 @@
+use goombay_rs::phylo::{
+    NewickError, Tree, neighbor_joining, parse_newick, parse_newick_trees, to_newick, upgma,
+};
+use spindalis::utils::Arr2D;
+
+fn round_trip(text: &str) -> String {
+    to_newick(&parse_newick(text).unwrap())
+}
+
+fn error(text: &str) -> (usize, usize, String) {
+    let NewickError {
+        line,
+        column,
+        message,
+    } = parse_newick(text).unwrap_err();
+    (line, column, message)
+}
+
+#[test]
+fn test_round_trip() {
+    for text in [
+        "((A:1,B:2)X:0.5,(C:1,D:1)Y:1.5,E:3);",
+        "((A,B),(C,D));",
+        "(A,B,(C,D)E)F;",
+        "((A:0.1,B:0.2)95:0.3,(C:0.1,D:0.2)70:0.3);",
+        "('Homo sapiens':1,'O''Brien':2,under_score:3);",
+        "(A:1e-7,B:2.5e20,C:0);",
+        ";",
+        "A;",
+        "(,,(,));",
+    ] {
+        assert_eq!(round_trip(text), text);
+        let tree = parse_newick(text).unwrap();
+        assert_eq!(parse_newick(&to_newick(&tree)).unwrap(), tree);
+    }
+}
+
+#[test]
+fn test_structure() {
+    let tree = parse_newick("((A:1,B:2)X:0.5,(C:1,D:1)Y:1.5,E:3);").unwrap();
+    assert!(!tree.is_rooted());
+    assert_eq!(tree.len(), 8);
+    assert_eq!(tree.leaf_names(), vec!["A", "B", "C", "D", "E"]);
+    let id = |name| tree.find(name).unwrap();
+    assert_eq!(tree.node(id("X")).branch_length, Some(0.5));
+    assert_eq!(tree.distance(id("A"), id("C")), 4.0);
+    assert_eq!(tree.node(tree.root()).name, None);
+
+    // Same node ids as building the tree by hand
+    let mut built = Tree::new(false);
+    let x = built.add_child(0, Some("X"), Some(0.5));
+    built.add_child(x, Some("A"), Some(1.0));
+    built.add_child(x, Some("B"), Some(2.0));
+    let y = built.add_child(0, Some("Y"), Some(1.5));
+    built.add_child(y, Some("C"), Some(1.0));
+    built.add_child(y, Some("D"), Some(1.0));
+    built.add_child(0, Some("E"), Some(3.0));
+    assert_eq!(tree, built);
+}
+
+#[test]
+fn test_bootstrap_values() {
+    let tree = parse_newick("((A,B)95:0.1,(C,D)'80':0.2,(E,F)G:0.3[70.5])99;").unwrap();
+    let inner: Vec<_> = tree.node(tree.root()).children().to_vec();
+    assert_eq!(tree.node(inner[0]).support, Some(95.0));
+    assert_eq!(tree.node(inner[0]).name, None);
+    // Quoted numbers stay names
+    assert_eq!(tree.node(inner[1]).support, None);
+    assert_eq!(tree.node(inner[1]).name.as_deref(), Some("80"));
+    // Support in a comment after the length
+    assert_eq!(tree.node(inner[2]).support, Some(70.5));
+    assert_eq!(tree.node(inner[2]).name.as_deref(), Some("G"));
+    // The root has no branch, so its label is a name
+    assert_eq!(tree.node(tree.root()).name.as_deref(), Some("99"));
+
+    assert_eq!(
+        to_newick(&tree),
+        "((A,B)95:0.1,(C,D)'80':0.2,(E,F)G:0.3[70.5])99;"
+    );
+}
+
+#[test]
+fn test_scientific_notation() {
+    let tree = parse_newick("(A:1.5E-3,B:2e+2,C:-0.25,D:.5);").unwrap();
+    let lengths: Vec<_> = tree
+        .leaves()
+        .into_iter()
+        .map(|id| tree.node(id).branch_length.unwrap())
+        .collect();
+    assert_eq!(lengths, vec![0.0015, 200.0, -0.25, 0.5]);
+    assert_eq!(to_newick(&tree), "(A:0.0015,B:200,C:-0.25,D:0.5);");
+}
+
+#[test]
+fn test_comments_and_whitespace() {
+    let text =
+        "[generated by hand]\n( A : 1 [&color=red] ,\n\t'B c' [x] : 2 )\n[&height=3] ;  [done]";
+    let tree = parse_newick(text).unwrap();
+    assert_eq!(tree.leaf_names(), vec!["A", "B c"]);
+    assert_eq!(to_newick(&tree), "(A:1,'B c':2);");
+}
+
+#[test]
+fn test_rooting() {
+    assert!(parse_newick("(A,B);").unwrap().is_rooted());
+    assert!(!parse_newick("(A,B,C);").unwrap().is_rooted());
+    assert!(parse_newick("[&R] (A,B,C);").unwrap().is_rooted());
+    assert!(!parse_newick("[&u](A,B);").unwrap().is_rooted());
+
+    assert_eq!(round_trip("[&R] (A,B,C);"), "[&R] (A,B,C);");
+    assert_eq!(round_trip("[&U] (A,B);"), "[&U] (A,B);");
+    assert_eq!(round_trip("[&R] (A,B);"), "(A,B);");
+}
+
+#[test]
+fn test_multifurcations() {
+    let tree = parse_newick("((A,B,C,D),(E,F,G),H,I,J);").unwrap();
+    assert_eq!(tree.node(tree.root()).children().len(), 5);
+    assert_eq!(
+        tree.node(tree.node(tree.root()).children()[0])
+            .children()
+            .len(),
+        4
+    );
+}
+
+#[test]
+fn test_builder_output_round_trips() {
+    let mut distances = Arr2D::full(0.0, 4, 4);
+    let values = [
+        [0.0, 0.3, 0.5, 0.6],
+        [0.3, 0.0, 0.55, 0.65],
+        [0.5, 0.55, 0.0, 0.2],
+        [0.6, 0.65, 0.2, 0.0],
+    ];
+    for (i, row) in values.iter().enumerate() {
+        for (j, &value) in row.iter().enumerate() {
+            distances[i][j] = value;
+        }
+    }
+    let names = ["a", "b", "c", "d"];
+    for tree in [
+        upgma(&distances, &names),
+        neighbor_joining(&distances, &names),
+    ] {
+        let text = to_newick(&tree);
+        let parsed = parse_newick(&text).unwrap();
+        assert_eq!(parsed, tree);
+        assert_eq!(to_newick(&parsed), text);
+    }
+}
+
+#[test]
+fn test_deep_tree() {
+    // A caterpillar deep enough to overflow a recursive parser
+    let depth = 100_000;
+    let text = format!("{}A{};", "(".repeat(depth), ",B)".repeat(depth));
+    let tree = parse_newick(&text).unwrap();
+    assert_eq!(tree.leaves().len(), depth + 1);
+    assert_eq!(to_newick(&tree), text);
+}
+
+#[test]
+fn test_several_trees() {
+    let trees = parse_newick_trees("(A,B);\n[second]\n(C,(D,E));\n").unwrap();
+    assert_eq!(trees.len(), 2);
+    assert_eq!(trees[1].leaf_names(), vec!["C", "D", "E"]);
+    assert!(parse_newick_trees("  ").unwrap().is_empty());
+    assert!(parse_newick("(A,B);(C,D);").is_err());
+}
+
+#[test]
+fn test_errors_report_position() {
+    assert_eq!(
+        error("(A,B"),
+        (1, 5, "missing ';' at end of tree".to_string())
+    );
+    assert_eq!(error("(A,B));"), (1, 6, "unmatched ')'".to_string()));
+    assert_eq!(
+        error("(A:1,\n B:x1);"),
+        (2, 4, "invalid branch length 'x1'".to_string())
+    );
+    assert_eq!(
+        error("(A,\n\n  'B);"),
+        (3, 3, "unterminated quoted label".to_string())
+    );
+    assert_eq!(
+        error("(A [note, B);"),
+        (1, 4, "unterminated comment".to_string())
+    );
+    assert_eq!(error("(A B);"), (1, 4, "unexpected 'B'".to_string()));
+    assert_eq!(
+        error("A,B;"),
+        (1, 2, "',' outside of parentheses".to_string())
+    );
+    assert_eq!(
+        error("((A,B);"),
+        (1, 7, "missing ')' before ';'".to_string())
+    );
+    assert_eq!(
+        error("(A:,B);"),
+        (1, 4, "missing branch length after ':'".to_string())
+    );
+    assert_eq!(
+        error("(A,B); x"),
+        (1, 8, "unexpected text after ';'".to_string())
+    );
+
+    let message = parse_newick("(A:1,\n B:x1);").unwrap_err().to_string();
+    assert_eq!(message, "line 2, column 4: invalid branch length 'x1'");
+}
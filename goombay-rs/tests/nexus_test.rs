@@ -0,0 +1,271 @@
+use goombay_rs::align::NeedlemanWunsch;
+use goombay_rs::phylo::{Nexus, NexusError, parse_newick, parse_nexus, to_newick, to_nexus};
+
+// MrBayes style file with an interleaved matrix and blocks of other programs
+const INTERLEAVED: &str = "#NEXUS
+[written by hand]
+BEGIN TAXA;
+    DIMENSIONS NTAX=4;
+    TAXLABELS Homo Pan 'Mus musculus' Rattus;
+END;
+
+BEGIN CHARACTERS;
+    DIMENSIONS NCHAR=12;
+    FORMAT DATATYPE=dna GAP=- MISSING=? INTERLEAVE;
+    CHARSTATELABELS 1 first [ignored];
+    MATRIX
+    Homo           ACGT-A
+    Pan            ACGTTA
+    'Mus musculus' TCGA-A
+    Rattus         TCGAC{AG}
+    Homo           CGTTAG
+    Pan            CGTTAG
+    'Mus musculus' CG?AAG
+    Rattus         CGA AAG
+    ;
+END;
+
+BEGIN ASSUMPTIONS;
+    TYPESET * default = unord: all;
+END;
+
+begin mrbayes;
+    set autoclose=yes nowarn=yes;
+    lset nst=6 rates=invgamma;
+    mcmc ngen=10000 [nested [comment]];
+end;
+
+BEGIN TREES;
+    TRANSLATE
+        1 Homo,
+        2 Pan,
+        3 'Mus musculus',
+        4 Rattus
+    ;
+    TREE * con_50 = [&U] ((1:0.1,2:0.2)95:0.05,3:0.3,4:0.4);
+    TREE rooted = [&R] ((1,2),(3,4));
+END;
+";
+
+fn error(text: &str) -> (usize, usize, String) {
+    let NexusError {
+        line,
+        column,
+        message,
+    } = parse_nexus(text).unwrap_err();
+    (line, column, message)
+}
+
+#[test]
+fn test_interleaved_file() {
+    let nexus = parse_nexus(INTERLEAVED).unwrap();
+    assert_eq!(nexus.taxa, vec!["Homo", "Pan", "Mus musculus", "Rattus"]);
+    assert_eq!(nexus.data_type, "DNA");
+    assert_eq!(nexus.sequence("Homo"), Some("ACGT-ACGTTAG"));
+    assert_eq!(nexus.sequence("Mus musculus"), Some("TCGA-ACG?AAG"));
+    // {AG} is one uncertain state
+    assert_eq!(nexus.sequence("Rattus"), Some("TCGAC?CGAAAG"));
+    assert_eq!(nexus.sequence("Gorilla"), None);
+
+    assert_eq!(nexus.trees.len(), 2);
+    let consensus = nexus.tree("con_50").unwrap();
+    assert!(!consensus.is_rooted());
+    assert_eq!(
+        to_newick(consensus),
+        "((Homo:0.1,Pan:0.2)95:0.05,'Mus musculus':0.3,Rattus:0.4);"
+    );
+    let rooted = nexus.tree("rooted").unwrap();
+    assert!(rooted.is_rooted());
+    assert_eq!(
+        rooted.leaf_names(),
+        vec!["Homo", "Pan", "Mus musculus", "Rattus"]
+    );
+}
+
+#[test]
+fn test_sequences_feed_aligners() {
+    let nexus = parse_nexus(INTERLEAVED).unwrap();
+    let unaligned = nexus.unaligned();
+    assert_eq!(
+        unaligned[0],
+        ("Homo".to_string(), "ACGTACGTTAG".to_string())
+    );
+    let model = NeedlemanWunsch::compute(&unaligned[0].1, &unaligned[1].1);
+    assert_eq!(model.align()[0], "ACG-TACGTTAG\nACGTTACGTTAG");
+}
+
+#[test]
+fn test_data_block_with_matchchar() {
+    // Sequential rows may wrap, and there is no TAXA block
+    let text = "#nexus
+begin data;
+    dimensions ntax=3 nchar=10;
+    format datatype=protein missing=? gap=- matchchar=.;
+    matrix
+        alpha MKVLAA
+              GLLA
+        beta  ....G.
+              A...
+        gamma M-V-AA
+              ?LL.
+    ;
+endblock;
+";
+    let nexus = parse_nexus(text).unwrap();
+    assert_eq!(nexus.taxa, vec!["alpha", "beta", "gamma"]);
+    assert_eq!(nexus.data_type, "PROTEIN");
+    assert_eq!(nexus.sequence("beta"), Some("MKVLGAALLA"));
+    assert_eq!(nexus.sequence("gamma"), Some("M-V-AA?LLA"));
+    assert!(nexus.trees.is_empty());
+}
+
+#[test]
+fn test_state_sets_with_blanks_and_commas() {
+    // Each set is one state however it is spaced or separated
+    let text = "#NEXUS
+BEGIN DATA;
+    DIMENSIONS NTAX=3 NCHAR=6;
+    FORMAT DATATYPE=DNA MATCHCHAR=.;
+    MATRIX
+        a AC{A G}GTA
+        b (A,G)CGGT.
+        c A ( C , T ) { A,G T} GTA
+    ;
+END;
+";
+    let nexus = parse_nexus(text).unwrap();
+    assert_eq!(nexus.sequence("a"), Some("AC?GTA"));
+    assert_eq!(nexus.sequence("b"), Some("?CGGTA"));
+    assert_eq!(nexus.sequence("c"), Some("A??GTA"));
+
+    assert_eq!(
+        error("#NEXUS\nBEGIN DATA; DIMENSIONS NCHAR=2;\nMATRIX\n a A{C G\n;\nEND;"),
+        (4, 5, "unterminated state set".to_string())
+    );
+}
+
+#[test]
+fn test_empty_matrix_with_matchchar() {
+    let text = "#NEXUS
+BEGIN DATA; DIMENSIONS NCHAR=4; FORMAT MATCHCHAR=.; MATRIX ; END;
+";
+    let nexus = parse_nexus(text).unwrap();
+    assert!(nexus.sequences.is_empty());
+    assert!(nexus.taxa.is_empty());
+}
+
+#[test]
+fn test_numeric_labels_without_translate() {
+    let text = "#NEXUS
+BEGIN TAXA; TAXLABELS a b c; END;
+BEGIN TREES; UTREE t1 = (1,2,(3,4)); END;
+";
+    let nexus = parse_nexus(text).unwrap();
+    let tree = nexus.tree("t1").unwrap();
+    assert!(!tree.is_rooted());
+    // 4 is not a taxon number, so it stays as it is
+    assert_eq!(tree.leaf_names(), vec!["a", "b", "c", "4"]);
+}
+
+#[test]
+fn test_round_trip() {
+    let nexus = parse_nexus(INTERLEAVED).unwrap();
+    let text = to_nexus(&nexus);
+    assert_eq!(parse_nexus(&text).unwrap(), nexus);
+    assert_eq!(to_nexus(&parse_nexus(&text).unwrap()), text);
+    assert!(text.contains("    TAXLABELS Homo Pan 'Mus musculus' Rattus;\n"));
+    assert!(text.contains("        'Mus musculus' TCGA-ACG?AAG\n"));
+    assert!(text.contains("    TREE con_50 = [&U] ((1:0.1,2:0.2)95:0.05,3:0.3,4:0.4);\n"));
+}
+
+#[test]
+fn test_rooting_comments() {
+    // Bifurcating roots would otherwise be read as rooted
+    let text = "#NEXUS
+BEGIN TREES;
+    TREE unrooted = [&U] ((A,B),C);
+    TREE rooted = [&R] (A,B,C);
+    TREE guessed = [a note] ((A,B),C);
+    UTREE plain = ((A,B),(C,D));
+END;
+";
+    let nexus = parse_nexus(text).unwrap();
+    assert!(!nexus.tree("unrooted").unwrap().is_rooted());
+    assert!(nexus.tree("rooted").unwrap().is_rooted());
+    assert!(nexus.tree("guessed").unwrap().is_rooted());
+    assert!(!nexus.tree("plain").unwrap().is_rooted());
+
+    let written = to_nexus(&nexus);
+    assert!(written.contains("    TREE plain = [&U] ((A,B),(C,D));\n"));
+    assert_eq!(parse_nexus(&written).unwrap(), nexus);
+}
+
+#[test]
+fn test_writes_only_present_blocks() {
+    let mut nexus = Nexus::default();
+    assert_eq!(to_nexus(&nexus), "#NEXUS\n");
+
+    nexus
+        .trees
+        .push(("nj".to_string(), parse_newick("(x:1,y:2,z:3);").unwrap()));
+    let text = to_nexus(&nexus);
+    assert_eq!(
+        text,
+        "#NEXUS\n\nBEGIN TREES;\n    TREE nj = [&U] (x:1,y:2,z:3);\nEND;\n"
+    );
+    assert_eq!(parse_nexus(&text).unwrap(), nexus);
+}
+
+#[test]
+fn test_errors_report_position() {
+    assert_eq!(
+        error("BEGIN TAXA;"),
+        (1, 1, "missing #NEXUS header".to_string())
+    );
+    assert_eq!(
+        error("#NEXUS\nBEGIN TAXA;\n  TAXLABELS a b"),
+        (3, 3, "missing ';' at end of TAXLABELS".to_string())
+    );
+    assert_eq!(
+        error("#NEXUS\nBEGIN TAXA;\n  TAXLABELS a b;\n"),
+        (2, 1, "TAXA block has no END".to_string())
+    );
+    assert_eq!(
+        error("#NEXUS\nBEGIN TAXA; DIMENSIONS NTAX=3;\n TAXLABELS a b; END;"),
+        (3, 2, "expected 3 taxa, found 2".to_string())
+    );
+    assert_eq!(
+        error("#NEXUS\nBEGIN DATA; DIMENSIONS NCHAR=4;\nMATRIX\n a ACGT\n b ACG\n;\nEND;"),
+        (5, 2, "row 'b' has 3 characters, expected 4".to_string())
+    );
+    assert_eq!(
+        error(
+            "#NEXUS\nBEGIN TAXA; TAXLABELS a; END;\nBEGIN DATA; DIMENSIONS NCHAR=2;\nMATRIX a AC z GT;\nEND;"
+        ),
+        (4, 13, "unknown taxon 'z'".to_string())
+    );
+    assert_eq!(
+        error("#NEXUS\nBEGIN TREES;\n  TREE t = (a,\n    b:x);\nEND;"),
+        (4, 7, "invalid branch length 'x'".to_string())
+    );
+    assert_eq!(
+        error("#NEXUS\nBEGIN TREES;\n  TREE t = (a,b:x);\nEND;"),
+        (3, 17, "invalid branch length 'x'".to_string())
+    );
+    assert_eq!(
+        error("#NEXUS\n[unclosed\nBEGIN TAXA;"),
+        (2, 1, "unterminated comment".to_string())
+    );
+    assert_eq!(
+        error("#NEXUS\nTAXLABELS a;"),
+        (2, 1, "expected BEGIN, found 'TAXLABELS'".to_string())
+    );
+
+    let message = parse_nexus("#NEXUS\nBEGIN DATA; DIMENSIONS NCHAR=x; END;")
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        message,
+        "line 2, column 30: NCHAR must be a whole number, found 'x'"
+    );
+}
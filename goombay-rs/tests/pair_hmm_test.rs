@@ -0,0 +1,392 @@
+use goombay_rs::align::{
+    Emissions, GlobalAlignmentMatrix, NeedlemanWunsch, PairHmm, PosteriorMatrix,
+};
+use goombay_rs::scoring::GeneralScoring;
+use spindalis::utils::Arr2D;
+
+// Probability of emitting `a` in the query aligned to `b` in the subject, or
+// to a gap when `b` is None
+fn emission(hmm: &PairHmm<GeneralScoring>, a: char, b: Option<char>) -> f64 {
+    match &hmm.emissions {
+        Emissions::Uniform {
+            identical,
+            different,
+            background,
+        } => match b {
+            Some(b) if a == b => *identical,
+            Some(_) => *different,
+            None => *background,
+        },
+        Emissions::Table {
+            alphabet,
+            pairs,
+            background,
+        } => {
+            let index = |c| alphabet.iter().position(|&x| x == c).unwrap();
+            match b {
+                Some(b) => pairs[index(a)][index(b)],
+                None => background[index(a)],
+            }
+        }
+    }
+}
+
+// Probabilities of every state path that emits query[i..] and subject[j..]
+// from `state` (0 match, 1 insert-x, 2 insert-y), by brute force
+fn paths(
+    hmm: &PairHmm<GeneralScoring>,
+    query: &[char],
+    subject: &[char],
+    state: usize,
+    i: usize,
+    j: usize,
+) -> Vec<f64> {
+    if i == query.len() && j == subject.len() {
+        return vec![hmm.end];
+    }
+    let (to_match, to_x, to_y) = match state {
+        0 => (
+            1.0 - 2.0 * hmm.gap_open - hmm.end,
+            hmm.gap_open,
+            hmm.gap_open,
+        ),
+        1 => (1.0 - hmm.gap_extend - hmm.end, hmm.gap_extend, 0.0),
+        _ => (1.0 - hmm.gap_extend - hmm.end, 0.0, hmm.gap_extend),
+    };
+    let mut probabilities = Vec::new();
+    if i < query.len() && j < subject.len() {
+        let emitted = emission(hmm, query[i], Some(subject[j]));
+        for p in paths(hmm, query, subject, 0, i + 1, j + 1) {
+            probabilities.push(to_match * emitted * p);
+        }
+    }
+    if i < query.len() && to_x > 0.0 {
+        let emitted = emission(hmm, query[i], None);
+        for p in paths(hmm, query, subject, 1, i + 1, j) {
+            probabilities.push(to_x * emitted * p);
+        }
+    }
+    if j < subject.len() && to_y > 0.0 {
+        let emitted = emission(hmm, subject[j], None);
+        for p in paths(hmm, query, subject, 2, i, j + 1) {
+            probabilities.push(to_y * emitted * p);
+        }
+    }
+    probabilities
+}
+
+fn brute_force(hmm: &PairHmm<GeneralScoring>, query: &str, subject: &str) -> (f64, f64) {
+    let query: Vec<char> = query.chars().collect();
+    let subject: Vec<char> = subject.chars().collect();
+    let all = paths(hmm, &query, &subject, 0, 0, 0);
+    let total: f64 = all.iter().sum();
+    let best = all.iter().copied().fold(0.0, f64::max);
+    (total.ln(), best.ln())
+}
+
+#[test]
+fn test_forward_and_viterbi_match_enumeration() {
+    let hmm = PairHmm::default().gap_open(0.1).gap_extend(0.3).end(0.05);
+    for (query, subject) in [
+        ("ACG", "AG"),
+        ("GATT", "GCAT"),
+        ("A", "T"),
+        ("AC", ""),
+        ("", "CG"),
+    ] {
+        let (total, best) = brute_force(&hmm, query, subject);
+        assert!((hmm.forward(query, subject) - total).abs() < 1e-9);
+        assert!((hmm.viterbi(query, subject).log_probability - best).abs() < 1e-9);
+        assert!((hmm.posterior(query, subject).log_likelihood - total).abs() < 1e-9);
+    }
+}
+
+// Transition-rich DNA emissions with a GC-rich background
+fn dna_table(hmm: &PairHmm<GeneralScoring>) -> PairHmm<GeneralScoring> {
+    let background = [0.2, 0.3, 0.3, 0.2];
+    let mut pairs = Arr2D::full(0.0, 4, 4);
+    for a in 0..4 {
+        for b in 0..4 {
+            let weight = if a == b {
+                8.0
+            } else if a % 2 == b % 2 {
+                // A-G and C-T are transitions
+                2.0
+            } else {
+                1.0
+            };
+            pairs[a][b] = weight * background[a] * background[b];
+        }
+    }
+    let total: f64 = (0..4).map(|a| pairs[a].iter().sum::<f64>()).sum();
+    for a in 0..4 {
+        for b in 0..4 {
+            pairs[a][b] /= total;
+        }
+    }
+    hmm.emission_table("acgt", pairs, &background)
+}
+
+#[test]
+fn test_emission_table() {
+    let hmm = dna_table(&PairHmm::default().gap_open(0.1).gap_extend(0.3).end(0.05));
+    for (query, subject) in [("ACG", "AG"), ("GATT", "GCAT"), ("CC", ""), ("", "GA")] {
+        let (total, best) = brute_force(&hmm, query, subject);
+        assert!((hmm.forward(query, subject) - total).abs() < 1e-9);
+        assert!((hmm.viterbi(query, subject).log_probability - best).abs() < 1e-9);
+        assert!((hmm.posterior(query, subject).log_likelihood - total).abs() < 1e-9);
+    }
+
+    // A table holding the uniform emissions gives the same model
+    let uniform = PairHmm::default();
+    let mut pairs = Arr2D::full(0.2 / 12.0, 4, 4);
+    for a in 0..4 {
+        pairs[a][a] = 0.2;
+    }
+    let table = uniform.emission_table("ACGT", pairs, &[0.25; 4]);
+    let (query, subject) = ("GATTACAGATTACA", "GATTACAGTACA");
+    assert!((table.forward(query, subject) - uniform.forward(query, subject)).abs() < 1e-9);
+    assert_eq!(
+        table.viterbi(query, subject).model.align(),
+        uniform.viterbi(query, subject).model.align()
+    );
+}
+
+#[test]
+fn test_emission_table_unknown_residues() {
+    let hmm = dna_table(&PairHmm::default().gap_open(0.1).gap_extend(0.3));
+    assert_eq!(hmm.forward("gatc", "gac"), hmm.forward("GATC", "GAC"));
+
+    // N stands for any base, so its likelihood sums those of every base
+    let any: f64 = ['A', 'C', 'G', 'T']
+        .iter()
+        .map(|base| hmm.forward("GATC", &format!("G{base}TC")).exp())
+        .sum();
+    assert!((hmm.forward("GATC", "GNTC") - any.ln()).abs() < 1e-9);
+    let any: f64 = ['A', 'C', 'G', 'T']
+        .iter()
+        .map(|base| hmm.forward(&format!("A{base}"), "AC").exp())
+        .sum();
+    assert!((hmm.forward("AN", "AC") - any.ln()).abs() < 1e-9);
+    assert_eq!(
+        hmm.viterbi("GATTNCA", "GATTACA").model.align()[0],
+        "GATTNCA\nGATTACA"
+    );
+}
+
+#[test]
+#[should_panic(expected = "emission table must be 4 x 4")]
+fn test_emission_table_shape() {
+    PairHmm::default().emission_table("ACGT", Arr2D::full(0.1, 4, 3), &[0.25; 4]);
+}
+
+#[test]
+fn test_empty_sequences() {
+    let hmm = PairHmm::default();
+    assert!((hmm.forward("", "") - hmm.end.ln()).abs() < 1e-12);
+    let result = hmm.viterbi("", "");
+    assert_eq!(result.model.align(), vec!["\n"]);
+}
+
+#[test]
+fn test_viterbi_alignment() {
+    let hmm = PairHmm::default();
+    let result = hmm.viterbi("GATTACAGATTACA", "gattacagtacA");
+
+    assert_eq!(result.model.align(), vec!["GATTACAGATTACA\nGATTACAG--TACA"]);
+    // Scored like NeedlemanWunsch: 12 identities and 2 gaps
+    assert_eq!(result.model.similarity(), 20);
+    assert_eq!(result.model.distance(), 8);
+    let nw = NeedlemanWunsch::compute("GATTACAGATTACA", "GATTACAGTACA");
+    assert_eq!(result.model.similarity(), nw.similarity());
+    assert_eq!(result.model.distance(), nw.distance());
+    assert_eq!(
+        result.model.normalized_similarity(),
+        nw.normalized_similarity()
+    );
+    assert!(result.log_probability <= hmm.forward("GATTACAGATTACA", "GATTACAGTACA"));
+}
+
+#[test]
+fn test_posterior_probabilities() {
+    let hmm = PairHmm::default();
+    let (query, subject) = ("ACGTTGCAAGT", "ACGTGCAAGT");
+    let posterior = hmm.posterior(query, subject);
+    let m = query.len();
+    let n = subject.len();
+    assert_eq!(
+        (posterior.matched.height, posterior.matched.width),
+        (m + 1, n + 1)
+    );
+
+    // A residue is aligned to at most one other
+    for i in 1..=m {
+        let row: f64 = (1..=n).map(|j| posterior.matched[i][j]).sum();
+        assert!(row <= 1.0 + 1e-9);
+    }
+    for j in 1..=n {
+        let column: f64 = (1..=m).map(|i| posterior.matched[i][j]).sum();
+        assert!(column <= 1.0 + 1e-9);
+    }
+    // The extra T in the query is the only residue likely to be unaligned
+    let gaps: f64 = (1..=m).map(|i| posterior.query_gap(i)).sum();
+    assert!((gaps - 1.0).abs() < 0.05);
+    assert!((1..=n).all(|j| posterior.subject_gap(j) < 0.05));
+    // The flanks are confidently aligned
+    assert!(posterior.matched[1][1] > 0.99);
+    assert!(posterior.matched[m][n] > 0.99);
+    assert!(posterior.matched[1][2] < 0.01);
+
+    // Column confidence follows the alignment, and the ambiguous T gap gets
+    // less confidence than the well-anchored ends
+    let viterbi = hmm.viterbi(query, subject);
+    let confidence = posterior.column_confidence(&viterbi.model);
+    assert_eq!(confidence.len(), 11);
+    assert!(confidence.iter().all(|&c| (0.0..=1.0).contains(&c)));
+    let gap_column = viterbi.model.align()[0]
+        .split('\n')
+        .nth(1)
+        .unwrap()
+        .find('-')
+        .unwrap();
+    assert!(confidence[gap_column] < confidence[0]);
+}
+
+#[test]
+#[should_panic(expected = "must not exceed 1")]
+fn test_invalid_transitions() {
+    PairHmm::default().gap_open(0.6).forward("A", "A");
+}
+
+// Highest sum of posteriors over every alignment of query[i..] and subject[j..]
+fn best_accuracy(matched: &Arr2D<f64>, i: usize, j: usize) -> f64 {
+    let (m, n) = (matched.height - 1, matched.width - 1);
+    let mut best: f64 = 0.0;
+    if i < m && j < n {
+        best = best.max(matched[i + 1][j + 1] + best_accuracy(matched, i + 1, j + 1));
+    }
+    if i < m {
+        best = best.max(best_accuracy(matched, i + 1, j));
+    }
+    if j < n {
+        best = best.max(best_accuracy(matched, i, j + 1));
+    }
+    best
+}
+
+// Sum of the posteriors of the aligned pairs in a model's first alignment
+fn pair_sum(posterior: &PosteriorMatrix, alignment: &str) -> f64 {
+    let (query, subject) = alignment.split_once('\n').unwrap();
+    let (mut i, mut j, mut sum) = (0, 0, 0.0);
+    for (q, s) in query.chars().zip(subject.chars()) {
+        if q != '-' {
+            i += 1;
+        }
+        if s != '-' {
+            j += 1;
+        }
+        if q != '-' && s != '-' {
+            sum += posterior.matched[i][j];
+        }
+    }
+    sum
+}
+
+// Default NeedlemanWunsch score of an alignment's columns
+fn column_score(alignment: &str) -> i32 {
+    let (query, subject) = alignment.split_once('\n').unwrap();
+    query
+        .chars()
+        .zip(subject.chars())
+        .map(|(q, s)| match (q, s) {
+            ('-', _) | (_, '-') => -2,
+            _ if q == s => 2,
+            _ => -1,
+        })
+        .sum()
+}
+
+#[test]
+fn test_mea_identical() {
+    let result = PairHmm::default().mea("ACGTACGGTA", "acgtacggta");
+    assert_eq!(result.model.align(), vec!["ACGTACGGTA\nACGTACGGTA"]);
+    assert_eq!(result.model.distance(), 0);
+    assert!(result.expected_accuracy > 0.95 && result.expected_accuracy <= 1.0);
+    assert!((result.expected_pairs - 10.0 * result.expected_accuracy).abs() < 1e-9);
+}
+
+#[test]
+fn test_mea_is_optimal() {
+    let hmm = PairHmm::default().gap_open(0.1).gap_extend(0.5);
+    for (query, subject) in [
+        ("GATTACA", "GCATGCA"),
+        ("ACGTTGCA", "ACTGCA"),
+        ("AAAA", "AAA"),
+        ("TTG", "CACAG"),
+    ] {
+        let posterior = hmm.posterior(query, subject);
+        let result = hmm.mea(query, subject);
+        let alignment = &result.model.align()[0];
+
+        assert!((best_accuracy(&posterior.matched, 0, 0) - result.expected_pairs).abs() < 1e-9);
+        assert!((pair_sum(&posterior, alignment) - result.expected_pairs).abs() < 1e-9);
+        // Viterbi can never beat MEA on expected accuracy
+        let viterbi = hmm.viterbi(query, subject);
+        assert!(pair_sum(&posterior, &viterbi.model.align()[0]) <= result.expected_pairs + 1e-9);
+
+        // Both models score their own path, which can fall short of the
+        // best NeedlemanWunsch alignment
+        let best = NeedlemanWunsch::compute(query, subject).similarity();
+        for model in [&result.model, &viterbi.model] {
+            assert_eq!(model.similarity(), column_score(&model.align()[0]));
+            assert!(model.similarity() <= best);
+        }
+    }
+}
+
+#[test]
+fn test_scoring_of_the_models() {
+    let scores = GeneralScoring {
+        identity: 1,
+        mismatch: 3,
+        gap: 2,
+    };
+    let hmm = PairHmm::set_scores(&scores);
+    let (query, subject) = ("GATTACAGATTACA", "GATTACAGTACA");
+    // The emissions and so the paths are those of the default model
+    assert_eq!(
+        hmm.viterbi(query, subject).model.align(),
+        PairHmm::default().viterbi(query, subject).model.align()
+    );
+
+    // 12 identities and 2 gaps
+    let nw = NeedlemanWunsch::set_scores(&scores).calculate_matrix(query, subject);
+    for model in [
+        hmm.viterbi(query, subject).model,
+        hmm.mea(query, subject).model,
+    ] {
+        assert_eq!(model.similarity(), 8);
+        assert_eq!(model.similarity(), nw.similarity());
+        assert_eq!(model.distance(), nw.distance());
+        assert_eq!(model.normalized_similarity(), nw.normalized_similarity());
+    }
+    let mismatched = hmm.viterbi("ACGT", "AGGT").model;
+    assert_eq!(mismatched.align()[0], "ACGT\nAGGT");
+    assert_eq!(mismatched.similarity(), 0);
+}
+
+#[test]
+fn test_mea_alignment() {
+    let hmm = PairHmm::default();
+    let result = hmm.mea("GATTACAGATTACA", "GATTACAGTACA");
+    assert_eq!(result.model.align(), vec!["GATTACAGATTACA\nGATTACAG--TACA"]);
+    assert_eq!(result.model.similarity(), 20);
+    assert_eq!(result.model.distance(), 8);
+    assert!(result.expected_accuracy > 0.9);
+
+    let empty = hmm.mea("ACGT", "");
+    assert_eq!(empty.model.align(), vec!["ACGT\n----"]);
+    assert_eq!(empty.expected_pairs, 0.0);
+    assert_eq!(empty.expected_accuracy, 1.0);
+    assert_eq!(empty.model.similarity(), -8);
+}
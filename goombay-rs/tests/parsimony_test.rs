@@ -0,0 +1,228 @@
+use goombay_rs::phylo::{StepMatrix, Tree, fitch, parse_newick, sankoff};
+use spindalis::utils::Arr2D;
+
+const NAMES: [&str; 5] = ["a", "b", "c", "d", "e"];
+const ROWS: [&str; 5] = ["ACGTAC-G", "ACGTTCAG", "TCATTGAG", "TCAATGCG", "GCA?TGCT"];
+
+fn tree(newick: &str) -> Tree {
+    parse_newick(newick).unwrap()
+}
+
+// Cost of the reconstruction: steps summed over every branch of every column
+fn reconstruction_cost(tree: &Tree, states: &[String], cost: impl Fn(char, char) -> f64) -> f64 {
+    let mut total = 0.0;
+    for id in tree.preorder() {
+        let Some(parent) = tree.node(id).parent() else {
+            continue;
+        };
+        for (from, to) in states[parent].chars().zip(states[id].chars()) {
+            if from != '-' && from != '?' && to != '-' && to != '?' {
+                total += cost(from, to);
+            }
+        }
+    }
+    total
+}
+
+// Least cost of one column over every assignment of states to internal
+// nodes
+fn brute_force(tree: &Tree, column: &[char], steps: &StepMatrix) -> f64 {
+    let internal: Vec<usize> = tree
+        .preorder()
+        .into_iter()
+        .filter(|&id| !tree.node(id).is_leaf())
+        .collect();
+    let states = steps.states();
+    let mut best = f64::INFINITY;
+    for mut code in 0..states.len().pow(internal.len() as u32) {
+        let mut assigned = vec![' '; tree.len()];
+        for &id in &internal {
+            assigned[id] = states[code % states.len()];
+            code /= states.len();
+        }
+        for (leaf, &c) in tree.leaves().into_iter().zip(column) {
+            assigned[leaf] = c;
+        }
+        let cost: f64 = tree
+            .preorder()
+            .into_iter()
+            .filter_map(|id| {
+                let parent = tree.node(id).parent()?;
+                Some(steps.cost(assigned[parent], assigned[id]))
+            })
+            .sum();
+        best = best.min(cost);
+    }
+    best
+}
+
+// Transitions cost 1 and transversions 2
+fn transversions() -> StepMatrix {
+    let mut costs = Arr2D::full(2.0, 4, 4);
+    for (i, j) in [(0, 2), (2, 0), (1, 3), (3, 1)] {
+        costs[i][j] = 1.0;
+    }
+    for i in 0..4 {
+        costs[i][i] = 0.0;
+    }
+    StepMatrix::new("ACGT", costs)
+}
+
+#[test]
+fn test_fitch_compares_topologies() {
+    let names = ["a", "b", "c", "d"];
+    // One informative site grouping a with b, and one constant site
+    let rows = ["AC", "AC", "GC", "GC"];
+    let right = fitch(&tree("((a,b),(c,d));"), &names, &rows);
+    let wrong = fitch(&tree("((a,c),(b,d));"), &names, &rows);
+    assert_eq!(right.sites, vec![1, 0]);
+    assert_eq!(right.total, 1);
+    assert_eq!(wrong.sites, vec![2, 0]);
+    assert_eq!(wrong.total, 2);
+}
+
+#[test]
+fn test_fitch_reconstruction() {
+    let tree = tree("(((a,b)x,c)y,(d,e)z)r;");
+    let result = fitch(&tree, &NAMES, &ROWS);
+    assert_eq!(result.sites, vec![2, 0, 1, 1, 1, 1, 1, 1]);
+    assert_eq!(result.total, 8);
+
+    // Leaves keep their rows and internal nodes get a full reconstruction
+    // whose changes add up to the score
+    let id = |name| tree.find(name).unwrap();
+    assert_eq!(result.states[id("c")], "TCATTGAG");
+    assert_eq!(result.states[id("x")], "ACGTTCAG");
+    assert_eq!(result.states[id("r")].len(), 8);
+    let unit = |a: char, b: char| if a == b { 0.0 } else { 1.0 };
+    assert_eq!(reconstruction_cost(&tree, &result.states, unit), 8.0);
+}
+
+#[test]
+fn test_fitch_matches_brute_force() {
+    let steps = StepMatrix::unit("ACGT");
+    let columns: Vec<Vec<char>> = ["ACGTA", "AACCG", "ATTAA", "GGCCT", "CATGC"]
+        .iter()
+        .map(|column| column.chars().collect())
+        .collect();
+    // Bifurcating, rooted on a leaf's branch, and with multifurcations
+    for newick in [
+        "(((a,b),c),(d,e));",
+        "(a,(b,(c,(d,e))));",
+        "((a,b,c),d,e);",
+        "(a,b,c,d,e);",
+    ] {
+        let tree = tree(newick);
+        for column in &columns {
+            let rows: Vec<String> = column.iter().map(char::to_string).collect();
+            let score = fitch(&tree, &NAMES, &rows);
+            assert_eq!(
+                score.total as f64,
+                brute_force(&tree, column, &steps),
+                "{newick} {column:?}"
+            );
+            let unit = |a: char, b: char| if a == b { 0.0 } else { 1.0 };
+            assert_eq!(
+                reconstruction_cost(&tree, &score.states, unit),
+                score.total as f64
+            );
+        }
+    }
+}
+
+#[test]
+fn test_missing_data() {
+    let tree = tree("((a,b),(c,d));");
+    let names = ["a", "b", "c", "d"];
+    // '?' and '-' fit whatever is cheapest, and an empty column costs nothing
+    let result = fitch(&tree, &names, &["A-?", "A-C", "?-g", "G-G"]);
+    assert_eq!(result.sites, vec![1, 0, 1]);
+    assert_eq!(result.states[tree.root()].chars().nth(1), Some('?'));
+
+    let weighted = sankoff(
+        &tree,
+        &names,
+        &["A-?", "A-C", "?-g", "G-G"],
+        &transversions(),
+    );
+    assert_eq!(weighted.sites, vec![1.0, 0.0, 2.0]);
+
+    // '.' is a gap as well
+    let dotted = ["A.?", "A-C", "?.g", "G.G"];
+    assert_eq!(fitch(&tree, &names, &dotted).sites, vec![1, 0, 1]);
+    let weighted = sankoff(&tree, &names, &dotted, &transversions());
+    assert_eq!(weighted.sites, vec![1.0, 0.0, 2.0]);
+}
+
+#[test]
+fn test_sankoff_unit_matches_fitch() {
+    let steps = StepMatrix::unit("ACGT");
+    for newick in ["(((a,b)x,c)y,(d,e)z)r;", "((a,b,c),d,e);"] {
+        let tree = tree(newick);
+        let unweighted = fitch(&tree, &NAMES, &ROWS);
+        let weighted = sankoff(&tree, &NAMES, &ROWS, &steps);
+        let sites: Vec<f64> = unweighted.sites.iter().map(|&s| s as f64).collect();
+        assert_eq!(weighted.sites, sites);
+        assert_eq!(weighted.total, unweighted.total as f64);
+    }
+}
+
+#[test]
+fn test_sankoff_step_matrix() {
+    let tree = tree("(((a,b)x,c)y,(d,e)z)r;");
+    let steps = transversions();
+    let result = sankoff(&tree, &NAMES, &ROWS, &steps);
+    assert_eq!(result.sites, vec![4.0, 0.0, 1.0, 2.0, 2.0, 2.0, 2.0, 2.0]);
+    assert_eq!(result.total, 15.0);
+    assert_eq!(
+        reconstruction_cost(&tree, &result.states, |a, b| steps.cost(a, b)),
+        15.0
+    );
+
+    // Each complete column against every possible reconstruction
+    for (site, &score) in result.sites.iter().enumerate() {
+        let column: Vec<char> = tree
+            .leaves()
+            .into_iter()
+            .map(|leaf| {
+                let name = tree.node(leaf).name.as_deref().unwrap();
+                let row = NAMES.iter().position(|&n| n == name).unwrap();
+                ROWS[row].chars().nth(site).unwrap()
+            })
+            .collect();
+        if column.iter().all(|c| c.is_ascii_alphabetic()) {
+            assert_eq!(score, brute_force(&tree, &column, &steps), "site {site}");
+        }
+    }
+}
+
+#[test]
+fn test_asymmetric_steps() {
+    // Gains of state 1 are cheap, losses expensive (Dollo-like)
+    let mut costs = Arr2D::full(0.0, 2, 2);
+    costs[0][1] = 1.0;
+    costs[1][0] = 10.0;
+    let steps = StepMatrix::new("01", costs);
+    let tree = tree("((a,b),(c,d));");
+    let result = sankoff(&tree, &["a", "b", "c", "d"], &["1", "1", "0", "1"], &steps);
+    // Two gains beat one gain and one loss
+    assert_eq!(result.total, 2.0);
+    assert_eq!(result.states[tree.root()], "0");
+}
+
+#[test]
+#[should_panic(expected = "leaf 'e' has no sequence")]
+fn test_missing_leaf() {
+    fitch(&tree("((a,b),(c,e));"), &["a", "b", "c"], &["A", "C", "G"]);
+}
+
+#[test]
+#[should_panic(expected = "'N' is not a state of the step matrix")]
+fn test_unknown_state() {
+    sankoff(
+        &tree("(a,b);"),
+        &["a", "b"],
+        &["A", "N"],
+        &StepMatrix::unit("ACGT"),
+    );
+}
@@ -0,0 +1,107 @@
+use goombay_rs::align::{LocalAlignmentMatrix, LocalSearch, SmithWaterman};
+use goombay_rs::scoring::GeneralScoring;
+
+fn database() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("weak", "TTTTTTACGTT"),
+        ("exact", "GGGACTGACTGGGG"),
+        ("none", "CCCCCCCC"),
+        ("partial", "AAACTGAAAA"),
+        ("exact_copy", "ACTGACTG"),
+    ]
+}
+
+#[test]
+fn test_ranked_hits() {
+    let search = LocalSearch::default().top_n(3);
+    let hits = search.search("ACTGACTG", database());
+
+    let ids: Vec<&str> = hits.iter().map(|hit| hit.id).collect();
+    // Equal scores keep database order
+    assert_eq!(ids, vec!["exact", "exact_copy", "partial"]);
+    assert_eq!(hits[0].score, 16);
+    assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+}
+
+#[test]
+fn test_scores_match_smith_waterman() {
+    let hits = LocalSearch::default()
+        .top_n(10)
+        .search("ACTGACTG", database());
+    for hit in hits {
+        let subject = database()
+            .into_iter()
+            .find(|(id, _)| *id == hit.id)
+            .unwrap()
+            .1;
+        let sw = SmithWaterman::compute("ACTGACTG", subject);
+        assert_eq!(hit.score, sw.similarity());
+    }
+}
+
+#[test]
+fn test_min_score_cutoff() {
+    let search = LocalSearch::default().min_score(8);
+    let hits = search.search("ACTGACTG", database());
+    assert!(hits.iter().all(|hit| hit.score >= 8));
+    assert!(!hits.iter().any(|hit| hit.id == "none"));
+}
+
+#[test]
+fn test_coordinates_and_alignment() {
+    let search = LocalSearch::default().top_n(1).with_alignment(true);
+    let hits = search.search("TTACTGTT", vec![(7, "GGGGACTGGGG")]);
+    let hit = &hits[0];
+
+    assert_eq!(hit.id, 7);
+    assert_eq!((hit.query_start, hit.query_end), (2, 6));
+    assert_eq!((hit.subject_start, hit.subject_end), (4, 8));
+    assert_eq!(hit.alignment.as_deref(), Some("ACTG\nACTG"));
+    assert_eq!(&"GGGGACTGGGG"[hit.subject_start..hit.subject_end], "ACTG");
+}
+
+#[test]
+fn test_alignment_is_optional() {
+    let hits = LocalSearch::default().search("ACTG", vec![("a", "ACTG")]);
+    assert_eq!(hits[0].alignment, None);
+}
+
+#[test]
+fn test_custom_scores() {
+    let scores = GeneralScoring {
+        identity: 3,
+        mismatch: 3,
+        gap: 2,
+    };
+    let search = LocalSearch::set_scores(&scores).with_alignment(true);
+    let hits = search.search("ACGT", vec![("a", "ACG")]);
+    let sw = SmithWaterman::set_scores(&scores).calculate_matrix("ACGT", "ACG");
+    assert_eq!(hits[0].score, sw.similarity());
+    assert_eq!(hits[0].alignment.as_deref(), Some("ACG\nACG"));
+}
+
+#[test]
+fn test_streaming_scan() {
+    // Records are generated lazily and never collected
+    let records = (0..1000).map(|i| {
+        let sequence = if i % 250 == 0 { "ACTGACTG" } else { "CCCCCCCC" };
+        (i, sequence.to_string())
+    });
+    let search = LocalSearch::default().min_score(10);
+    let ids: Vec<usize> = search.scan("ACTGACTG", records).map(|hit| hit.id).collect();
+    assert_eq!(ids, vec![0, 250, 500, 750]);
+}
+
+#[test]
+fn test_top_n_bounds_results() {
+    let records = (0..100).map(|i| (i, "ACTG"));
+    let hits = LocalSearch::default().top_n(5).search("ACTG", records);
+    let ids: Vec<i32> = hits.iter().map(|hit| hit.id).collect();
+    assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+    assert!(
+        LocalSearch::default()
+            .top_n(0)
+            .search("ACTG", vec![(0, "ACTG")])
+            .is_empty()
+    );
+}
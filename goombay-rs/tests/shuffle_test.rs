@@ -0,0 +1,132 @@
+use goombay_rs::align::{
+    GlobalAlignmentMatrix, MonteCarlo, NeedlemanWunsch, SeededRng, Shuffle, shuffle,
+};
+use goombay_rs::scoring::NucleotideScoring;
+use std::collections::{BTreeMap, BTreeSet};
+
+const SEQUENCE: &str = "ATGGCGTACGATTTACGGCATCGATCGGGCTAACGTTAGCATCGACGTACGATCGA";
+
+fn counts(sequence: &str, k: usize) -> BTreeMap<String, usize> {
+    let chars: Vec<char> = sequence.chars().collect();
+    let mut counts = BTreeMap::new();
+    for window in chars.windows(k) {
+        *counts.entry(window.iter().collect()).or_default() += 1;
+    }
+    counts
+}
+
+#[test]
+fn test_seeded_rng() {
+    let mut a = SeededRng::new(42);
+    let mut b = SeededRng::new(42);
+    let mut c = SeededRng::new(43);
+    let first: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+    assert_eq!(first, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+    assert_ne!(first, (0..5).map(|_| c.next_u64()).collect::<Vec<_>>());
+
+    let mut rng = SeededRng::new(7);
+    let mut seen = [0; 6];
+    for _ in 0..6000 {
+        seen[rng.below(6)] += 1;
+    }
+    assert!(seen.iter().all(|&n| n > 800 && n < 1200));
+}
+
+#[test]
+fn test_mononucleotide_shuffle() {
+    let mut rng = SeededRng::new(1);
+    let shuffled = shuffle(SEQUENCE, Shuffle::Mononucleotide, &mut rng);
+    assert_ne!(shuffled, SEQUENCE);
+    assert_eq!(counts(&shuffled, 1), counts(SEQUENCE, 1));
+}
+
+#[test]
+fn test_dinucleotide_shuffle() {
+    let mut rng = SeededRng::new(1);
+    let mut distinct = BTreeSet::new();
+    for _ in 0..20 {
+        let shuffled = shuffle(SEQUENCE, Shuffle::Dinucleotide, &mut rng);
+        assert_eq!(counts(&shuffled, 2), counts(SEQUENCE, 2));
+        assert_eq!(shuffled.chars().next(), SEQUENCE.chars().next());
+        assert_eq!(shuffled.chars().last(), SEQUENCE.chars().last());
+        distinct.insert(shuffled);
+    }
+    assert!(distinct.len() > 15);
+
+    // Same seed, same shuffle
+    let a = shuffle(SEQUENCE, Shuffle::Dinucleotide, &mut SeededRng::new(9));
+    let b = shuffle(SEQUENCE, Shuffle::Dinucleotide, &mut SeededRng::new(9));
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_shuffle_short_sequences() {
+    let mut rng = SeededRng::new(0);
+    for kind in [Shuffle::Mononucleotide, Shuffle::Dinucleotide] {
+        assert_eq!(shuffle("", kind, &mut rng), "");
+        assert_eq!(shuffle("A", kind, &mut rng), "A");
+        assert_eq!(shuffle("AAAA", kind, &mut rng), "AAAA");
+    }
+    // AC has only one dinucleotide arrangement
+    assert_eq!(shuffle("AC", Shuffle::Dinucleotide, &mut rng), "AC");
+}
+
+#[test]
+fn test_global_score_significance() {
+    let query = "ATGGCGTACGATTTACGGCATCGATCGGGCTAACG";
+    let subject = "ATGGCGTACGTTTTACGGCATCGATCGGCTAACG";
+    let monte_carlo = MonteCarlo::default().shuffles(200).seed(5);
+    let result = monte_carlo.significance(query, subject, |q, s| {
+        NeedlemanWunsch::compute(q, s).similarity()
+    });
+
+    assert_eq!(
+        result.score,
+        NeedlemanWunsch::compute(query, subject).similarity()
+    );
+    assert!(result.z_score > 5.0);
+    assert_eq!(result.p_value, 1.0 / 201.0);
+    assert!(result.mean < result.score as f64);
+
+    // The seed fixes the shuffles
+    let again = monte_carlo.significance(query, subject, |q, s| {
+        NeedlemanWunsch::compute(q, s).similarity()
+    });
+    assert_eq!(result, again);
+    let reseeded = monte_carlo.seed(6).significance(query, subject, |q, s| {
+        NeedlemanWunsch::compute(q, s).similarity()
+    });
+    assert_ne!(result.mean, reseeded.mean);
+}
+
+#[test]
+fn test_custom_scoring_significance() {
+    // An unrelated subject is not significant under either shuffle
+    let aligner = NeedlemanWunsch::set_scores(&NucleotideScoring::default());
+    let query = "ACGTTGCAACGTAGCTAGCTAGGATCCA";
+    let subject = "TTGACCGATGCAAGCTTCGGATCCAGTA";
+    for kind in [Shuffle::Mononucleotide, Shuffle::Dinucleotide] {
+        let result = MonteCarlo::default()
+            .shuffles(100)
+            .shuffle(kind)
+            .significance(query, subject, |q, s| {
+                aligner.calculate_matrix(q, s).similarity()
+            });
+        assert!(result.z_score < 3.0);
+        assert!(result.p_value > 0.01);
+        assert!(result.std_dev > 0.0);
+    }
+}
+
+#[test]
+fn test_constant_scores() {
+    // Every shuffle of a single-letter subject is the subject itself
+    let result = MonteCarlo::default()
+        .shuffles(10)
+        .significance("AAAA", "AAAA", |q, s| {
+            NeedlemanWunsch::compute(q, s).similarity()
+        });
+    assert_eq!(result.std_dev, 0.0);
+    assert_eq!(result.z_score, 0.0);
+    assert_eq!(result.p_value, 1.0);
+}
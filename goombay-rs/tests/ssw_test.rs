@@ -0,0 +1,109 @@
+use goombay_rs::align::{
+    LocalAlignmentMatrix, SeededRng, SimdEngine, SmithWaterman, StripedSmithWaterman,
+};
+use goombay_rs::scoring::GeneralScoring;
+
+mod common;
+use common::{DNA, random_sequence};
+
+const ENGINES: [SimdEngine; 3] = [SimdEngine::Avx2, SimdEngine::Sse2, SimdEngine::Portable];
+
+#[test]
+fn test_identical_sequences() {
+    let ssw = StripedSmithWaterman::default();
+    assert_eq!(ssw.score("ACTG", "ACTG"), 8);
+}
+
+#[test]
+fn test_matches_scalar_examples() {
+    let pairs = [
+        ("AAACTGAA", "TTTACTGTT"),
+        ("TGTTACGG", "GGTTGACTA"),
+        ("AAAA", "TTTT"),
+        ("ACTGNNNACTG", "ACTG"),
+        ("TGTTACGGAAAAAAAAAAGTGAC", "GGTTGACTA"),
+    ];
+    for engine in ENGINES {
+        let ssw = StripedSmithWaterman::default().engine(engine);
+        for (query, subject) in pairs {
+            let expected = SmithWaterman::compute(query, subject).similarity();
+            assert_eq!(ssw.score(query, subject), expected, "{query} vs {subject}");
+        }
+    }
+}
+
+#[test]
+fn test_empty_sequences() {
+    let ssw = StripedSmithWaterman::default();
+    assert_eq!(ssw.score("", "ACTG"), 0);
+    assert_eq!(ssw.score("ACTG", ""), 0);
+}
+
+#[test]
+fn test_random_against_scalar() {
+    let score_sets = [
+        GeneralScoring {
+            identity: 2,
+            mismatch: 1,
+            gap: 2,
+        },
+        // Mismatches cost more than two gaps, exercising the lazy-F pass
+        GeneralScoring {
+            identity: 3,
+            mismatch: 9,
+            gap: 1,
+        },
+    ];
+    let mut rng = SeededRng::new(42);
+    for scores in &score_sets {
+        let sw = SmithWaterman::set_scores(scores);
+        for engine in ENGINES {
+            let ssw = StripedSmithWaterman::set_scores(scores).engine(engine);
+            for _ in 0..40 {
+                let query_len = 1 + rng.below(70);
+                let query = random_sequence(&mut rng, query_len, &DNA);
+                let subject = random_sequence(&mut rng, 1 + query_len / 2, &DNA);
+                let expected = sw.calculate_matrix(&query, &subject).similarity();
+                assert_eq!(
+                    ssw.score(&query, &subject),
+                    expected,
+                    "{query} vs {subject}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_profile_reuse() {
+    let ssw = StripedSmithWaterman::default();
+    let mut profile = ssw.profile("MKTAYIAKQRQISFVKSHFSRQ");
+    let database = [
+        "MKTAYIAKQR",
+        "QISFVKSHFS",
+        "WWWWWWWWWW",
+        "mktayiakqrqisfvkshfsrq",
+    ];
+    for subject in database {
+        let expected = SmithWaterman::compute("MKTAYIAKQRQISFVKSHFSRQ", subject).similarity();
+        assert_eq!(profile.score(subject), expected);
+    }
+}
+
+#[test]
+fn test_overflow_into_wider_lanes() {
+    // 200 matches score 400, beyond 8-bit lanes
+    let medium = "ACGT".repeat(50);
+    // Four matches at 20000 each are beyond 16-bit lanes
+    let large_scores = GeneralScoring {
+        identity: 20000,
+        mismatch: 1,
+        gap: 1,
+    };
+    for engine in ENGINES {
+        let ssw = StripedSmithWaterman::default().engine(engine);
+        assert_eq!(ssw.score(&medium, &medium), 400);
+        let ssw = StripedSmithWaterman::set_scores(&large_scores).engine(engine);
+        assert_eq!(ssw.score("ACGT", "TACGTA"), 80000);
+    }
+}
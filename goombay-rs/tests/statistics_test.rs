@@ -0,0 +1,149 @@
+use goombay_rs::align::{KarlinAltschul, LocalAlignmentMatrix, SmithWaterman};
+use goombay_rs::scoring::{GeneralScoring, NucleotideScoring};
+
+const UNIFORM_DNA: [(char, f64); 4] = [('A', 0.25), ('C', 0.25), ('G', 0.25), ('T', 0.25)];
+
+fn scoring(identity: usize, mismatch: usize, gap: usize) -> GeneralScoring {
+    GeneralScoring {
+        identity,
+        mismatch,
+        gap,
+    }
+}
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() < tolerance,
+        "{actual} is not within {tolerance} of {expected}"
+    );
+}
+
+#[test]
+fn test_ungapped_matches_blast() {
+    // Ungapped parameters reported by BLAST for uniform nucleotides, which
+    // are rounded to three significant figures
+    for (identity, mismatch, lambda, k, h) in [
+        (1, 3, 1.374, 0.711, 1.31),
+        (1, 2, 1.33, 0.621, 1.12),
+        (2, 3, 0.634, 0.408, 0.912),
+    ] {
+        let params =
+            KarlinAltschul::ungapped(&scoring(identity, mismatch, 2), &UNIFORM_DNA).unwrap();
+        assert_close(params.lambda, lambda, 0.005);
+        assert_close(params.k, k, 0.0005);
+        assert_close(params.h, h, 0.005);
+    }
+}
+
+#[test]
+fn test_ungapped_closed_form() {
+    // For +1/-1 scores lambda = ln(q / p) and K = (q - p)^2 / q, with p the
+    // chance of a match and q of a mismatch
+    let params = KarlinAltschul::ungapped(&scoring(1, 1, 1), &UNIFORM_DNA).unwrap();
+    assert_close(params.lambda, 3.0_f64.ln(), 1e-9);
+    assert_close(params.k, 0.5 * 0.5 / 0.75, 1e-9);
+
+    // Scaling every score scales lambda but leaves K and H alone
+    let scaled = KarlinAltschul::ungapped(&scoring(3, 3, 3), &UNIFORM_DNA).unwrap();
+    assert_close(scaled.lambda, params.lambda / 3.0, 1e-9);
+    assert_close(scaled.k, params.k, 1e-9);
+    assert_close(scaled.h, params.h, 1e-9);
+}
+
+#[test]
+fn test_ungapped_background() {
+    // Frequencies are normalised and case does not matter
+    let counts = [('a', 1.0), ('c', 1.0), ('g', 1.0), ('t', 1.0)];
+    let from_counts = KarlinAltschul::ungapped(&scoring(1, 2, 2), &counts).unwrap();
+    let uniform = KarlinAltschul::ungapped(&scoring(1, 2, 2), &UNIFORM_DNA).unwrap();
+    assert_close(from_counts.lambda, uniform.lambda, 1e-12);
+    assert_close(from_counts.k, uniform.k, 1e-12);
+
+    // A GC-rich background matches more often by chance, lowering lambda
+    let gc_rich = [('A', 0.1), ('C', 0.4), ('G', 0.4), ('T', 0.1)];
+    let skewed = KarlinAltschul::ungapped(&scoring(1, 2, 2), &gc_rich).unwrap();
+    assert!(skewed.lambda < uniform.lambda);
+
+    // N scores 0 against everything under NucleotideScoring
+    let with_n = [('A', 0.2), ('C', 0.2), ('G', 0.2), ('T', 0.2), ('N', 0.2)];
+    assert!(KarlinAltschul::ungapped(&NucleotideScoring::default(), &with_n).is_some());
+}
+
+#[test]
+fn test_ungapped_requires_negative_drift() {
+    // Expected score 0.25 * 3 - 0.75 * 1 = 0
+    assert!(KarlinAltschul::ungapped(&scoring(3, 1, 1), &UNIFORM_DNA).is_none());
+    // No positive score at all
+    assert!(KarlinAltschul::ungapped(&scoring(0, 1, 1), &UNIFORM_DNA).is_none());
+}
+
+#[test]
+fn test_gapped_tables() {
+    let blastn = KarlinAltschul::gapped(2, 3, 5, 2).unwrap();
+    assert_eq!(blastn, KarlinAltschul::new(0.625, 0.41, 0.78));
+    assert!(KarlinAltschul::gapped(2, 3, 7, 7).is_none());
+
+    // Linear gaps of 2 per residue are gap_open 0, gap_extend 2
+    let linear = KarlinAltschul::for_scoring(&scoring(1, 3, 2)).unwrap();
+    assert_eq!(linear, KarlinAltschul::gapped(1, 3, 0, 2).unwrap());
+    let linear = KarlinAltschul::for_scoring(&scoring(2, 3, 4)).unwrap();
+    assert_eq!(linear, KarlinAltschul::new(0.55, 0.21, 0.46));
+    let linear = KarlinAltschul::for_scoring(&scoring(1, 2, 2)).unwrap();
+    assert_eq!(linear, KarlinAltschul::gapped(1, 2, 0, 2).unwrap());
+
+    // A multiple of a table row scales lambda and keeps K and H
+    let doubled = KarlinAltschul::for_scoring(&scoring(2, 6, 4)).unwrap();
+    assert_close(doubled.lambda, 1.25 / 2.0, 1e-12);
+    assert_eq!((doubled.k, doubled.h), (0.42, 0.83));
+
+    // No table covers a mismatch penalty below the reward
+    assert!(KarlinAltschul::for_scoring(&scoring(2, 1, 2)).is_none());
+}
+
+#[test]
+fn test_gapped_protein_tables() {
+    let blastp = KarlinAltschul::gapped_protein("BLOSUM62", 11, 1).unwrap();
+    assert_eq!(blastp, KarlinAltschul::new(0.267, 0.041, 0.14));
+    assert_eq!(
+        KarlinAltschul::gapped_protein("blosum62", 10, 1),
+        Some(KarlinAltschul::new(0.243, 0.024, 0.10))
+    );
+    assert_eq!(
+        KarlinAltschul::gapped_protein("PAM30", 9, 1),
+        Some(KarlinAltschul::new(0.294, 0.11, 0.61))
+    );
+    assert!(KarlinAltschul::gapped_protein("BLOSUM62", 20, 20).is_none());
+    assert!(KarlinAltschul::gapped_protein("GONNET", 11, 1).is_none());
+}
+
+#[test]
+fn test_bit_score_and_e_value() {
+    let params = KarlinAltschul::new(0.625, 0.41, 0.78);
+    let bits = params.bit_score(40);
+    assert_close(bits, (0.625 * 40.0 - 0.41_f64.ln()) / 2.0_f64.ln(), 1e-12);
+
+    // E = m * n * 2^-bits, and the p-value follows 1 - exp(-E)
+    let e = params.e_value(40, 1000, 1_000_000);
+    assert_close(e, 1000.0 * 1_000_000.0 * 2.0_f64.powf(-bits), 1e-9 * e);
+    assert_close(params.p_value(40, 1000, 1_000_000), 1.0 - (-e).exp(), 1e-12);
+    assert!(params.p_value(40, 1000, 1_000_000) <= e);
+
+    // Higher scores and smaller databases are more significant
+    assert!(params.e_value(50, 1000, 1_000_000) < e);
+    assert!(params.e_value(40, 1000, 1000) < e);
+}
+
+#[test]
+fn test_local_hit() {
+    let scores = scoring(1, 3, 2);
+    let params = KarlinAltschul::for_scoring(&scores).unwrap();
+    let model = SmithWaterman::set_scores(&scores)
+        .calculate_matrix("TTGATTACAGATTACATT", "CCCGATTACAGATTACACC");
+    let hit = params.hit(&model, 18, 19);
+
+    assert_eq!(hit.score, 14);
+    assert_close(hit.bit_score, params.bit_score(14), 1e-12);
+    assert_close(hit.e_value, params.e_value(14, 18, 19), 1e-12);
+    assert!(hit.e_value < 1e-3);
+    assert_eq!(hit, params.significance(14, 18, 19));
+}
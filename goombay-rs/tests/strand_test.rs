@@ -0,0 +1,78 @@
+use goombay_rs::align::{
+    BothStrands, NeedlemanWunsch, SmithWaterman, Strand, complement, reverse_complement,
+};
+
+#[test]
+fn test_reverse_complement_iupac() {
+    assert_eq!(reverse_complement("ACGT"), "ACGT");
+    assert_eq!(reverse_complement("AACGTT"), "AACGTT");
+    assert_eq!(reverse_complement("GATTACA"), "TGTAATC");
+    assert_eq!(reverse_complement("RYKMBVDHSWN"), "NWSDHBVKMRY");
+    assert_eq!(reverse_complement("acgU-n"), "n-Acgt");
+    assert_eq!(complement('*'), '*');
+
+    let sequence = "ACGTRYKMBVDHSWNacgtrykmbvdhswn";
+    assert_eq!(reverse_complement(&reverse_complement(sequence)), sequence);
+}
+
+#[test]
+fn test_local_forward_strand() {
+    let subject = "TTTTGATTACAGGGG";
+    let hit = BothStrands::default().local("GATTACA", subject);
+
+    assert_eq!(hit.strand, Strand::Forward);
+    assert_eq!(hit.strand.symbol(), '+');
+    assert_eq!(hit.score, 14);
+    assert_eq!((hit.query_start, hit.query_end), (0, 7));
+    assert_eq!((hit.subject_start, hit.subject_end), (4, 11));
+}
+
+#[test]
+fn test_local_reverse_strand() {
+    // The read is the reverse complement of subject[4..11], plus a tail that
+    // matches nothing
+    let subject = "TTTTGATTACAGGGG";
+    let read = format!("{}CCC", reverse_complement("GATTACA"));
+    let hit = BothStrands::default().local(&read, subject);
+
+    assert_eq!(hit.strand, Strand::Reverse);
+    assert_eq!(hit.strand.symbol(), '-');
+    assert_eq!(hit.score, 14);
+    assert_eq!((hit.subject_start, hit.subject_end), (4, 11));
+    // Query coordinates are on the read as given
+    assert_eq!((hit.query_start, hit.query_end), (0, 7));
+    assert_eq!(hit.model.align()[0], "GATTACA\nGATTACA");
+
+    let forward = SmithWaterman::compute(&read, subject).similarity();
+    assert!(forward < hit.score);
+}
+
+#[test]
+fn test_semi_global_both_strands() {
+    let subject = "CCCCCCACGTTGCATTTTTT";
+    let forward = BothStrands::default().semi_global("ACGTTGCA", subject);
+    assert_eq!(forward.strand, Strand::Forward);
+    assert_eq!((forward.subject_start, forward.subject_end), (6, 14));
+    assert_eq!(forward.score, 16);
+
+    let reverse = BothStrands::default().semi_global("AATGCAACGT", subject);
+    assert_eq!(reverse.strand, Strand::Reverse);
+    assert_eq!((reverse.query_start, reverse.query_end), (0, 10));
+    assert_eq!((reverse.subject_start, reverse.subject_end), (6, 16));
+    assert_eq!(reverse.score, 20);
+    // The model's global score over the window is the semi-global score
+    assert_eq!(reverse.model.similarity(), reverse.score);
+    assert_eq!(
+        NeedlemanWunsch::compute("ACGTTGCATT", "ACGTTGCATT").similarity(),
+        reverse.score
+    );
+}
+
+#[test]
+fn test_semi_global_keeps_whole_query() {
+    // End gaps in the query are not free, so the mismatching ends still count
+    let hit = BothStrands::default().semi_global("GACGTTGCAG", "CCCCCCACGTTGCATTTTTT");
+    assert_eq!(hit.strand, Strand::Forward);
+    assert_eq!(hit.subject_end - hit.subject_start, 10);
+    assert_eq!(hit.score, 14);
+}
@@ -0,0 +1,132 @@
+use goombay_rs::align::{GeneticCode, Strand, TranslatedAligner, reverse_complement};
+
+// MKLVWHEFRD
+const CODING: &str = "ATGAAACTGGTTTGGCATGAATTTCGTGAT";
+
+#[test]
+fn test_standard_code() {
+    let code = GeneticCode::standard();
+    assert_eq!(code.translate(CODING), "MKLVWHEFRD");
+    assert_eq!(code.translate("atgtaatgagg"), "M**");
+    // Fourfold degenerate site, and an ambiguous one that is not
+    assert_eq!(code.codon(&['C', 'T', 'N']), 'L');
+    assert_eq!(code.codon(&['A', 'T', 'N']), 'X');
+    assert_eq!(code.codon(&['A', 'U', 'G']), 'M');
+    assert_eq!(code.codon(&['A', '-', 'G']), 'X');
+}
+
+#[test]
+fn test_other_tables() {
+    let mito = GeneticCode::ncbi(2).unwrap();
+    assert_eq!(mito.translate("TGAAGAATA"), "W*M");
+    assert_eq!(GeneticCode::ncbi(11), Some(GeneticCode::standard()));
+    assert_eq!(GeneticCode::ncbi(99), None);
+
+    let custom = GeneticCode::from_table(&"G".repeat(64));
+    assert_eq!(custom.translate("ATGTAA"), "GG");
+}
+
+#[test]
+#[should_panic]
+fn test_short_table() {
+    GeneticCode::from_table("FFLL");
+}
+
+#[test]
+fn test_six_frames() {
+    let frames = GeneticCode::standard().six_frames("ATGAAACTGG");
+    let labels: Vec<i8> = frames.iter().map(|(frame, _)| *frame).collect();
+    assert_eq!(labels, vec![1, 2, 3, -1, -2, -3]);
+    assert_eq!(frames[0].1, "MKL");
+    assert_eq!(frames[1].1, "*NW");
+    assert_eq!(frames[3].1, GeneticCode::standard().translate("CCAGTTTCAT"));
+}
+
+#[test]
+fn test_forward_frame() {
+    let dna = format!("GG{CODING}CC");
+    let model = TranslatedAligner::default().calculate_matrix("MKLVWHEFRD", &dna);
+
+    assert_eq!(model.similarity(), 20);
+    assert_eq!(model.strand, Strand::Forward);
+    assert_eq!(model.frame, 3);
+    assert_eq!((model.query_start, model.query_end), (0, 10));
+    assert_eq!((model.subject_start, model.subject_end), (2, 32));
+    assert_eq!(model.frameshifts, 0);
+    assert_eq!(
+        model.align()[0],
+        format!(" M  K  L  V  W  H  E  F  R  D \n{CODING}")
+    );
+}
+
+#[test]
+fn test_reverse_frame() {
+    let dna = reverse_complement(&format!("GGG{CODING}C"));
+    let model = TranslatedAligner::default().calculate_matrix("MKLVWHEFRD", &dna);
+
+    assert_eq!(model.similarity(), 20);
+    assert_eq!(model.strand, Strand::Reverse);
+    assert_eq!(model.frame, -1);
+    // Forward-strand span of the coding sequence on the given DNA
+    assert_eq!((model.subject_start, model.subject_end), (1, 31));
+
+    let forward_only = TranslatedAligner::default()
+        .both_strands(false)
+        .calculate_matrix("MKLVWHEFRD", &dna);
+    assert_eq!(forward_only.strand, Strand::Forward);
+    assert!(forward_only.similarity() < 20);
+}
+
+#[test]
+fn test_frameshift() {
+    // One extra nucleotide after the fifth codon
+    let dna = format!("{}A{}", &CODING[..15], &CODING[15..]);
+    let model = TranslatedAligner::default().calculate_matrix("MKLVWHEFRD", &dna);
+
+    assert_eq!(model.frameshifts, 1);
+    assert_eq!(model.similarity(), 20 - 4);
+    assert_eq!((model.subject_start, model.subject_end), (0, 31));
+    let alignment = &model.align()[0];
+    let (protein, codons) = alignment.split_once('\n').unwrap();
+    assert_eq!(protein.len(), codons.len());
+    assert_eq!(protein.matches('!').count(), 1);
+    assert_eq!(
+        codons.replace(|c: char| c.is_ascii_lowercase(), "").len(),
+        30
+    );
+
+    // Too expensive to shift: only the longer half is aligned
+    let strict = TranslatedAligner::default()
+        .frameshift(100)
+        .calculate_matrix("MKLVWHEFRD", &dna);
+    assert_eq!(strict.frameshifts, 0);
+    assert_eq!(strict.similarity(), 10);
+}
+
+#[test]
+fn test_codon_gaps() {
+    // An extra codon in the DNA and a residue missing from it
+    let inserted = format!("{}GCA{}", &CODING[..15], &CODING[15..]);
+    let model = TranslatedAligner::default().calculate_matrix("MKLVWHEFRD", &inserted);
+    assert_eq!(model.similarity(), 20 - 2);
+    assert!(model.align()[0].contains("---"));
+
+    let model = TranslatedAligner::default().calculate_matrix("MKLVWAHEFRD", CODING);
+    assert_eq!(model.similarity(), 20 - 2);
+    let alignment = &model.align()[0];
+    let (protein, codons) = alignment.split_once('\n').unwrap();
+    let gap = codons.find("---").unwrap();
+    assert_eq!(&protein[gap..gap + 3], " A ");
+}
+
+#[test]
+fn test_genetic_code_changes_scores() {
+    // TGA is a stop codon in the standard code but W in vertebrate mitochondria
+    let dna = "ATGTGAAAACTG";
+    let standard = TranslatedAligner::default().calculate_matrix("MWKL", dna);
+    let mito = TranslatedAligner::default()
+        .genetic_code(GeneticCode::ncbi(2).unwrap())
+        .calculate_matrix("MWKL", dna);
+    assert_eq!(mito.similarity(), 8);
+    assert!(standard.similarity() < mito.similarity());
+}
@@ -0,0 +1,173 @@
+use goombay_rs::phylo::{NodeId, Tree};
+
+mod common;
+use common::render;
+
+fn show(tree: &Tree) -> String {
+    render(tree, tree.root())
+}
+
+// ((A:1,B:2)X[90]:0.5,(C:1,D:1)Y:1.5,E:3)
+fn example(rooted: bool) -> Tree {
+    let mut tree = Tree::new(rooted);
+    let root = tree.root();
+    let x = tree.add_child(root, Some("X"), Some(0.5));
+    tree.node_mut(x).support = Some(90.0);
+    tree.add_child(x, Some("A"), Some(1.0));
+    tree.add_child(x, Some("B"), Some(2.0));
+    let y = tree.add_child(root, Some("Y"), Some(1.5));
+    tree.add_child(y, Some("C"), Some(1.0));
+    tree.add_child(y, Some("D"), Some(1.0));
+    tree.add_child(root, Some("E"), Some(3.0));
+    tree
+}
+
+fn names(tree: &Tree, order: Vec<NodeId>) -> Vec<&str> {
+    order
+        .into_iter()
+        .map(|id| tree.node(id).name.as_deref().unwrap_or("*"))
+        .collect()
+}
+
+#[test]
+fn test_construction() {
+    let tree = example(false);
+    assert!(!tree.is_rooted());
+    assert_eq!(tree.len(), 8);
+    assert_eq!(show(&tree), "((A:1,B:2)X[90]:0.5,(C:1,D:1)Y:1.5,E:3)");
+    assert_eq!(tree.leaf_names(), vec!["A", "B", "C", "D", "E"]);
+
+    let x = tree.find("X").unwrap();
+    assert_eq!(tree.node(x).parent(), Some(tree.root()));
+    assert_eq!(tree.node(x).children().len(), 2);
+    assert!(tree.find("Z").is_none());
+    assert_eq!(show(&Tree::default()), "");
+}
+
+#[test]
+fn test_traversals() {
+    let tree = example(true);
+    assert_eq!(
+        names(&tree, tree.preorder()),
+        vec!["*", "X", "A", "B", "Y", "C", "D", "E"]
+    );
+    assert_eq!(
+        names(&tree, tree.postorder()),
+        vec!["A", "B", "X", "C", "D", "Y", "E", "*"]
+    );
+    assert_eq!(
+        names(&tree, tree.levelorder()),
+        vec!["*", "X", "Y", "E", "A", "B", "C", "D"]
+    );
+    let y = tree.find("Y").unwrap();
+    assert_eq!(names(&tree, tree.preorder_from(y)), vec!["Y", "C", "D"]);
+    assert_eq!(names(&tree, tree.postorder_from(y)), vec!["C", "D", "Y"]);
+}
+
+#[test]
+fn test_lca_and_distances() {
+    let tree = example(true);
+    let id = |name| tree.find(name).unwrap();
+
+    assert_eq!(tree.lca(id("A"), id("B")), id("X"));
+    assert_eq!(tree.lca(id("A"), id("X")), id("X"));
+    assert_eq!(tree.lca(id("A"), id("C")), tree.root());
+    assert_eq!(tree.lca_of(&[id("C"), id("D")]), Some(id("Y")));
+    assert_eq!(tree.lca_of(&[id("A"), id("B"), id("E")]), Some(tree.root()));
+    assert_eq!(tree.lca_of(&[]), None);
+
+    assert_eq!(tree.depth(id("B")), 2.5);
+    assert_eq!(tree.distance(id("A"), id("B")), 3.0);
+    assert_eq!(tree.distance(id("A"), id("C")), 4.0);
+    assert_eq!(tree.distance(id("E"), id("E")), 0.0);
+}
+
+#[test]
+fn test_subtree() {
+    let tree = example(false);
+    let sub = tree.subtree(tree.find("Y").unwrap());
+    assert!(sub.is_rooted());
+    assert_eq!(show(&sub), "(C:1,D:1)Y");
+    assert_eq!(sub.len(), 3);
+}
+
+#[test]
+fn test_prune() {
+    let tree = example(true);
+
+    // B goes, X is left with one child and is merged into A
+    let pruned = tree.prune(&["B"]);
+    assert_eq!(show(&pruned), "(A[90]:1.5,(C:1,D:1)Y:1.5,E:3)");
+
+    // Removing a whole clade removes its internal node
+    let pruned = tree.prune(&["C", "D", "E"]);
+    assert_eq!(show(&pruned), "(A:1,B:2)X");
+
+    // Names that are not leaves are ignored
+    assert_eq!(tree.prune(&["X", "Q"]), tree);
+}
+
+#[test]
+fn test_prune_unrooted() {
+    // The root of an unrooted tree would be left with two branches, so the
+    // root moves into X and the two branches become one
+    let tree = example(false);
+    let pruned = tree.prune(&["E"]);
+    assert!(!pruned.is_rooted());
+    assert_eq!(show(&pruned), "(A:1,B:2,(C:1,D:1)Y[90]:2)X");
+    let id = |name| pruned.find(name).unwrap();
+    assert_eq!(pruned.distance(id("A"), id("C")), 4.0);
+}
+
+#[test]
+#[should_panic(expected = "every leaf")]
+fn test_prune_everything() {
+    example(true).prune(&["A", "B", "C", "D", "E"]);
+}
+
+#[test]
+fn test_reroot_on_branch() {
+    let tree = example(false);
+    let rerooted = tree.reroot(tree.find("E").unwrap());
+    assert!(rerooted.is_rooted());
+    assert_eq!(
+        show(&rerooted),
+        "(E:1.5,((A:1,B:2)X[90]:0.5,(C:1,D:1)Y:1.5):1.5)"
+    );
+
+    // Distances between leaves do not change
+    let id = |tree: &Tree, name| tree.find(name).unwrap();
+    for (a, b) in [("A", "E"), ("B", "C"), ("D", "E")] {
+        assert_eq!(
+            tree.distance(id(&tree, a), id(&tree, b)),
+            rerooted.distance(id(&rerooted, a), id(&rerooted, b))
+        );
+    }
+}
+
+#[test]
+fn test_reroot_moves_support() {
+    // Rooting inside A's branch flips X's branch, and its support goes with
+    // the branch rather than the node
+    let tree = example(true);
+    let rerooted = tree.reroot(tree.find("A").unwrap());
+    assert_eq!(
+        show(&rerooted),
+        "(A:0.5,(B:2,((C:1,D:1)Y:1.5,E:3)[90]:0.5)X:0.5)"
+    );
+}
+
+#[test]
+fn test_root_at_and_unroot() {
+    let tree = example(false);
+    let moved = tree.root_at(tree.find("Y").unwrap());
+    assert!(!moved.is_rooted());
+    assert_eq!(show(&moved), "(C:1,D:1,((A:1,B:2)X[90]:0.5,E:3):1.5)Y");
+
+    // Unrooting removes the bifurcating root added by `reroot`
+    let rooted = tree.reroot(tree.find("E").unwrap());
+    let unrooted = rooted.unroot();
+    assert!(!unrooted.is_rooted());
+    assert_eq!(show(&unrooted), "((A:1,B:2)X[90]:0.5,(C:1,D:1)Y:1.5,E:3)");
+    assert_eq!(unrooted, tree);
+}
@@ -0,0 +1,74 @@
+use goombay_rs::align::wavefront::DEFAULT_TILE_SIZE;
+use goombay_rs::align::{
+    GlobalAlignmentMatrix, LocalAlignmentMatrix, NeedlemanWunsch, SeededRng, SmithWaterman,
+};
+use goombay_rs::scoring::GeneralScoring;
+
+mod common;
+use common::{DNA, random_sequence};
+
+#[test]
+fn test_needleman_wunsch_identical_to_serial() {
+    let mut rng = SeededRng::new(7);
+    let nw = NeedlemanWunsch::default();
+    for (query_len, subject_len) in [(1, 1), (5, 17), (40, 33), (64, 64)] {
+        let query = random_sequence(&mut rng, query_len, &DNA);
+        let subject = random_sequence(&mut rng, subject_len, &DNA);
+        let serial = nw.calculate_matrix(&query, &subject);
+        for tile_size in [1, 3, 8, DEFAULT_TILE_SIZE] {
+            let parallel = nw.calculate_matrix_parallel(&query, &subject, tile_size);
+            assert_eq!(parallel.data.score_matrix(), serial.data.score_matrix());
+            assert_eq!(parallel.data.pointer_matrix(), serial.data.pointer_matrix());
+            assert_eq!(parallel.align(), serial.align());
+            assert_eq!(parallel.similarity(), serial.similarity());
+        }
+    }
+}
+
+#[test]
+fn test_smith_waterman_identical_to_serial() {
+    let mut rng = SeededRng::new(11);
+    let scores = GeneralScoring {
+        identity: 3,
+        mismatch: 2,
+        gap: 2,
+    };
+    let sw = SmithWaterman::set_scores(&scores);
+    for (query_len, subject_len) in [(1, 1), (9, 30), (45, 38), (64, 64)] {
+        let query = random_sequence(&mut rng, query_len, &DNA);
+        let subject = random_sequence(&mut rng, subject_len, &DNA);
+        let serial = sw.calculate_matrix(&query, &subject);
+        for tile_size in [1, 4, 10, DEFAULT_TILE_SIZE] {
+            let parallel = sw.calculate_matrix_parallel(&query, &subject, tile_size);
+            assert_eq!(parallel.data.score_matrix(), serial.data.score_matrix());
+            assert_eq!(parallel.data.pointer_matrix(), serial.data.pointer_matrix());
+            assert_eq!(parallel.start_indices, serial.start_indices);
+            assert_eq!(parallel.max_score, serial.max_score);
+            assert_eq!(
+                parallel.all_alignments(true).align(),
+                serial.all_alignments(true).align()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_repeated_maxima_keep_serial_order() {
+    let sw = SmithWaterman::default();
+    let serial = sw.calculate_matrix("ACTGNNNACTGNNACTG", "ACTGACTG");
+    let parallel = sw.calculate_matrix_parallel("ACTGNNNACTGNNACTG", "ACTGACTG", 2);
+    assert_eq!(parallel.start_indices, serial.start_indices);
+}
+
+#[test]
+fn test_empty_sequences() {
+    let nw = NeedlemanWunsch::default();
+    for (query, subject) in [("", ""), ("ACTG", ""), ("", "ACTG")] {
+        let serial = nw.calculate_matrix(query, subject);
+        let parallel = nw.calculate_matrix_parallel(query, subject, 4);
+        assert_eq!(parallel.data.score_matrix(), serial.data.score_matrix());
+        assert_eq!(parallel.data.pointer_matrix(), serial.data.pointer_matrix());
+    }
+    let sw = SmithWaterman::default();
+    assert_eq!(sw.calculate_matrix_parallel("", "ACTG", 4).similarity(), 0);
+}
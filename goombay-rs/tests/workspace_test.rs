@@ -0,0 +1,99 @@
+use goombay_rs::align::{
+    Aligner, LocalAlignmentMatrix, NeedlemanWunsch, SmithWaterman, WagnerFischer,
+};
+use goombay_rs::scoring::GeneralScoring;
+
+const PAIRS: [(&str, &str); 5] = [
+    ("ACTG", "ACTG"),
+    ("ACGT", "AGT"),
+    ("TGTTACGG", "GGTTGACTA"),
+    ("", "ACTG"),
+    ("AAAA", "TTTT"),
+];
+
+#[test]
+fn test_needleman_wunsch_matches_model() {
+    let mut aligner = Aligner::new(NeedlemanWunsch::default());
+    for (query, subject) in PAIRS {
+        let model = NeedlemanWunsch::compute(query, subject);
+        let view = aligner.calculate_matrix(query, subject);
+        assert_eq!(view.align(), model.align());
+        assert_eq!(view.similarity(), model.similarity());
+        assert_eq!(view.distance(), model.distance());
+        assert_eq!(view.normalized_similarity(), model.normalized_similarity());
+        assert_eq!(view.normalized_distance(), model.normalized_distance());
+        assert_eq!(
+            view.all_alignments(true).align(),
+            model.all_alignments(true).align()
+        );
+    }
+}
+
+#[test]
+fn test_wagner_fischer_matches_model() {
+    let mut aligner = Aligner::new(WagnerFischer::default());
+    for (query, subject) in PAIRS {
+        let model = WagnerFischer::compute(query, subject);
+        let view = aligner.calculate_matrix(query, subject);
+        assert_eq!(view.align(), model.align());
+        assert_eq!(view.distance(), model.distance());
+        assert_eq!(view.normalized_distance(), model.normalized_distance());
+    }
+}
+
+#[test]
+fn test_smith_waterman_matches_model() {
+    let scores = GeneralScoring {
+        identity: 3,
+        mismatch: 3,
+        gap: 2,
+    };
+    let mut aligner = Aligner::new(SmithWaterman::set_scores(&scores));
+    for (query, subject) in PAIRS {
+        let model = SmithWaterman::set_scores(&scores).calculate_matrix(query, subject);
+        let view = aligner.calculate_matrix(query, subject);
+        assert_eq!(view.similarity(), model.similarity());
+        assert_eq!(view.start_indices(), model.start_indices.as_slice());
+        assert_eq!(
+            view.all_alignments(true).align(),
+            model.all_alignments(true).align()
+        );
+    }
+}
+
+#[test]
+fn test_matrices_match_model() {
+    let mut aligner = Aligner::new(NeedlemanWunsch::default());
+    let model = NeedlemanWunsch::compute("TGTTACGG", "GGTTGACTA");
+    let view = aligner.calculate_matrix("TGTTACGG", "GGTTGACTA");
+    assert_eq!(&view.score_matrix().to_arr2d(), model.data.score_matrix());
+    assert_eq!(
+        &view.pointer_matrix().to_arr2d(),
+        model.data.pointer_matrix()
+    );
+}
+
+#[test]
+fn test_buffers_are_reused() {
+    let mut aligner = Aligner::new(NeedlemanWunsch::default());
+    aligner.calculate_matrix("ACTGACTGACTG", "ACTGACTGACTG");
+    let capacity = aligner.workspace().score_matrix().capacity();
+
+    // Smaller pairs fit into the buffers from the first call
+    for (query, subject) in PAIRS {
+        let view = aligner.calculate_matrix(query, subject);
+        assert_eq!(
+            view.score_matrix().shape(),
+            (query.len() + 1, subject.len() + 1)
+        );
+    }
+    assert_eq!(aligner.workspace().score_matrix().capacity(), capacity);
+}
+
+#[test]
+fn test_to_model_outlives_next_call() {
+    let mut aligner = Aligner::new(NeedlemanWunsch::default());
+    let first = aligner.calculate_matrix("ACTG", "ACT").to_model();
+    aligner.calculate_matrix("AAAA", "TTTT");
+    assert_eq!(first.align()[0], "ACTG\nACT-");
+}
@@ -0,0 +1,113 @@
+use goombay_rs::align::{LocalAlignmentMatrix, SmithWaterman, XDrop};
+use goombay_rs::scoring::GeneralScoring;
+
+fn random_sequence(seed: u64, len: usize) -> String {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ['A', 'C', 'G', 'T'][(state >> 33) as usize % 4]
+        })
+        .collect()
+}
+
+#[test]
+fn test_exact_match_extends_both_ways() {
+    let xdrop = XDrop::default();
+    // "ACGTACGTAC" sits at query[3..13] and subject[5..15]
+    let ext = xdrop.extend("TTTACGTACGTACGGG", "CCCCCACGTACGTACAAAA", (7, 9));
+
+    assert_eq!((ext.query_start, ext.query_end), (3, 13));
+    assert_eq!((ext.subject_start, ext.subject_end), (5, 15));
+    assert_eq!(ext.similarity(), 20);
+    assert_eq!(ext.align(), vec!["ACGTACGTAC\nACGTACGTAC".to_string()]);
+}
+
+#[test]
+fn test_extension_through_gap() {
+    let xdrop = XDrop::default();
+    let ext = xdrop.extend("ACGTACGTTTGCATGCA", "ACGTACGTGCATGCA", (0, 0));
+
+    assert_eq!((ext.query_start, ext.query_end), (0, 17));
+    assert_eq!((ext.subject_start, ext.subject_end), (0, 15));
+    assert_eq!(ext.similarity(), 26);
+    let alignment = &ext.align()[0];
+    let lines: Vec<&str> = alignment.lines().collect();
+    assert_eq!(lines[0].replace('-', ""), "ACGTACGTTTGCATGCA");
+    assert_eq!(lines[1].replace('-', ""), "ACGTACGTGCATGCA");
+}
+
+#[test]
+fn test_drop_off_stops_extension() {
+    // After the shared prefix the sequences have nothing in common
+    let query = format!("ACGTACGT{}", "A".repeat(200));
+    let subject = format!("ACGTACGT{}", "C".repeat(200));
+
+    let ext = XDrop::default().extend(&query, &subject, (0, 0));
+    assert_eq!((ext.query_end, ext.subject_end), (8, 8));
+    assert_eq!(ext.similarity(), 16);
+    // Only a thin band past the seed is ever computed
+    assert!(ext.cells_computed < 200);
+
+    // A larger drop-off explores more before giving up, but keeps the best end
+    let wide = XDrop::default().x_drop(40).extend(&query, &subject, (0, 0));
+    assert_eq!(wide.similarity(), 16);
+    assert!(wide.cells_computed > ext.cells_computed);
+}
+
+#[test]
+fn test_start_at_lowest_running_score() {
+    // Running score from the seed: -1 after the first mismatch, up to 3, back
+    // down to exactly 0, then up to 8. The alignment starts after the -1.
+    let (query, subject) = ("CAACCCAAAA", "GAAGGGAAAA");
+    let ext = XDrop::default().extend(query, subject, (0, 0));
+    assert_eq!((ext.query_start, ext.query_end), (1, 10));
+    assert_eq!((ext.subject_start, ext.subject_end), (1, 10));
+    assert_eq!(ext.similarity(), 9);
+    assert_eq!(ext.align(), vec!["AACCCAAAA\nAAGGGAAAA".to_string()]);
+
+    let sw = SmithWaterman::compute(query, subject);
+    assert_eq!(ext.similarity(), sw.similarity());
+    let model = ext.model();
+    assert_eq!(model.similarity(), 9);
+    assert_eq!(model.align(), ext.align());
+}
+
+#[test]
+fn test_no_extension_without_similarity() {
+    let ext = XDrop::default().extend("AAAA", "CCCC", (2, 2));
+    assert_eq!(ext.similarity(), 0);
+    assert_eq!(ext.query_start, ext.query_end);
+    assert_eq!(ext.subject_start, ext.subject_end);
+}
+
+#[test]
+fn test_large_drop_off_matches_smith_waterman() {
+    let scores = GeneralScoring {
+        identity: 2,
+        mismatch: 1,
+        gap: 2,
+    };
+    let xdrop = XDrop::set_scores(&scores).x_drop(1000);
+    let sw = SmithWaterman::set_scores(&scores);
+    for seed in 0..20 {
+        let core = random_sequence(seed, 30);
+        let query = format!("{}{core}", random_sequence(seed + 100, 10));
+        let subject = format!("{}{core}", random_sequence(seed + 200, 15));
+
+        // Anchored at the start of the shared core, the extension can only be
+        // as good as the best local alignment
+        let ext = xdrop.extend(&query, &subject, (10, 15));
+        let best = sw.calculate_matrix(&query, &subject).similarity();
+        assert!(ext.similarity() <= best);
+        assert!(ext.similarity() >= 60);
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_seed_out_of_range() {
+    XDrop::default().extend("ACGT", "ACGT", (5, 0));
+}